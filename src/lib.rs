@@ -1,14 +1,19 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use fs2::FileExt;
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::env;
-use std::ffi::OsStr;
+use std::ffi::{OsStr, OsString};
 use std::fs;
-use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus};
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tempfile::TempDir;
 use walkdir::WalkDir;
 
@@ -22,20 +27,374 @@ struct Asset {
 struct Release {
     assets: Vec<Asset>,
     tag_name: Option<String>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+    /// ISO 8601, so it orders as a string; missing on drafts.
+    #[serde(default)]
+    published_at: Option<String>,
 }
 
-#[derive(Debug)]
+static PATH_WARNED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReleaseInfo {
     pub owner: String,
     pub name: String,
     pub tag: String,
     pub asset_name: String,
     pub asset_url: String,
+    /// Release asset holding the sha256 of the chosen asset, when the release ships one.
+    #[serde(default)]
+    pub checksum_url: Option<String>,
+    /// Cosign `.sig`/`.pem`/`.bundle` siblings of the chosen asset.
+    #[serde(default)]
+    pub signature_urls: Vec<String>,
+}
+
+/// Per-call knobs for resolving, downloading, and installing a release.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Select assets for this architecture (e.g. `aarch64`) instead of the host's.
+    pub arch: Option<String>,
+    /// Prefer assets that name no architecture and stop scoring arch tokens.
+    pub ignore_arch: bool,
+    /// Split the asset download into this many concurrent byte ranges when the server allows it.
+    pub parallel_download: Option<usize>,
+    /// How many repos batch operations resolve and download at once; defaults to
+    /// `DEFAULT_REPO_JOBS` to stay clear of GitHub's rate limits.
+    pub max_parallel_repos: Option<usize>,
+    /// Error instead of guessing when an archive holds several plausible binaries.
+    pub fail_on_multiple_candidates: bool,
+    /// Answer `release_info_with` from the info cache when the entry is younger than this.
+    pub info_max_age: Option<Duration>,
+    /// Read the release from this URL (or `-` for stdin) instead of the GitHub API.
+    pub release_json: Option<String>,
+    /// TOML file of `owner = "token"` pairs, used instead of the config's `owner-tokens`.
+    pub owner_token_map: Option<PathBuf>,
+    /// Resolve releases from this host instead of GitHub; anything but `github.com` is
+    /// taken to be a GitLab instance.
+    pub host: Option<String>,
+    /// Timeout and retry settings from flags; these win over env vars and config.
+    pub network: NetworkConfig,
+    /// Archive-relative path of the binary (e.g. `bin/linux/tool`), bypassing the search heuristics.
+    pub binary_path: Option<String>,
+    /// How to draw download progress on stderr.
+    pub progress: ProgressMode,
+    /// Skip checking the asset against the release's `.sha256` or checksums file.
+    pub no_verify: bool,
+    /// Refuse assets that neither the release's checksum file nor `lockfile` can verify.
+    pub checksum_required: bool,
+    /// Refuse to `run` a binary whose digest differs from this lockfile's entry.
+    pub lockfile: Option<PathBuf>,
+    /// Hash used for digests written to manifests and checked against lockfiles.
+    pub digest_algo: DigestAlgo,
+    /// Keep the download and extraction dirs when preparing a binary fails.
+    pub keep_temp: bool,
+    /// Destination filenames for extracted binaries, keyed by their original names.
+    pub rename_map: BTreeMap<String, String>,
+    /// Resolve "latest" as the highest semver release rather than the one GitHub marks latest.
+    pub semver_latest: bool,
+    /// Match OS/arch tokens in the release tag for assets whose names carry none.
+    pub tag_tokens: bool,
+    /// Without a tag, take the newest published release even when it is a prerelease.
+    pub pre: bool,
+    /// What downloading does when a destination file already exists.
+    pub dest_exists: DestExists,
+    /// Drop every asset not built for this OS before scoring, rather than deprioritizing it.
+    pub only_os: Option<String>,
+    /// Drop every asset not built for this architecture before scoring.
+    pub only_arch: Option<String>,
+    /// Keep, with a warning, binaries whose ELF/Mach-O/PE header names a different
+    /// architecture than expected, rather than failing.
+    pub no_verify_exec_format: bool,
+    /// Fail on such a header mismatch even under `no_verify_exec_format`.
+    pub strict: bool,
+    /// Glob (`*`, `?`) selecting the release asset by name instead of scoring candidates.
+    pub asset: Option<String>,
+    /// Substrings that raise an asset's score; defaults to `[asset] prefer` in `config.toml`.
+    pub asset_prefer: Vec<String>,
+    /// Substrings that lower an asset's score; defaults to `[asset] avoid` in `config.toml`.
+    pub asset_avoid: Vec<String>,
+    /// Filename (extension optional) of the one binary to take from an archive; others are dropped.
+    pub bin: Option<String>,
+    /// Ask on stdin which binary to take when an archive holds several plausible ones.
+    pub prompt_bin: bool,
+    /// Follow symlinks inside extracted archives; links resolving outside the archive are
+    /// still never picked.
+    pub follow_symlinks: bool,
+    /// Let dotfiles in archives count as probable binaries; files under dot-dirs always do.
+    pub include_hidden: bool,
+    /// When the release has no assets, clone the repo and build it with cargo or go.
+    pub build: bool,
+    /// Keep polling for a release that doesn't exist yet, for up to this long.
+    pub wait_for_release: Option<Duration>,
+    /// Install into a versioned store under the data dir and symlink the active version into
+    /// the install dir (copying on Windows).
+    pub store: bool,
+    /// Base dir for download and extraction scratch dirs, instead of the system temp dir.
+    pub temp_dir: Option<PathBuf>,
+    /// Reuse assets from the download cache under the data dir, and add new downloads to it.
+    pub cache: bool,
+    /// Download the asset even when it is cached, replacing the cached copy.
+    pub refresh: bool,
+    /// Copy config files shipped in the archive into this dir, never replacing existing ones.
+    pub install_config: Option<PathBuf>,
+    /// Glob over archive-relative paths picking the files `install_config` copies, instead of
+    /// `DEFAULT_CONFIG_GLOBS`.
+    pub config_glob: Option<String>,
+    /// Copy man pages shipped in the archive into this dir's `manN` sections; without it they
+    /// are left alone.
+    pub man_dir: Option<PathBuf>,
+    /// Ignore `YOINK*` environment variables, config files, and caches so that only
+    /// explicit arguments and built-in defaults affect behavior.
+    pub isolated: bool,
+}
+
+impl Options {
+    /// Reads a yoink-specific environment variable, unless `isolated`.
+    fn env(&self, key: &str) -> Option<String> {
+        if self.isolated {
+            return None;
+        }
+        env::var(key).ok()
+    }
+}
+
+/// HTTP timeout and retry settings, merged from config `[network]`, `YOINK_*` env vars,
+/// and flags, in increasing precedence.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct NetworkConfig {
+    /// Per-request timeout (default 120s). Config takes seconds or a duration like `"500ms"`.
+    #[serde(default, deserialize_with = "deserialize_timeout")]
+    pub timeout: Option<Duration>,
+    /// Retries for connection errors and 5xx responses (default 0).
+    pub retries: Option<u32>,
+    /// Retries for 404s, for releases still propagating to the API and CDN (default 0).
+    pub retry_404: Option<u32>,
+}
+
+impl NetworkConfig {
+    /// Fills unset fields from `fallback`.
+    fn or(self, fallback: NetworkConfig) -> NetworkConfig {
+        NetworkConfig {
+            timeout: self.timeout.or(fallback.timeout),
+            retries: self.retries.or(fallback.retries),
+            retry_404: self.retry_404.or(fallback.retry_404),
+        }
+    }
+
+    fn from_env(options: &Options) -> Result<NetworkConfig> {
+        let count = |key: &str| -> Result<Option<u32>> {
+            options
+                .env(key)
+                .map(|value| {
+                    value
+                        .parse()
+                        .with_context(|| format!("{key} expects a number, got {value}"))
+                })
+                .transpose()
+        };
+        Ok(NetworkConfig {
+            timeout: options
+                .env("YOINK_TIMEOUT")
+                .map(|value| parse_duration(&value))
+                .transpose()
+                .context("YOINK_TIMEOUT")?,
+            retries: count("YOINK_RETRIES")?,
+            retry_404: count("YOINK_RETRY_404")?,
+        })
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout.unwrap_or(Duration::from_secs(120))
+    }
+}
+
+fn deserialize_timeout<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timeout {
+        Seconds(u64),
+        Text(String),
+    }
+    match Option::<Timeout>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Timeout::Seconds(secs)) => Ok(Some(Duration::from_secs(secs))),
+        Some(Timeout::Text(text)) => parse_duration(&text)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+fn network_config(options: &Options) -> Result<NetworkConfig> {
+    Ok(options
+        .network
+        .clone()
+        .or(NetworkConfig::from_env(options)?)
+        .or(load_config(options)?.network))
+}
+
+/// Download progress rendering on stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgressMode {
+    /// Print nothing.
+    #[default]
+    None,
+    /// Redraw a one-line bar in place, for interactive terminals.
+    Bar,
+    /// Append a dot per chunk, for CI logs and dumb terminals.
+    Dots,
+}
+
+impl ProgressMode {
+    /// `Bar` when stderr is a terminal, otherwise `Dots`.
+    pub fn auto() -> Self {
+        if io::stderr().is_terminal() {
+            Self::Bar
+        } else {
+            Self::Dots
+        }
+    }
+}
+
+impl std::str::FromStr for ProgressMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "none" => Ok(Self::None),
+            "bar" => Ok(Self::Bar),
+            "dots" => Ok(Self::Dots),
+            _ => bail!("unknown progress mode {value}; expected none, bar, or dots"),
+        }
+    }
+}
+
+/// Why a public yoink function failed. Each variant carries the full error for display; match
+/// on the variant to decide whether to retry.
+#[non_exhaustive]
+pub enum YoinkError {
+    /// The repo, release, or tag doesn't exist.
+    NotFound(anyhow::Error),
+    /// The release has no asset matching this platform or `--asset`.
+    NoAsset(anyhow::Error),
+    /// A request failed in transit or with a server error.
+    Network(anyhow::Error),
+    /// GitHub refused the request for exceeding its rate limit.
+    RateLimited(anyhow::Error),
+    /// The asset couldn't be unpacked, or no binary was found in it.
+    Extract(anyhow::Error),
+    /// Reading or writing a local file failed.
+    Io(anyhow::Error),
+    /// Anything else, such as invalid input or a missing install.
+    Other(anyhow::Error),
+}
+
+impl YoinkError {
+    fn inner(&self) -> &anyhow::Error {
+        match self {
+            Self::NotFound(err)
+            | Self::NoAsset(err)
+            | Self::Network(err)
+            | Self::RateLimited(err)
+            | Self::Extract(err)
+            | Self::Io(err)
+            | Self::Other(err) => err,
+        }
+    }
+}
+
+impl From<anyhow::Error> for YoinkError {
+    fn from(err: anyhow::Error) -> Self {
+        type Kind = fn(anyhow::Error) -> YoinkError;
+        let kind = err.chain().find_map(|cause| -> Option<Kind> {
+            if let Some(yoink) = cause.downcast_ref::<YoinkError>() {
+                return Some(match yoink {
+                    Self::NotFound(_) => Self::NotFound,
+                    Self::NoAsset(_) => Self::NoAsset,
+                    Self::Network(_) => Self::Network,
+                    Self::RateLimited(_) => Self::RateLimited,
+                    Self::Extract(_) => Self::Extract,
+                    Self::Io(_) => Self::Io,
+                    Self::Other(_) => Self::Other,
+                });
+            }
+            if cause.is::<NoMatchingRelease>() {
+                return Some(Self::NotFound);
+            }
+            if cause.is::<NoSuitableAsset>() {
+                return Some(Self::NoAsset);
+            }
+            if cause.is::<RateLimitExceeded>() {
+                return Some(Self::RateLimited);
+            }
+            if cause.is::<ExtractFailed>() || cause.is::<UnsafeEntryPath>() {
+                return Some(Self::Extract);
+            }
+            if let Some(http) = cause.downcast_ref::<reqwest::Error>() {
+                return Some(match http.status().map(|status| status.as_u16()) {
+                    Some(404) => Self::NotFound,
+                    _ => Self::Network,
+                });
+            }
+            if cause.is::<io::Error>() {
+                return Some(Self::Io);
+            }
+            None
+        });
+        kind.unwrap_or(Self::Other)(err)
+    }
+}
+
+impl std::fmt::Display for YoinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.inner(), f)
+    }
+}
+
+impl std::fmt::Debug for YoinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self.inner(), f)
+    }
+}
+
+impl std::error::Error for YoinkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner().chain().nth(1)
+    }
+}
+
+pub fn install(repo: &str) -> Result<PathBuf, YoinkError> {
+    install_with(repo, &Options::default())
+}
+
+pub fn install_with(repo: &str, options: &Options) -> Result<PathBuf, YoinkError> {
+    Ok(install_with_report(repo, options)?.path)
 }
 
-pub fn install(repo: &str) -> Result<PathBuf> {
-    let (dest, _version) = install_with_version(repo)?;
-    Ok(dest)
+/// What an install did, for audit-friendly output.
+#[derive(Debug, Serialize)]
+pub struct InstallReport {
+    pub repo: String,
+    pub version: String,
+    pub path: PathBuf,
+    /// The install dir or a binary could only be written via the sudo fallback.
+    pub used_sudo: bool,
+    /// How to put the install dir on `$PATH` when it isn't; set at most once per process.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_warning: Option<String>,
+    /// Problems that didn't stop the install, such as a binary built for another architecture
+    /// under `--no-verify-exec-format`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -46,905 +405,1539 @@ pub struct DownloadSummary {
     pub asset_name: String,
     pub primary_path: PathBuf,
     pub paths: Vec<PathBuf>,
+    /// Where each of `paths` came from: its path inside the archive, or the asset name when
+    /// the asset is the binary itself.
+    pub sources: Vec<PathBuf>,
+    /// Hash `emit_manifest` records for `paths`.
+    pub digest_algo: DigestAlgo,
+    /// Problems that didn't stop the download; see `InstallReport::warnings`.
+    pub warnings: Vec<String>,
+}
+
+pub fn release_info(repo: &str) -> Result<ReleaseInfo, YoinkError> {
+    release_info_with(repo, &Options::default())
+}
+
+pub fn release_info_with(repo: &str, options: &Options) -> Result<ReleaseInfo, YoinkError> {
+    let (owner, name, tag) = parse_repo(repo, options)?;
+    let max_age = options
+        .info_max_age
+        .filter(|_| !options.isolated && options.release_json.is_none());
+    let key = release_info_key(repo, options)?;
+    if let Some(max_age) = max_age {
+        if let Some(info) = cached_release_info(&key, max_age)? {
+            return Ok(info);
+        }
+    }
+    let forge = repo_forge(repo, options);
+    let client = forge.client(&owner, options)?;
+    let info = resolve_release_info(&*forge, &client, &owner, &name, tag.as_deref(), options)?;
+    if max_age.is_some() {
+        store_release_info(&key, &info)?;
+    }
+    Ok(info)
+}
+
+/// The info cache key for `repo` resolved under `options`.
+fn release_info_key(repo: &str, options: &Options) -> Result<String> {
+    let (_owner, _name, tag) = parse_repo(repo, options)?;
+    let mut key = state_key(repo, options)?;
+    if let Some(tag) = &tag {
+        key = format!("{key}@{tag}");
+    }
+    if let Some(arch) = &options.arch {
+        key = format!("{key}#{arch}");
+    }
+    if options.semver_latest && tag.is_none() {
+        key.push_str("+semver");
+    }
+    if options.pre && tag.is_none() {
+        key.push_str("+pre");
+    }
+    if let Some(pattern) = &options.asset {
+        key = format!("{key}={pattern}");
+    }
+    // Everything else that steers asset selection, so a cached pick is only reused for the
+    // same choice.
+    if let Some(os) = &options.only_os {
+        key = format!("{key}+os={os}");
+    }
+    if let Some(arch) = &options.only_arch {
+        key = format!("{key}+arch={arch}");
+    }
+    if options.tag_tokens {
+        key.push_str("+tag-tokens");
+    }
+    if options.ignore_arch {
+        key.push_str("+any-arch");
+    }
+    if !options.asset_prefer.is_empty() {
+        key = format!("{key}+prefer={}", options.asset_prefer.join(","));
+    }
+    if !options.asset_avoid.is_empty() {
+        key = format!("{key}+avoid={}", options.asset_avoid.join(","));
+    }
+    Ok(key)
+}
+
+/// Parses durations such as `500ms`, `90s`, `10m`, `1h`, or `2d`; a bare number is seconds.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let amount: u64 = digits
+        .parse()
+        .with_context(|| format!("invalid duration: {value}"))?;
+    let multiplier = match unit {
+        "ms" => return Ok(Duration::from_millis(amount)),
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("invalid duration unit in {value}; use ms, s, m, h, or d"),
+    };
+    Ok(Duration::from_secs(amount.saturating_mul(multiplier)))
+}
+
+pub fn download_to_dir(repo: &str, dest_dir: &Path) -> Result<DownloadSummary, YoinkError> {
+    download_to_dir_with(repo, dest_dir, &Options::default())
+}
+
+pub fn download_to_dir_with(
+    repo: &str,
+    dest_dir: &Path,
+    options: &Options,
+) -> Result<DownloadSummary, YoinkError> {
+    let prepared = prepare_binary(repo, options)?;
+    Ok(write_prepared(prepared, dest_dir, options)?)
+}
+
+/// Downloads each of `repos` into `dest_dir`, at most `Options::max_parallel_repos` at once.
+/// Results come back in the order of `repos`.
+pub fn download_many(
+    repos: &[String],
+    dest_dir: &Path,
+    options: &Options,
+) -> Vec<Result<DownloadSummary, YoinkError>> {
+    bounded_map(repos, repo_jobs(options), |repo| {
+        download_to_dir_with(repo, dest_dir, options)
+    })
+}
+
+/// Downloads the best asset for every architecture the release ships for the target OS,
+/// each into a subdirectory of `dest_dir` named after the architecture.
+pub fn download_all_arches(
+    repo: &str,
+    dest_dir: &Path,
+    options: &Options,
+) -> Result<Vec<DownloadSummary>, YoinkError> {
+    let (owner, name, tag) = parse_repo(repo, options)?;
+    let forge = repo_forge(repo, options);
+    let client = forge.client(&owner, options)?;
+    let configured;
+    let options = match with_asset_preferences(options)? {
+        Some(with_preferences) => {
+            configured = with_preferences;
+            &configured
+        }
+        None => options,
+    };
+    let release = load_release(&*forge, &client, &owner, &name, tag.as_deref(), options)?;
+    let tag = release.tag_name.as_deref().unwrap_or("unknown").to_string();
+    let os = options.only_os.as_deref().unwrap_or(env::consts::OS);
+
+    let mut summaries = Vec::new();
+    for arch in KNOWN_ARCHES {
+        let arch_assets: Vec<Asset> = release
+            .assets
+            .iter()
+            .filter(|asset| !is_ignored_asset(&asset.name))
+            .filter(|asset| asset_arch(&asset.name) == Some(arch))
+            .filter(|asset| asset_matches_os(&asset.name, os))
+            .cloned()
+            .collect();
+        if arch_assets.is_empty() {
+            continue;
+        }
+        let mut arch_options = options.clone();
+        arch_options.arch = Some(arch.to_string());
+        let asset = pick_asset(&arch_assets, &name, Some(&tag), &arch_options)?;
+        let info = ReleaseInfo {
+            owner: owner.clone(),
+            name: name.clone(),
+            tag: tag.clone(),
+            checksum_url: checksum_asset(&release.assets, &asset.name),
+            signature_urls: signature_assets(&release.assets, &asset.name),
+            asset_name: asset.name,
+            asset_url: asset.browser_download_url,
+        };
+        let prepared = prepare_release_asset(&client, info, &*forge, &arch_options)?;
+        // Only a template that varies by arch keeps the arches apart by itself.
+        let arch_dir = if dest_dir.to_str().is_some_and(|dir| dir.contains("{arch}")) {
+            dest_dir.to_path_buf()
+        } else {
+            dest_dir.join(arch)
+        };
+        summaries.push(write_prepared(prepared, &arch_dir, &arch_options)?);
+    }
+
+    if summaries.is_empty() {
+        return Err(anyhow!("release has no architecture-specific assets for {os}").into());
+    }
+    Ok(summaries)
+}
+
+/// Downloads every published release newer than `since`, oldest first, each into a
+/// subdirectory of `dest_dir` named after its tag.
+pub fn download_since_tag(
+    repo: &str,
+    since: &str,
+    dest_dir: &Path,
+    options: &Options,
+) -> Result<Vec<DownloadSummary>, YoinkError> {
+    let (owner, name, _tag) = parse_repo(repo, options)?;
+    let forge = repo_forge(repo, options);
+    let client = forge.client(&owner, options)?;
+    let configured;
+    let options = match with_asset_preferences(options)? {
+        Some(with_preferences) => {
+            configured = with_preferences;
+            &configured
+        }
+        None => options,
+    };
+    let mut releases: Vec<(String, Release)> = forge
+        .fetch_releases(&client, &owner, &name, options)?
+        .into_iter()
+        .filter(|release| !release.draft && !release.prerelease)
+        .filter_map(|release| Some((release.tag_name.clone()?, release)))
+        .filter(|(tag, _)| {
+            version_key(tag).is_some() && compare_versions(tag, since) == Ordering::Greater
+        })
+        .collect();
+    if releases.is_empty() {
+        return Err(anyhow!("no releases of {owner}/{name} newer than {since}").into());
+    }
+    releases.sort_by(|(a, _), (b, _)| compare_versions(a, b));
+
+    let mut summaries = Vec::new();
+    for (tag, release) in releases {
+        let asset = pick_asset(&release.assets, &name, Some(&tag), options)
+            .with_context(|| format!("pick asset for {tag}"))?;
+        let info = ReleaseInfo {
+            owner: owner.clone(),
+            name: name.clone(),
+            tag: tag.clone(),
+            checksum_url: checksum_asset(&release.assets, &asset.name),
+            signature_urls: signature_assets(&release.assets, &asset.name),
+            asset_name: asset.name,
+            asset_url: asset.browser_download_url,
+        };
+        let prepared = prepare_release_asset(&client, info, &*forge, options)?;
+        let subdir = dest_dir.join(tag.replace(['/', '\\'], "-"));
+        summaries.push(write_prepared(prepared, &subdir, options)?);
+    }
+    Ok(summaries)
+}
+
+fn is_dest_template(dir: &Path) -> bool {
+    dir.to_str().is_some_and(|dir| dir.contains('{'))
 }
 
-pub fn release_info(repo: &str) -> Result<ReleaseInfo> {
-    let (owner, name) = parse_repo(repo)?;
-    let client = github_client()?;
-    resolve_release_info(&client, &owner, &name)
+/// Fills `{os}`, `{arch}`, `{version}`/`{tag}`, and `{repo}` in a destination dir, using
+/// `--only-os`/`--arch`/`--only-arch` when given and the host platform otherwise.
+fn expand_dest_template(dir: &Path, repo_name: &str, tag: &str, options: &Options) -> PathBuf {
+    let Some(template) = dir.to_str().filter(|_| is_dest_template(dir)) else {
+        return dir.to_path_buf();
+    };
+    let os = normalize_os(options.only_os.as_deref().unwrap_or(env::consts::OS));
+    let arch = normalize_arch(
+        options
+            .arch
+            .as_deref()
+            .or(options.only_arch.as_deref())
+            .unwrap_or(env::consts::ARCH),
+    );
+    PathBuf::from(
+        template
+            .replace("{os}", os)
+            .replace("{arch}", arch)
+            .replace("{version}", display_version(tag))
+            .replace("{tag}", tag)
+            .replace("{repo}", repo_name),
+    )
 }
 
-pub fn download_to_dir(repo: &str, dest_dir: &Path) -> Result<DownloadSummary> {
-    let prepared = prepare_binary(repo)?;
+fn write_prepared(
+    prepared: PreparedBinary,
+    dest_dir: &Path,
+    options: &Options,
+) -> Result<DownloadSummary> {
+    let dest_dir = &expand_dest_template(dest_dir, &prepared.name, &prepared.tag, options);
     fs::create_dir_all(dest_dir).with_context(|| format!("create {}", dest_dir.display()))?;
 
     let Some(name) = prepared.path.file_name() else {
         bail!("downloaded binary has no filename");
     };
-    let dest = dest_dir.join(name);
-    install_binary(&prepared.path, &dest)?;
+    let dest = dest_dir.join(with_exe_suffix(&prepared.path, renamed(name, options)));
+    let repo = format!("{}/{}", prepared.owner, prepared.name);
+    let dest = unclaimed_dest(
+        &prepared.path,
+        dest,
+        &repo,
+        &prepared.owner,
+        options.dest_exists,
+    )?;
+    place_binary(&prepared.path, &dest, options.dest_exists, true)?;
     let mut downloaded = vec![dest.clone()];
+    let mut sources = vec![prepared.source(&prepared.path)];
 
     for extra in &prepared.extra_paths {
         let Some(name) = extra.file_name() else {
             continue;
         };
-        let extra_dest = dest_dir.join(name);
+        let extra_dest = dest_dir.join(renamed(name, options));
         if downloaded.iter().any(|path| path == &extra_dest) {
             continue;
         }
-        install_binary(extra, &extra_dest)?;
+        let extra_dest = unclaimed_dest(
+            extra,
+            extra_dest,
+            &repo,
+            &prepared.owner,
+            options.dest_exists,
+        )?;
+        place_binary(extra, &extra_dest, options.dest_exists, false)?;
         downloaded.push(extra_dest);
+        sources.push(prepared.source(extra));
+    }
+    ensure_written(&downloaded)?;
+    if let Some(man_dir) = &options.man_dir {
+        prepared.install_man_pages(man_dir)?;
     }
 
     Ok(DownloadSummary {
-        repo: format!("{}/{}", prepared.owner, prepared.name),
+        repo,
         tag: prepared.tag,
         url: prepared.asset_url,
         asset_name: prepared.asset_name,
         primary_path: dest,
         paths: downloaded,
+        sources,
+        digest_algo: options.digest_algo,
+        warnings: prepared.warnings,
     })
 }
 
-fn install_with_version(repo: &str) -> Result<(PathBuf, String)> {
-    let prepared = prepare_binary(repo)?;
-    let install_dir = default_install_dir()?;
-    ensure_install_dir(&install_dir)?;
+/// Parses `--rename-map`'s `from=to,from=to` list.
+pub fn parse_rename_map(input: &str) -> Result<BTreeMap<String, String>> {
+    let mut map = BTreeMap::new();
+    for pair in input
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+    {
+        let Some((from, to)) = pair.split_once('=') else {
+            bail!("expected from=to in rename map, got {pair}");
+        };
+        let (from, to) = (from.trim(), to.trim());
+        if from.is_empty() || to.is_empty() || to.contains(['/', '\\']) {
+            bail!("invalid rename {pair}");
+        }
+        map.insert(from.to_string(), to.to_string());
+    }
+    Ok(map)
+}
+
+/// Applies `rename_map` to a binary's filename, matching `uv.exe` by `uv` too.
+fn renamed(name: &OsStr, options: &Options) -> OsString {
+    let Some(text) = name.to_str() else {
+        return name.to_os_string();
+    };
+    if let Some(to) = options.rename_map.get(text) {
+        return OsString::from(to);
+    }
+    let path = Path::new(text);
+    if let (Some(stem), Some(ext)) = (
+        path.file_stem().and_then(OsStr::to_str),
+        path.extension().and_then(OsStr::to_str),
+    ) {
+        if let Some(to) = options.rename_map.get(stem) {
+            return OsString::from(format!("{to}.{ext}"));
+        }
+    }
+    name.to_os_string()
+}
+
+pub fn install_with_report(repo: &str, options: &Options) -> Result<InstallReport, YoinkError> {
+    let (_owner, _name, tag) = parse_repo(repo, options)?;
+    if let Some(tag) = &tag {
+        let state = load_state()?;
+        let repo = state_key(repo, options)?;
+        if let Some(entry) = state.installs.get(&repo) {
+            if entry.pinned && tag_has_prefix(&entry.version, tag) && entry.bin.exists() {
+                return Ok(InstallReport {
+                    repo,
+                    version: entry.version.clone(),
+                    path: entry.bin.clone(),
+                    used_sudo: false,
+                    path_warning: None,
+                    warnings: Vec::new(),
+                });
+            }
+        }
+    }
+    let prepared = prepare_binary(repo, options)?;
+    Ok(install_prepared(
+        prepared,
+        tag.is_some(),
+        &default_install_dir(options)?,
+        options,
+    )?)
+}
+
+/// What `ensure` did to reach the declared version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnsureAction {
+    /// The declared version was already installed.
+    Unchanged,
+    /// Nothing was installed before.
+    Installed,
+    /// A different version was installed and has been replaced.
+    Replaced { from: String },
+}
+
+#[derive(Debug)]
+pub struct EnsureReport {
+    pub action: EnsureAction,
+    pub install: InstallReport,
+}
+
+/// Installs `owner/repo@version` unless a release that version names or prefixes is already
+/// installed, and pins it.
+pub fn ensure(repo: &str, options: &Options) -> Result<EnsureReport, YoinkError> {
+    let (owner, name, tag) = parse_repo(repo, options)?;
+    let Some(tag) = tag else {
+        return Err(anyhow!("ensure needs a version, e.g. {owner}/{name}@v1.2.3").into());
+    };
+    let key = state_key(repo, options)?;
+    let previous = load_state()?.installs.remove(&key);
+    if let Some(entry) = &previous {
+        if tag_has_prefix(&entry.version, &tag) && entry.bin.exists() {
+            return Ok(EnsureReport {
+                action: EnsureAction::Unchanged,
+                install: InstallReport {
+                    repo: key,
+                    version: entry.version.clone(),
+                    path: entry.bin.clone(),
+                    used_sudo: false,
+                    path_warning: None,
+                    warnings: Vec::new(),
+                },
+            });
+        }
+    }
+    let prepared = prepare_binary(repo, options)?;
+    let install = install_prepared(prepared, true, &default_install_dir(options)?, options)?;
+    let action = match previous {
+        Some(entry) if entry.version != install.version => EnsureAction::Replaced {
+            from: entry.version,
+        },
+        _ => EnsureAction::Installed,
+    };
+    Ok(EnsureReport { action, install })
+}
+
+/// Reinstalls the version of `owner/repo` that was installed before the current one, pinning it
+/// so `upgrade_all` doesn't undo the rollback.
+pub fn rollback(repo: &str, options: &Options) -> Result<InstallReport, YoinkError> {
+    let key = state_key(repo, options)?;
+    let mut state = load_state()?;
+    let entry = state
+        .installs
+        .remove(&key)
+        .with_context(|| format!("{key} not installed"))?;
+    let Some((previous, earlier)) = entry.history.split_last() else {
+        return Err(anyhow!("no earlier version of {key} recorded to roll back to").into());
+    };
+    let install_dir = match entry.install_dir() {
+        Some(dir) => dir.to_path_buf(),
+        None => default_install_dir(options)?,
+    };
+    let report = match relink_stored(&key, &entry, previous, &install_dir)? {
+        Some(report) => report,
+        None => {
+            let options = Options {
+                host: options.host.clone().or_else(|| entry.host.clone()),
+                store: entry.store_path.is_some(),
+                ..options.clone()
+            };
+            let prepared = prepare_binary(&format!("{key}@{previous}"), &options)?;
+            install_prepared(prepared, true, &install_dir, &options)?
+        }
+    };
+    // Recording the install pushed the version we rolled back from; drop it so a second
+    // rollback keeps going back rather than toggling between two versions.
+    let earlier = earlier.to_vec();
+    update_install(&key, |entry| entry.history = earlier)?;
+    Ok(report)
+}
+
+/// Points a store install's binaries back at `version` when the store still holds every one
+/// of them, so rolling back needs no download. `None` when it doesn't.
+fn relink_stored(
+    key: &str,
+    entry: &InstallEntry,
+    version: &str,
+    install_dir: &Path,
+) -> Result<Option<InstallReport>> {
+    let Some(stored) = entry.store_path.as_deref().and_then(Path::parent) else {
+        return Ok(None);
+    };
+    let stored = stored.join(version);
+    let bins: Vec<PathBuf> = entry.all_bins().cloned().collect();
+    let targets: Option<Vec<PathBuf>> = bins
+        .iter()
+        .map(|bin| bin.file_name().map(|name| stored.join(name)))
+        .collect();
+    let Some(targets) = targets.filter(|targets| targets.iter().all(|path| path.is_file())) else {
+        return Ok(None);
+    };
+
+    let lock = lock_install_dir(install_dir)?;
+    let mut used_sudo = false;
+    for (target, bin) in targets.iter().zip(&bins) {
+        used_sudo |= link_payload(target, bin)?;
+    }
+    commit_install(
+        key,
+        version,
+        &bins,
+        true,
+        &InstallMeta {
+            store_path: Some(&stored),
+            host: entry.host.as_deref(),
+            man_pages: &entry.man_pages,
+        },
+    )?;
+    lock.unlock()
+        .with_context(|| format!("unlock {}", install_dir.display()))?;
+    Ok(Some(InstallReport {
+        repo: key.to_string(),
+        version: version.to_string(),
+        path: entry.bin.clone(),
+        used_sudo,
+        path_warning: None,
+        warnings: Vec::new(),
+    }))
+}
+
+/// Copies a prepared binary and its extras into the install dir and records them.
+fn install_prepared(
+    prepared: PreparedBinary,
+    pinned: bool,
+    install_dir: &Path,
+    options: &Options,
+) -> Result<InstallReport> {
+    let mut used_sudo = ensure_install_dir(install_dir)?;
+    let lock = lock_install_dir(install_dir)?;
+    let store_path = match options.store {
+        true => Some(
+            store_root()?
+                .join(&prepared.owner)
+                .join(&prepared.name)
+                .join(&prepared.tag),
+        ),
+        false => None,
+    };
+    let place = |payload: &Path, dest: &Path, executable: bool| match &store_path {
+        Some(store_path) => {
+            fs::create_dir_all(store_path)
+                .with_context(|| format!("create {}", store_path.display()))?;
+            let stored = store_path.join(dest.file_name().unwrap_or(payload.as_os_str()));
+            install_binary(payload, &stored, executable)?;
+            link_payload(&stored, dest)
+        }
+        None => install_payload(payload, dest, executable),
+    };
 
     let Some(name) = prepared.path.file_name() else {
         bail!("downloaded binary has no filename");
     };
-    let dest = install_dir.join(name);
-    install_payload(&prepared.path, &dest)?;
+    let dest = install_dir.join(with_exe_suffix(&prepared.path, renamed(name, options)));
+    used_sudo |= place(&prepared.path, &dest, true)?;
     let mut installed_bins = vec![dest.clone()];
     for extra in &prepared.extra_paths {
         let Some(name) = extra.file_name() else {
             continue;
         };
-        let extra_dest = install_dir.join(name);
+        let extra_dest = install_dir.join(renamed(name, options));
         if installed_bins.iter().any(|path| path == &extra_dest) {
             continue;
         }
-        install_payload(extra, &extra_dest)?;
+        used_sudo |= place(extra, &extra_dest, false)?;
         installed_bins.push(extra_dest);
     }
-    let version = prepared.tag.clone();
-    record_install(
-        &format!("{}/{}", prepared.owner, prepared.name),
-        &version,
+    let man_pages = match &options.man_dir {
+        Some(man_dir) => prepared.install_man_pages(man_dir)?,
+        None => Vec::new(),
+    };
+    let repo = format!(
+        "{}{}/{}",
+        prepared.repo_prefix, prepared.owner, prepared.name
+    );
+    commit_install(
+        &repo,
+        &prepared.tag,
         &installed_bins,
+        pinned,
+        &InstallMeta {
+            store_path: store_path.as_deref(),
+            host: prepared.host.as_deref(),
+            man_pages: &man_pages,
+        },
     )?;
+    lock.unlock()
+        .with_context(|| format!("unlock {}", install_dir.display()))?;
+
+    Ok(InstallReport {
+        repo,
+        version: prepared.tag,
+        path: dest,
+        used_sudo,
+        path_warning: path_warning_once(
+            install_dir,
+            &env::var_os("PATH").unwrap_or_default(),
+            options,
+        ),
+        warnings: prepared.warnings,
+    })
+}
 
-    Ok((dest, version))
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    #[serde(default, rename = "tool")]
+    tools: Vec<ManifestEntry>,
 }
 
-pub fn is_repo_shape(input: &str) -> bool {
-    parse_repo(input).is_ok()
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestEntry {
+    repo: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
 }
 
-pub fn run(repo: &str, args: &[String]) -> Result<i32> {
-    let prepared = prepare_binary(repo)?;
-    set_executable(&prepared.path)?;
-    let status = Command::new(&prepared.path)
-        .args(args)
-        .status()
-        .with_context(|| format!("run {}", prepared.path.display()))?;
-    Ok(exit_status_code(status))
+/// Renders the recorded installs as a TOML manifest that `read_repo_file` accepts. Pinned
+/// installs are written as `owner/repo@version` so importing them pins the same version.
+pub fn export_manifest() -> Result<String, YoinkError> {
+    let state = load_state()?;
+    let manifest = Manifest {
+        tools: state
+            .installs
+            .into_iter()
+            .map(|(repo, entry)| ManifestEntry {
+                repo: match entry.pinned {
+                    true => format!("{repo}@{}", entry.version),
+                    false => repo,
+                },
+                version: Some(entry.version),
+            })
+            .collect(),
+    };
+    Ok(toml::to_string(&manifest).context("serialize manifest")?)
 }
 
-#[derive(Debug)]
-pub struct InstallSummary {
-    pub repo: String,
-    pub version: String,
+#[derive(Serialize)]
+struct EmittedManifest {
+    #[serde(rename = "tool")]
+    tools: Vec<EmittedTool>,
 }
 
-pub fn list_installs() -> Result<Vec<InstallSummary>> {
-    let state = load_state()?;
-    let mut installs = Vec::new();
-    for (repo, entry) in state.installs {
-        installs.push(InstallSummary {
-            repo,
-            version: display_version(&entry.version).to_string(),
-        });
-    }
-    Ok(installs)
+#[derive(Serialize)]
+struct EmittedTool {
+    repo: String,
+    version: String,
+    asset: String,
+    url: String,
+    #[serde(rename = "bin")]
+    bins: Vec<EmittedBin>,
 }
 
-#[derive(Debug)]
-pub struct UpgradeSummary {
-    pub repo: String,
-    pub version: String,
-    pub path: PathBuf,
+#[derive(Serialize)]
+struct EmittedBin {
+    path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha512: Option<String>,
 }
 
-pub fn upgrade_all() -> Result<Vec<UpgradeSummary>> {
-    let state = load_state()?;
-    let repos: Vec<String> = state.installs.keys().cloned().collect();
-    let mut upgrades = Vec::new();
-    for repo in repos {
-        let (path, version) = install_with_version(&repo)?;
-        upgrades.push(UpgradeSummary {
-            repo,
-            version: display_version(&version).to_string(),
-            path,
+/// Writes a report of what `summaries` put on disk to `path`: TOML for `.toml` (which
+/// `read_repo_file` also accepts), JSON otherwise.
+pub fn emit_manifest(path: &Path, summaries: &[DownloadSummary]) -> Result<(), YoinkError> {
+    let mut tools = Vec::new();
+    for summary in summaries {
+        let bins = summary
+            .paths
+            .iter()
+            .map(|bin| {
+                let digest = file_digest(bin, summary.digest_algo)?;
+                let (sha256, sha512) = match summary.digest_algo {
+                    DigestAlgo::Sha256 => (Some(digest), None),
+                    DigestAlgo::Sha512 => (None, Some(digest)),
+                };
+                Ok(EmittedBin {
+                    path: bin.clone(),
+                    sha256,
+                    sha512,
+                })
+            })
+            .collect::<Result<_>>()?;
+        tools.push(EmittedTool {
+            repo: summary.repo.clone(),
+            version: summary.tag.clone(),
+            asset: summary.asset_name.clone(),
+            url: summary.url.clone(),
+            bins,
         });
     }
-    Ok(upgrades)
+    let manifest = EmittedManifest { tools };
+    let is_toml = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| ext.eq_ignore_ascii_case("toml"))
+        .unwrap_or(false);
+    let contents = if is_toml {
+        toml::to_string(&manifest).context("serialize manifest")?
+    } else {
+        serde_json::to_string_pretty(&manifest).context("serialize manifest")? + "\n"
+    };
+    Ok(fs::write(path, contents).with_context(|| format!("write manifest {}", path.display()))?)
 }
 
-pub fn uninstall(repo: &str) -> Result<()> {
-    let (owner, name) = parse_repo(repo)?;
-    let key = format!("{owner}/{name}");
-    remove_install(&key)
+/// Reads a list of repos, either a TOML manifest (`.toml`) as written by `export_manifest`
+/// or plain text with one `owner/repo` per line; blank lines and `#` comments are ignored.
+pub fn read_repo_file(path: &Path) -> Result<Vec<String>, YoinkError> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("read repo file {}", path.display()))?;
+    let is_toml = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| ext.eq_ignore_ascii_case("toml"))
+        .unwrap_or(false);
+    if is_toml {
+        let manifest: Manifest = toml::from_str(&contents)
+            .with_context(|| format!("parse manifest {}", path.display()))?;
+        return Ok(manifest.tools.into_iter().map(|tool| tool.repo).collect());
+    }
+
+    let mut repos = Vec::new();
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        repos.push(line.to_string());
+    }
+    Ok(repos)
 }
 
-fn parse_repo(repo: &str) -> Result<(String, String)> {
-    let mut parts = repo.split('/');
-    let owner = parts.next().unwrap_or("");
-    let name = parts.next().unwrap_or("");
-    if owner.is_empty() || name.is_empty() || parts.next().is_some() {
-        bail!("expected repo in owner/name form")
-    }
-    Ok((owner.to_string(), name.to_string()))
+/// Where the primary binary of `repo` would be written inside `dir`, without downloading anything.
+pub fn destination_path(repo: &str, dir: &Path) -> Result<PathBuf, YoinkError> {
+    destination_path_with(repo, dir, &Options::default())
 }
 
-struct PreparedBinary {
-    owner: String,
-    name: String,
-    tag: String,
-    asset_name: String,
-    asset_url: String,
-    path: PathBuf,
-    extra_paths: Vec<PathBuf>,
-    _download_dir: TempDir,
-    _extracted: Option<ExtractedPaths>,
+/// Like `destination_path`, naming the binary after `--bin` when given and applying
+/// `--rename-map`.
+pub fn destination_path_with(
+    repo: &str,
+    dir: &Path,
+    options: &Options,
+) -> Result<PathBuf, YoinkError> {
+    let (_owner, name, _tag) = parse_repo(repo, options)?;
+    let name = match &options.bin {
+        Some(bin) => bin.strip_suffix(".exe").unwrap_or(bin).to_string(),
+        None => name,
+    };
+    Ok(dir.join(renamed(OsStr::new(&binary_name(&name)), options)))
 }
 
-fn prepare_binary(repo: &str) -> Result<PreparedBinary> {
-    let (owner, name) = parse_repo(repo)?;
-    let client = github_client()?;
-    let info = resolve_release_info(&client, &owner, &name)?;
+/// What downloading `repo` into a directory would do.
+#[derive(Debug)]
+pub struct DryRun {
+    pub info: ReleaseInfo,
+    /// Where the primary binary would land; for archives this assumes it is named after
+    /// the repo, since the contents aren't known until extraction.
+    pub destination: PathBuf,
+}
 
-    let temp_dir = tempfile::tempdir().context("create temp dir")?;
-    let download_path = temp_dir.path().join(&info.asset_name);
-    let asset_name = info.asset_name.clone();
-    let asset_url = info.asset_url.clone();
-    download_asset(&client, &asset_url, &download_path)?;
+/// Resolves the release and asset for `repo` without downloading the asset or writing files.
+pub fn dry_run(repo: &str, dir: &Path, options: &Options) -> Result<DryRun, YoinkError> {
+    let info = release_info_with(repo, options)?;
+    let asset = info.asset_name.as_str();
+    let packed = is_archive_name(asset) || is_gzip_name(asset) || is_zstd_name(asset);
+    let file_name = match &options.bin {
+        // `--bin` picks the unpacked file by name, as `destination_path_with` assumes.
+        Some(bin) if packed => binary_name(bin.strip_suffix(".exe").unwrap_or(bin)),
+        _ if is_archive_name(asset) => binary_name(&info.name),
+        _ if is_gzip_name(asset) => strip_suffix_ignore_case(asset, ".gz").to_string(),
+        _ if is_zstd_name(asset) => strip_suffix_ignore_case(asset, ".zst").to_string(),
+        _ => asset.to_string(),
+    };
+    let dir = expand_dest_template(dir, &info.name, &info.tag, options);
+    let destination = dir.join(renamed(OsStr::new(&file_name), options));
+    Ok(DryRun { info, destination })
+}
 
-    let mut extracted = None;
-    let (payload_path, extra_paths) = if is_archive_name(&asset_name) {
-        let extracted_paths = extract_archive(&download_path, &name)?;
-        let primary = extracted_paths.primary.clone();
-        let extras = extracted_paths.extras.clone();
-        extracted = Some(extracted_paths);
-        (primary, extras)
-    } else if is_gzip_name(&asset_name) {
-        let extracted_paths = extract_gzip(&download_path, &name)?;
-        let primary = extracted_paths.primary.clone();
-        let extras = extracted_paths.extras.clone();
-        extracted = Some(extracted_paths);
-        (primary, extras)
-    } else {
-        (download_path, Vec::new())
-    };
+/// Where `install` would put the primary binary of `repo`.
+pub fn install_path(repo: &str) -> Result<PathBuf, YoinkError> {
+    install_path_with(repo, &Options::default())
+}
 
-    Ok(PreparedBinary {
-        owner,
-        name,
-        tag: info.tag,
-        asset_name,
-        asset_url,
-        path: payload_path,
-        extra_paths,
-        _download_dir: temp_dir,
-        _extracted: extracted,
-    })
+pub fn install_path_with(repo: &str, options: &Options) -> Result<PathBuf, YoinkError> {
+    destination_path_with(repo, &default_install_dir(options)?, options)
 }
 
-fn github_client() -> Result<Client> {
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        reqwest::header::USER_AGENT,
-        reqwest::header::HeaderValue::from_static("yoink"),
-    );
-    headers.insert(
-        reqwest::header::ACCEPT,
-        reqwest::header::HeaderValue::from_static("application/vnd.github+json"),
-    );
-    headers.insert(
-        "X-GitHub-Api-Version",
-        reqwest::header::HeaderValue::from_static("2022-11-28"),
-    );
+/// What `check_repo` learned about a repo without downloading anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepoStatus {
+    HasRelease,
+    NoRelease,
+    NotFound,
+}
 
-    if let Some(token) = github_token() {
-        let value = format!("token {}", token);
-        let header =
-            reqwest::header::HeaderValue::from_str(&value).context("parse GitHub token header")?;
-        headers.insert(reqwest::header::AUTHORIZATION, header);
+/// Cheaply confirms `repo` exists and has a latest release.
+pub fn check_repo(repo: &str, options: &Options) -> Result<RepoStatus, YoinkError> {
+    let (owner, name, _tag) = parse_repo(repo, options)?;
+    let forge = repo_forge(repo, options);
+    let client = forge.client(&owner, options)?;
+    match forge.fetch_latest_release(&client, &owner, &name, options) {
+        Ok(_) => return Ok(RepoStatus::HasRelease),
+        Err(err) if is_release_missing(&err) => {}
+        Err(err) => return Err(err.into()),
+    }
+    match forge.repo_exists(&client, &owner, &name, options)? {
+        true => Ok(RepoStatus::NoRelease),
+        false => Ok(RepoStatus::NotFound),
     }
-
-    Client::builder()
-        .default_headers(headers)
-        .timeout(std::time::Duration::from_secs(120))
-        .build()
-        .context("build http client")
 }
 
-fn github_token() -> Option<String> {
-    env::var("YOINK_GITHUB_TOKEN")
-        .ok()
-        .or_else(|| env::var("GITHUB_TOKEN").ok())
+pub fn is_repo_shape(input: &str) -> bool {
+    is_repo_shape_with(input, &Options::default())
 }
 
-fn github_api_base() -> String {
-    env::var("YOINK_GITHUB_API_BASE").unwrap_or_else(|_| "https://api.github.com".to_string())
+/// `is_repo_shape`, where a bare name only counts when `options` lets `YOINK_DEFAULT_OWNER`
+/// through.
+pub fn is_repo_shape_with(input: &str, options: &Options) -> bool {
+    parse_repo(input, options).is_ok()
 }
 
-fn resolve_release_info(client: &Client, owner: &str, repo: &str) -> Result<ReleaseInfo> {
-    let release = fetch_latest_release(client, owner, repo)?;
-    let asset = pick_asset(&release.assets, repo)?;
-    let tag = release.tag_name.as_deref().unwrap_or("unknown").to_string();
+pub fn run(repo: &str, args: &[String]) -> Result<i32, YoinkError> {
+    run_with(repo, args, &Options::default())
+}
 
-    Ok(ReleaseInfo {
-        owner: owner.to_string(),
-        name: repo.to_string(),
-        tag,
-        asset_name: asset.name,
-        asset_url: asset.browser_download_url,
-    })
+pub fn run_with(repo: &str, args: &[String], options: &Options) -> Result<i32, YoinkError> {
+    let prepared = prepare_runnable(repo, options)?;
+    let status = Command::new(&prepared.path)
+        .args(args)
+        .status()
+        .with_context(|| format!("run {}", prepared.path.display()))?;
+    Ok(exit_status_code(status))
 }
 
-fn fetch_latest_release(client: &Client, owner: &str, repo: &str) -> Result<Release> {
-    let base = github_api_base();
-    let base = base.trim_end_matches('/');
-    let url = format!("{base}/repos/{owner}/{repo}/releases/latest");
-    let response = client
-        .get(&url)
-        .send()
-        .with_context(|| format!("fetch latest release for {owner}/{repo}"))?
-        .error_for_status()
-        .with_context(|| format!("bad response for {owner}/{repo}"))?;
-    response
-        .json::<Release>()
-        .with_context(|| format!("parse release for {owner}/{repo}"))
+/// Like `run`, but captures the child's output: returns its exit code, stdout, and stderr.
+pub fn run_captured(repo: &str, args: &[String]) -> Result<(i32, Vec<u8>, Vec<u8>), YoinkError> {
+    run_captured_with(repo, args, &Options::default())
 }
 
-fn pick_asset(assets: &[Asset], repo_name: &str) -> Result<Asset> {
-    if assets.is_empty() {
-        bail!("release has no assets")
-    }
+pub fn run_captured_with(
+    repo: &str,
+    args: &[String],
+    options: &Options,
+) -> Result<(i32, Vec<u8>, Vec<u8>), YoinkError> {
+    let prepared = prepare_runnable(repo, options)?;
+    let output = Command::new(&prepared.path)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .with_context(|| format!("run {}", prepared.path.display()))?;
+    Ok((
+        exit_status_code(output.status),
+        output.stdout,
+        output.stderr,
+    ))
+}
 
-    let mut candidates: Vec<&Asset> = assets
-        .iter()
-        .filter(|asset| !is_ignored_asset(&asset.name))
-        .collect();
-    if candidates.is_empty() {
-        candidates = assets.iter().collect();
-    }
+fn prepare_runnable(repo: &str, options: &Options) -> Result<PreparedBinary> {
+    let prepared = prepare_locked(repo, options)?;
+    set_executable(&prepared.path)?;
+    Ok(prepared)
+}
 
-    let mut prefer_shorter = false;
-    let repo_tokens = tokenize_name(repo_name);
-    if !repo_tokens.is_empty() {
-        let repo_candidates: Vec<&Asset> = candidates
-            .iter()
-            .copied()
-            .filter(|asset| asset_matches_repo(&asset.name, &repo_tokens))
-            .collect();
-        if !repo_candidates.is_empty() {
-            candidates = repo_candidates;
-            prefer_shorter = true;
-        }
-    }
+/// Prepares `repo` and checks it against `--lockfile`, when one is given. The lockfile digest
+/// is the verification `--checksum-required` asks for, so a release without a checksum file
+/// is fine here.
+fn prepare_locked(repo: &str, options: &Options) -> Result<PreparedBinary> {
+    let Some(lockfile) = &options.lockfile else {
+        return prepare_binary(repo, options);
+    };
+    let unchecked = Options {
+        checksum_required: false,
+        ..options.clone()
+    };
+    let prepared = prepare_binary(repo, &unchecked)?;
+    let key = format!("{}/{}", prepared.owner, prepared.name);
+    verify_locked(lockfile, &key, &prepared.path, options.digest_algo)?;
+    Ok(prepared)
+}
 
-    let os_tokens = os_tokens();
-    let arch_tokens = arch_tokens();
+/// A release's primary binary, readable without installing it anywhere.
+///
+/// The bytes live in a scratch dir owned by this value (under `Options::temp_dir` if set);
+/// it is removed when the `FetchedBinary` is dropped, so finish reading first.
+pub struct FetchedBinary {
+    pub repo: String,
+    pub tag: String,
+    pub asset_name: String,
+    pub url: String,
+    pub size: u64,
+    file: fs::File,
+    _prepared: PreparedBinary,
+}
 
-    let mut best: Option<(&Asset, i32, usize)> = None;
-    for asset in candidates {
-        let score = asset_score(&asset.name, &os_tokens, &arch_tokens);
-        let stem_len = asset_stem(&asset.name).len();
-        if best
-            .map(|(_, best_score, best_len)| {
-                score > best_score || (prefer_shorter && score == best_score && stem_len < best_len)
-            })
-            .unwrap_or(true)
-        {
-            best = Some((asset, score, stem_len));
-        }
+impl Read for FetchedBinary {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.file.read(buf)
     }
-
-    best.map(|(asset, _, _)| asset.clone())
-        .context("no suitable assets")
 }
 
-fn asset_score(name: &str, os_tokens: &[&str], arch_tokens: &[&str]) -> i32 {
-    let lower = name.to_lowercase();
-    let mut score = 0;
+/// Resolves, downloads and extracts `repo`'s primary binary, returning a reader over it.
+pub fn fetch_primary(repo: &str) -> Result<FetchedBinary, YoinkError> {
+    fetch_primary_with(repo, &Options::default())
+}
 
-    if contains_any(&lower, os_tokens) {
-        score += 2;
-    }
-    if contains_any(&lower, arch_tokens) {
-        score += 2;
-    }
-    if is_archive_name(&lower) {
-        score += 1;
-    }
-    if lower.ends_with(".exe") {
-        score += 1;
-    }
-    score
+pub fn fetch_primary_with(repo: &str, options: &Options) -> Result<FetchedBinary, YoinkError> {
+    let prepared = prepare_locked(repo, options)?;
+    let file = fs::File::open(&prepared.path)
+        .with_context(|| format!("open {}", prepared.path.display()))?;
+    let size = file
+        .metadata()
+        .with_context(|| format!("stat {}", prepared.path.display()))?
+        .len();
+    Ok(FetchedBinary {
+        repo: format!("{}/{}", prepared.owner, prepared.name),
+        tag: prepared.tag.clone(),
+        asset_name: prepared.asset_name.clone(),
+        url: prepared.asset_url.clone(),
+        size,
+        file,
+        _prepared: prepared,
+    })
 }
 
-fn contains_any(haystack: &str, tokens: &[&str]) -> bool {
-    tokens.iter().any(|token| haystack.contains(token))
+/// The binaries a release asset holds, as an install would pick them.
+#[derive(Debug)]
+pub struct ArchiveBins {
+    pub repo: String,
+    pub tag: String,
+    pub asset_name: String,
+    /// Archive-relative path of the binary an install treats as primary.
+    pub primary: PathBuf,
+    /// Archive-relative paths of the other probable binaries.
+    pub extras: Vec<PathBuf>,
 }
 
-fn tokenize_name(name: &str) -> Vec<String> {
-    name.to_lowercase()
-        .split(|c: char| !c.is_ascii_alphanumeric())
-        .filter(|token| !token.is_empty())
-        .map(|token| token.to_string())
-        .collect()
+/// Downloads and extracts `repo`'s asset and reports its binaries without installing any.
+pub fn list_bins(repo: &str, options: &Options) -> Result<ArchiveBins, YoinkError> {
+    let prepared = prepare_binary(repo, options)?;
+    Ok(ArchiveBins {
+        repo: format!("{}/{}", prepared.owner, prepared.name),
+        primary: prepared.source(&prepared.path),
+        extras: prepared
+            .extra_paths
+            .iter()
+            .map(|extra| prepared.source(extra))
+            .collect(),
+        tag: prepared.tag,
+        asset_name: prepared.asset_name,
+    })
 }
 
-fn asset_matches_repo(asset_name: &str, repo_tokens: &[String]) -> bool {
-    let stem = asset_stem(asset_name);
-    let tokens = tokenize_name(stem);
-    tokens_match_sequence(&tokens, repo_tokens)
+/// Finds the repo's binary on `PATH`; with `min_version`, also requires its `--version`
+/// output to report at least that version.
+pub fn present_on_path(
+    repo: &str,
+    min_version: Option<&str>,
+) -> Result<Option<PathBuf>, YoinkError> {
+    present_on_path_with(repo, min_version, &Options::default())
 }
 
-fn tokens_match_sequence(haystack: &[String], needle: &[String]) -> bool {
-    if needle.is_empty() {
-        return false;
-    }
-    if needle.len() > haystack.len() {
-        return false;
-    }
-    for start in 0..=(haystack.len() - needle.len()) {
-        if haystack[start..start + needle.len()] == *needle {
-            return true;
+/// Like `present_on_path`, looking for the name an install with `options` would give the
+/// binary, so `--bin` and `--rename-map` are honored.
+pub fn present_on_path_with(
+    repo: &str,
+    min_version: Option<&str>,
+    options: &Options,
+) -> Result<Option<PathBuf>, YoinkError> {
+    let installed = destination_path_with(repo, Path::new(""), options)?;
+    let Some(path) = installed.to_str().and_then(find_on_path) else {
+        return Ok(None);
+    };
+    if let Some(min_version) = min_version {
+        match probe_version(&path) {
+            Some(version) if compare_versions(&version, min_version) != Ordering::Less => {}
+            _ => return Ok(None),
         }
     }
-    false
+    Ok(Some(path))
 }
 
-fn os_tokens() -> Vec<&'static str> {
-    match env::consts::OS {
-        "macos" => vec!["darwin", "macos", "osx", "mac", "apple-darwin"],
-        "linux" => vec!["linux", "gnu", "unknown-linux"],
-        "windows" => vec!["windows", "win", "mingw", "msvc"],
-        other => vec![other],
-    }
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
 }
 
-fn arch_tokens() -> Vec<&'static str> {
-    match env::consts::ARCH {
-        "x86_64" => vec!["x86_64", "amd64", "x64"],
-        "aarch64" => vec!["aarch64", "arm64"],
-        "arm" => vec!["armv7", "armv7l", "armv6", "arm"],
-        other => vec![other],
-    }
+/// Runs `bin --version` and returns the first version-shaped token it prints.
+fn probe_version(bin: &Path) -> Option<String> {
+    let output = Command::new(bin).arg("--version").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).into_owned()
+        + &String::from_utf8_lossy(&output.stderr);
+    text.split(|ch: char| ch.is_whitespace() || ch == ',' || ch == '(' || ch == ')')
+        .map(|token| token.trim_start_matches(['v', 'V']))
+        .find(|token| token.contains('.') && version_key(token).is_some())
+        .map(str::to_string)
 }
 
-fn is_ignored_asset(name: &str) -> bool {
-    let lower = name.to_lowercase();
-    lower.ends_with(".sha256")
-        || lower.ends_with(".sha256sum")
-        || lower.ends_with(".sha512")
-        || lower.ends_with(".sig")
-        || lower.ends_with(".asc")
-        || lower.ends_with(".md5")
-        || lower.contains("checksum")
-        || lower.contains("checksums")
-        || lower.contains("sbom")
+/// Pinned binary digests, one `[[lock]]` table per repo, keyed by `DigestAlgo`:
+///
+/// ```toml
+/// [[lock]]
+/// repo = "mxcl/tool"
+/// sha256 = "…"
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Lockfile {
+    #[serde(default, rename = "lock")]
+    entries: Vec<LockEntry>,
 }
 
-fn is_archive_name(name: &str) -> bool {
-    let lower = name.to_lowercase();
-    lower.ends_with(".zip")
-        || lower.ends_with(".tar.gz")
-        || lower.ends_with(".tgz")
-        || lower.ends_with(".tar.xz")
-        || lower.ends_with(".tar.bz2")
+#[derive(Debug, Serialize, Deserialize)]
+struct LockEntry {
+    repo: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sha512: Option<String>,
 }
 
-fn is_gzip_name(name: &str) -> bool {
-    let lower = name.to_lowercase();
-    lower.ends_with(".gz") && !lower.ends_with(".tar.gz")
+/// Hashes yoink can record and check binaries with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DigestAlgo {
+    #[default]
+    Sha256,
+    Sha512,
 }
 
-fn asset_stem(name: &str) -> &str {
-    let lower = name.to_lowercase();
-    if lower.ends_with(".tar.gz") {
-        return &name[..name.len().saturating_sub(7)];
-    }
-    if lower.ends_with(".tar.xz") {
-        return &name[..name.len().saturating_sub(7)];
-    }
-    if lower.ends_with(".tar.bz2") {
-        return &name[..name.len().saturating_sub(8)];
-    }
-    if lower.ends_with(".zip") {
-        return &name[..name.len().saturating_sub(4)];
-    }
-    if lower.ends_with(".tgz") {
-        return &name[..name.len().saturating_sub(4)];
-    }
-    if lower.ends_with(".gz") {
-        return &name[..name.len().saturating_sub(3)];
-    }
-    if lower.ends_with(".exe") {
-        return &name[..name.len().saturating_sub(4)];
+impl DigestAlgo {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+        }
     }
-    name
 }
 
-fn download_asset(client: &Client, url: &str, dest: &Path) -> Result<()> {
-    let mut response = client
-        .get(url)
-        .send()
-        .with_context(|| format!("download asset {url}"))?
-        .error_for_status()
-        .with_context(|| format!("bad download response {url}"))?;
-    let mut file = fs::File::create(dest)
-        .with_context(|| format!("create download file {}", dest.display()))?;
-    io::copy(&mut response, &mut file)
-        .with_context(|| format!("write download to {}", dest.display()))?;
-    Ok(())
-}
+impl std::str::FromStr for DigestAlgo {
+    type Err = anyhow::Error;
 
-struct ExtractedPaths {
-    primary: PathBuf,
-    extras: Vec<PathBuf>,
-    _temp_dir: TempDir,
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "sha256" => Ok(Self::Sha256),
+            "sha512" => Ok(Self::Sha512),
+            _ => bail!("unknown digest algorithm {value}; expected sha256 or sha512"),
+        }
+    }
 }
 
-fn extract_archive(archive_path: &Path, repo_name: &str) -> Result<ExtractedPaths> {
-    let temp_dir = tempfile::tempdir().context("create extract dir")?;
-    let extract_root = temp_dir.path();
+fn read_lockfile(path: &Path) -> Result<Lockfile> {
+    let contents = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    toml::from_str(&contents).with_context(|| format!("parse lockfile {}", path.display()))
+}
 
-    let name = archive_path
-        .file_name()
-        .and_then(OsStr::to_str)
-        .unwrap_or("")
-        .to_lowercase();
+fn sha256_file(path: &Path) -> Result<String> {
+    file_digest(path, DigestAlgo::Sha256)
+}
 
-    if name.ends_with(".zip") {
-        extract_zip(archive_path, extract_root)?;
-    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
-        extract_tar_gz(archive_path, extract_root)?;
-    } else if name.ends_with(".tar.xz") {
-        extract_tar_xz(archive_path, extract_root)?;
-    } else if name.ends_with(".tar.bz2") {
-        extract_tar_bz2(archive_path, extract_root)?;
-    } else {
-        bail!("unsupported archive format: {}", archive_path.display());
+fn file_digest(path: &Path, algo: DigestAlgo) -> Result<String> {
+    fn hash<D: Digest + io::Write>(file: &mut fs::File, path: &Path) -> Result<String> {
+        let mut hasher = D::new();
+        io::copy(file, &mut hasher).with_context(|| format!("hash {}", path.display()))?;
+        let digest = hasher.finalize();
+        Ok(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+    let mut file = fs::File::open(path).with_context(|| format!("open {}", path.display()))?;
+    match algo {
+        DigestAlgo::Sha256 => hash::<Sha256>(&mut file, path),
+        DigestAlgo::Sha512 => hash::<Sha512>(&mut file, path),
     }
-
-    let (primary, extras) = find_binaries(extract_root, repo_name)?;
-    Ok(ExtractedPaths {
-        primary,
-        extras,
-        _temp_dir: temp_dir,
-    })
 }
 
-fn extract_gzip(gzip_path: &Path, repo_name: &str) -> Result<ExtractedPaths> {
-    let temp_dir = tempfile::tempdir().context("create extract dir")?;
-    let extract_root = temp_dir.path();
+fn verify_locked(lockfile: &Path, repo: &str, binary: &Path, algo: DigestAlgo) -> Result<()> {
+    let lock = read_lockfile(lockfile)?;
+    let entry = lock
+        .entries
+        .iter()
+        .find(|entry| entry.repo.eq_ignore_ascii_case(repo))
+        .with_context(|| format!("{repo} is not in {}", lockfile.display()))?;
+    let expected = match algo {
+        DigestAlgo::Sha256 => &entry.sha256,
+        DigestAlgo::Sha512 => &entry.sha512,
+    };
+    let name = algo.name();
+    let expected = expected
+        .as_deref()
+        .map(str::trim)
+        .with_context(|| format!("{repo} has no {name} in {}", lockfile.display()))?;
+    let actual = file_digest(binary, algo)?;
+    if !actual.eq_ignore_ascii_case(expected) {
+        bail!(
+            "{repo}: {name} {actual} does not match {expected} in {}; refusing to run",
+            lockfile.display()
+        );
+    }
+    Ok(())
+}
 
-    let filename = gzip_path
-        .file_name()
-        .and_then(OsStr::to_str)
-        .unwrap_or("download");
-    let dest_name = filename.trim_end_matches(".gz");
-    let dest = extract_root.join(dest_name);
+/// Repo exercised by `self_test` unless `YOINK_SELFTEST_REPO` names another.
+const SELF_TEST_REPO: &str = "charmbracelet/gum";
 
-    let mut input =
-        fs::File::open(gzip_path).with_context(|| format!("open {}", gzip_path.display()))?;
-    let mut decoder = flate2::read::GzDecoder::new(&mut input);
-    let mut output =
-        fs::File::create(&dest).with_context(|| format!("create {}", dest.display()))?;
-    io::copy(&mut decoder, &mut output).with_context(|| format!("write {}", dest.display()))?;
+/// Downloads a known tool into a temp dir and checks that `--version` runs, exercising the
+/// whole resolve/download/extract path. Returns `owner/repo@tag` of what was tested.
+pub fn self_test() -> Result<String, YoinkError> {
+    let repo = env::var("YOINK_SELFTEST_REPO")
+        .ok()
+        .unwrap_or_else(|| SELF_TEST_REPO.to_string());
+    let temp_dir = tempfile::tempdir().context("create self-test dir")?;
+    let summary = download_to_dir(&repo, temp_dir.path())?;
+    let status = Command::new(&summary.primary_path)
+        .arg("--version")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .with_context(|| format!("run {}", summary.primary_path.display()))?;
+    if !status.success() {
+        return Err(anyhow!(
+            "{} --version exited with {}",
+            summary.primary_path.display(),
+            exit_status_code(status)
+        )
+        .into());
+    }
+    Ok(format!("{}@{}", summary.repo, summary.tag))
+}
 
-    let (primary, extras) = if dest_name == repo_name {
-        (dest, Vec::new())
-    } else {
-        find_binaries(extract_root, repo_name)?
-    };
+#[derive(Debug)]
+pub struct InstallSummary {
+    pub repo: String,
+    pub version: String,
+    /// Seconds since the Unix epoch; absent for installs recorded before timestamps were kept.
+    pub installed_at: Option<u64>,
+    /// The primary binary recorded at install time.
+    pub bin: PathBuf,
+    /// Extra binaries installed alongside `bin`.
+    pub bins: Vec<PathBuf>,
+}
 
-    Ok(ExtractedPaths {
-        primary,
-        extras,
-        _temp_dir: temp_dir,
-    })
+pub fn list_installs() -> Result<Vec<InstallSummary>, YoinkError> {
+    let state = load_state()?;
+    let mut installs = Vec::new();
+    for (repo, entry) in state.installs {
+        installs.push(InstallSummary {
+            repo,
+            version: display_version(&entry.version).to_string(),
+            installed_at: entry.installed_at,
+            bin: entry.bin,
+            bins: entry.bins,
+        });
+    }
+    Ok(installs)
 }
 
-fn extract_zip(archive_path: &Path, dest: &Path) -> Result<()> {
-    let file =
-        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
-    let mut archive = zip::ZipArchive::new(file)
-        .with_context(|| format!("open zip {}", archive_path.display()))?;
+/// Policies for a download destination that already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DestExists {
+    Error,
+    #[default]
+    Overwrite,
+    Skip,
+    /// Rename the existing file to `name.bak` (or `name.<timestamp>.bak`) first.
+    Backup,
+    /// Keep another repo's file and write this one as `name-owner` instead. A repo's own
+    /// earlier download, or an identical file, is replaced in place.
+    Rename,
+}
 
-    for i in 0..archive.len() {
-        let mut entry = archive
-            .by_index(i)
-            .with_context(|| format!("read zip entry {i}"))?;
-        let out_path = dest.join(entry.mangled_name());
-        if entry.is_dir() {
-            fs::create_dir_all(&out_path)
-                .with_context(|| format!("create {}", out_path.display()))?;
-            continue;
-        }
-        if let Some(parent) = out_path.parent() {
-            fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+impl std::str::FromStr for DestExists {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "error" => Ok(Self::Error),
+            "overwrite" => Ok(Self::Overwrite),
+            "skip" => Ok(Self::Skip),
+            "backup" => Ok(Self::Backup),
+            "rename" => Ok(Self::Rename),
+            _ => {
+                bail!("unknown policy {value}; expected error, overwrite, skip, backup, or rename")
+            }
         }
-        let mut outfile = fs::File::create(&out_path)
-            .with_context(|| format!("create {}", out_path.display()))?;
-        io::copy(&mut entry, &mut outfile)
-            .with_context(|| format!("write {}", out_path.display()))?;
     }
-
-    Ok(())
 }
 
-fn extract_tar_gz(archive_path: &Path, dest: &Path) -> Result<()> {
-    let file =
-        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
-    let decoder = flate2::read::GzDecoder::new(file);
-    let mut archive = tar::Archive::new(decoder);
-    archive
-        .unpack(dest)
-        .with_context(|| format!("unpack {}", archive_path.display()))
+/// Orderings for `list_installs` output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListSort {
+    #[default]
+    Name,
+    Version,
+    InstalledAt,
 }
 
-fn extract_tar_xz(archive_path: &Path, dest: &Path) -> Result<()> {
-    let file =
-        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
-    let decoder = xz2::read::XzDecoder::new(file);
-    let mut archive = tar::Archive::new(decoder);
-    archive
-        .unpack(dest)
-        .with_context(|| format!("unpack {}", archive_path.display()))
-}
+impl std::str::FromStr for ListSort {
+    type Err = anyhow::Error;
 
-fn extract_tar_bz2(archive_path: &Path, dest: &Path) -> Result<()> {
-    let file =
-        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
-    let decoder = bzip2::read::BzDecoder::new(file);
-    let mut archive = tar::Archive::new(decoder);
-    archive
-        .unpack(dest)
-        .with_context(|| format!("unpack {}", archive_path.display()))
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "name" => Ok(Self::Name),
+            "version" => Ok(Self::Version),
+            "installed-at" => Ok(Self::InstalledAt),
+            _ => bail!("unknown sort {value}; expected name, version, or installed-at"),
+        }
+    }
 }
 
-fn find_binaries(root: &Path, repo_name: &str) -> Result<(PathBuf, Vec<PathBuf>)> {
-    let target = binary_name(repo_name).to_lowercase();
-    let fallback = repo_name.to_lowercase();
+/// What a bare `yoink owner/repo` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DefaultAction {
+    /// Download into the current directory.
+    #[default]
+    Download,
+    /// Install into the install dir and record it.
+    Install,
+}
 
-    let mut exact_matches = Vec::new();
-    let mut candidates = Vec::new();
-    let mut probable_matches = Vec::new();
+impl std::str::FromStr for DefaultAction {
+    type Err = anyhow::Error;
 
-    for entry in WalkDir::new(root) {
-        let entry = entry.context("walk archive")?;
-        if !entry.file_type().is_file() {
-            continue;
-        }
-        let path = entry.path();
-        let name = path
-            .file_name()
-            .and_then(OsStr::to_str)
-            .unwrap_or("")
-            .to_lowercase();
-        if name == target || name == fallback {
-            exact_matches.push(path.to_path_buf());
-        }
-        candidates.push(path.to_path_buf());
-        if is_probable_binary_candidate(path) {
-            probable_matches.push(path.to_path_buf());
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "download" => Ok(Self::Download),
+            "install" => Ok(Self::Install),
+            _ => bail!("unknown default action {value}; expected download or install"),
         }
     }
+}
 
-    let primary = if exact_matches.len() == 1 {
-        exact_matches.remove(0)
-    } else if exact_matches.len() > 1 {
-        exact_matches.sort_by_key(|path| path.to_string_lossy().len());
-        exact_matches.remove(0)
-    } else if probable_matches.len() == 1 {
-        probable_matches[0].clone()
-    } else if probable_matches.len() > 1 {
-        let mut bin_matches: Vec<PathBuf> = probable_matches
-            .iter()
-            .filter(|path| path_has_component(path, "bin"))
-            .cloned()
-            .collect();
-        if bin_matches.len() == 1 {
-            bin_matches.remove(0)
-        } else {
-            let mut sorted = probable_matches.clone();
-            sorted.sort_by_key(|path| path.to_string_lossy().len());
-            sorted.remove(0)
-        }
-    } else if candidates.len() == 1 {
-        candidates.remove(0)
-    } else {
-        bail!("unable to locate extracted binary");
+/// `YOINK_DEFAULT_ACTION`, else `default-action` in `config.toml`, else `Download`.
+pub fn default_action() -> Result<DefaultAction, YoinkError> {
+    let configured = match env::var("YOINK_DEFAULT_ACTION").ok() {
+        Some(value) => Some(value),
+        None => load_config(&Options::default())?.default_action,
     };
+    match configured {
+        Some(value) => Ok(value.parse()?),
+        None => Ok(DefaultAction::default()),
+    }
+}
 
-    let mut extras = Vec::new();
-    if !probable_matches.is_empty() {
-        extras = probable_matches
-            .into_iter()
-            .filter(|path| *path != primary)
-            .collect();
-        extras.sort_by_key(|path| path.to_string_lossy().len());
+/// Stable-sorts installs, oldest version or install first; ties keep repo-name order.
+pub fn sort_installs(installs: &mut [InstallSummary], sort: ListSort) {
+    match sort {
+        ListSort::Name => installs.sort_by(|a, b| a.repo.cmp(&b.repo)),
+        ListSort::Version => installs.sort_by(|a, b| compare_versions(&a.version, &b.version)),
+        ListSort::InstalledAt => installs.sort_by_key(|install| install.installed_at),
     }
+}
 
-    Ok((primary, extras))
+#[derive(Debug)]
+pub struct UpgradeSummary {
+    pub repo: String,
+    pub version: String,
+    pub path: PathBuf,
+    pub used_sudo: bool,
 }
 
-fn path_has_component(path: &Path, needle: &str) -> bool {
-    path.components().any(|component| {
-        component
-            .as_os_str()
-            .to_str()
-            .map(|segment| segment.eq_ignore_ascii_case(needle))
-            .unwrap_or(false)
-    })
+/// What `upgrade_all` did: the repos it upgraded and, separately, the ones that failed.
+#[derive(Debug, Default)]
+pub struct UpgradeReport {
+    pub upgraded: Vec<UpgradeSummary>,
+    /// Each repo that couldn't be upgraded, with why, sorted by repo.
+    pub failed: Vec<(String, YoinkError)>,
 }
 
-fn is_probable_binary_candidate(path: &Path) -> bool {
-    let name = match path.file_name().and_then(OsStr::to_str) {
-        Some(name) => name.to_lowercase(),
-        None => return false,
-    };
+/// How many repos batch operations work on at once unless `max_parallel_repos` is set.
+const DEFAULT_REPO_JOBS: usize = 4;
 
-    if name.starts_with('.')
-        || name.starts_with("readme")
-        || name.starts_with("license")
-        || name.starts_with("changelog")
-        || name.starts_with("notice")
-        || name.starts_with("copying")
-    {
-        return false;
-    }
+fn repo_jobs(options: &Options) -> usize {
+    options
+        .max_parallel_repos
+        .unwrap_or(DEFAULT_REPO_JOBS)
+        .max(1)
+}
 
-    if path_has_component(path, "share")
-        || path_has_component(path, "doc")
-        || path_has_component(path, "docs")
-        || path_has_component(path, "man")
-        || path_has_component(path, "completions")
-        || path_has_component(path, "completion")
-    {
-        return false;
-    }
+/// Runs `job` over `items` on at most `jobs` threads; results keep the order of `items`.
+fn bounded_map<T: Sync, R: Send>(items: &[T], jobs: usize, job: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let mut done = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.min(items.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, atomic::Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            break done;
+                        };
+                        done.push((index, job(item)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| match worker.join() {
+                Ok(done) => done,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect::<Vec<_>>()
+    });
+    done.sort_by_key(|(index, _)| *index);
+    done.into_iter().map(|(_, result)| result).collect()
+}
 
-    if let Some(ext) = Path::new(&name).extension().and_then(OsStr::to_str) {
-        let ext = ext.to_lowercase();
-        if matches!(
-            ext.as_str(),
-            "md" | "txt"
-                | "rst"
-                | "json"
-                | "yaml"
-                | "yml"
-                | "toml"
-                | "ini"
-                | "cfg"
-                | "conf"
-                | "1"
-                | "2"
-                | "3"
-                | "4"
-                | "5"
-                | "6"
-                | "7"
-                | "8"
-                | "9"
-                | "asc"
-                | "sig"
-                | "sha256"
-                | "sha512"
-                | "md5"
-        ) {
-            return false;
+/// Upgrades every unpinned install. Downloads run in parallel; installs and state writes
+/// happen one at a time. A failing repo doesn't stop the others; its error lands in the
+/// report's `failed` list next to the repos that did upgrade.
+pub fn upgrade_all() -> Result<UpgradeReport, YoinkError> {
+    upgrade_all_with(&Options::default())
+}
+
+/// `upgrade_all`, preparing at most `Options::max_parallel_repos` repos at once.
+pub fn upgrade_all_with(options: &Options) -> Result<UpgradeReport, YoinkError> {
+    let state = load_state()?;
+    // Each upgrade lands where, and how, the repo was installed before.
+    let (repos, targets): (Vec<String>, Vec<(Option<PathBuf>, Options)>) = state
+        .installs
+        .iter()
+        .filter(|(_, entry)| !entry.pinned)
+        .map(|(repo, entry)| {
+            let install_dir = entry.install_dir().map(Path::to_path_buf);
+            let options = Options {
+                store: entry.store_path.is_some(),
+                host: entry.host.clone(),
+                ..options.clone()
+            };
+            (repo.clone(), (install_dir, options))
+        })
+        .unzip();
+
+    let jobs: Vec<(&String, &Options)> = repos
+        .iter()
+        .zip(targets.iter().map(|(_, options)| options))
+        .collect();
+    let prepared = bounded_map(&jobs, repo_jobs(options), |(repo, options)| {
+        prepare_binary(repo, options)
+    });
+
+    let mut report = UpgradeReport::default();
+    for (index, result) in prepared.into_iter().enumerate() {
+        let repo = &repos[index];
+        let install = |prepared| {
+            let (install_dir, options) = &targets[index];
+            let install_dir = match install_dir {
+                Some(dir) => dir.clone(),
+                None => default_install_dir(options)?,
+            };
+            install_prepared(prepared, false, &install_dir, options)
+        };
+        match result.and_then(install) {
+            Ok(install) => report.upgraded.push(UpgradeSummary {
+                repo: repo.clone(),
+                version: display_version(&install.version).to_string(),
+                path: install.path,
+                used_sudo: install.used_sudo,
+            }),
+            Err(err) => report.failed.push((repo.clone(), err.into())),
         }
     }
+    Ok(report)
+}
 
-    true
+#[derive(Debug)]
+pub struct ReleaseNotes {
+    pub tag: String,
+    pub body: String,
 }
 
-fn install_payload(payload_path: &Path, dest: &Path) -> Result<()> {
-    if let Err(err) = install_binary(payload_path, dest) {
-        if is_permission_denied(&err) {
-            install_with_sudo(payload_path, dest)?;
-        } else {
-            return Err(err);
-        }
-    }
-    Ok(())
+/// Release notes for every release newer than the installed version of `repo`, newest first.
+pub fn release_notes_since_installed(repo: &str) -> Result<Vec<ReleaseNotes>, YoinkError> {
+    let (owner, name, _tag) = parse_repo(repo, &Options::default())?;
+    let key = state_key(repo, &Options::default())?;
+    let state = load_state()?;
+    let entry = state
+        .installs
+        .get(&key)
+        .with_context(|| format!("{key} not installed"))?;
+    let installed = entry.version.clone();
+
+    let options = Options {
+        host: entry.host.clone(),
+        ..Options::default()
+    };
+    let forge = repo_forge(&key, &options);
+    let client = forge.client(&owner, &options)?;
+    let mut notes: Vec<ReleaseNotes> = forge
+        .fetch_releases(&client, &owner, &name, &options)?
+        .into_iter()
+        .filter_map(|release| {
+            let tag = release.tag_name?;
+            if compare_versions(&tag, &installed) != Ordering::Greater {
+                return None;
+            }
+            Some(ReleaseNotes {
+                tag,
+                body: release.body.unwrap_or_default(),
+            })
+        })
+        .collect();
+    notes.sort_by(|a, b| compare_versions(&b.tag, &a.tag));
+    Ok(notes)
 }
 
-fn default_install_dir() -> Result<PathBuf> {
-    if let Ok(dir) = env::var("YOINKDIR") {
-        return Ok(PathBuf::from(dir));
-    }
-    if let Ok(dir) = env::var("YOINK_BIN_DIR") {
-        return Ok(PathBuf::from(dir));
-    }
-    if cfg!(windows) {
-        let base = dirs_next::data_local_dir().context("determine local data dir")?;
-        return Ok(base.join("Programs").join("yoink").join("bin"));
-    }
-    let home = env::var("HOME")
-        .ok()
-        .map(PathBuf::from)
-        .or_else(|| dirs_next::home_dir())
-        .context("determine home dir")?;
-    Ok(home.join(".local").join("bin"))
+pub fn uninstall(repo: &str) -> Result<(), YoinkError> {
+    uninstall_with(repo, true)
 }
 
-fn binary_name(repo_name: &str) -> String {
-    if cfg!(windows) {
-        format!("{repo_name}.exe")
-    } else {
-        repo_name.to_string()
-    }
+/// Removes an install's binaries and state entry; with `prune_store`, also every version of
+/// it kept in the store.
+/// `repo` may also be the name of an installed binary, e.g. `gh` for `cli/cli`.
+pub fn uninstall_with(repo: &str, prune_store: bool) -> Result<(), YoinkError> {
+    let key = match state_key(repo, &Options::default()) {
+        // A bare name that `YOINK_DEFAULT_OWNER` turned into a repo may still be a binary.
+        Ok(key) if repo.contains('/') || load_state()?.installs.contains_key(&key) => key,
+        _ => repo_for_bin(repo)?,
+    };
+    Ok(remove_install(&key, prune_store)?)
 }
 
-fn set_executable(path: &Path) -> Result<()> {
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(path)
-            .with_context(|| format!("stat {}", path.display()))?
-            .permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(path, perms).with_context(|| format!("chmod {}", path.display()))?;
+/// The one installed repo providing a binary named `bin`.
+fn repo_for_bin(bin: &str) -> Result<String> {
+    let state = load_state()?;
+    let provides = |path: &PathBuf| {
+        let name = path.file_name().and_then(OsStr::to_str).unwrap_or("");
+        name == bin || (cfg!(windows) && name.strip_suffix(".exe") == Some(bin))
+    };
+    let repos: Vec<&String> = state
+        .installs
+        .iter()
+        .filter(|(_, entry)| entry.all_bins().any(provides))
+        .map(|(repo, _)| repo)
+        .collect();
+    match repos.as_slice() {
+        [] => bail!("no installed repo provides {bin}"),
+        [repo] => Ok(repo.to_string()),
+        _ => bail!(
+            "{bin} is provided by {}; pass the full owner/repo",
+            repos
+                .iter()
+                .map(|repo| repo.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
     }
-    Ok(())
 }
 
-fn install_binary(payload_path: &Path, dest: &Path) -> Result<()> {
-    fs::copy(payload_path, dest).with_context(|| format!("copy to {}", dest.display()))?;
-    set_executable(dest)?;
-    Ok(())
+/// Binaries recorded for an installed repo.
+#[derive(Debug)]
+pub struct InstalledBins {
+    pub repo: String,
+    pub primary: PathBuf,
+    /// Every installed binary, primary first.
+    pub bins: Vec<PathBuf>,
 }
 
-fn install_with_sudo(payload_path: &Path, dest: &Path) -> Result<()> {
-    #[cfg(unix)]
-    {
-        set_executable(payload_path)?;
-        let status = Command::new("sudo")
-            .arg("mv")
-            .arg("--")
-            .arg(payload_path)
-            .arg(dest)
-            .status()
-            .with_context(|| {
-                format!("run sudo mv {} {}", payload_path.display(), dest.display())
-            })?;
-        if !status.success() {
-            bail!("sudo mv failed with status {}", status);
-        }
-        Ok(())
-    }
-
-    #[cfg(not(unix))]
-    {
-        let _ = payload_path;
-        let _ = dest;
-        bail!("install location requires permissions not supported on this platform");
-    }
-}
-
-fn is_permission_denied(err: &anyhow::Error) -> bool {
-    err.chain().any(|cause| {
-        cause
-            .downcast_ref::<io::Error>()
-            .map(|io_err| io_err.kind() == io::ErrorKind::PermissionDenied)
-            .unwrap_or(false)
+pub fn installed_bins(repo: &str) -> Result<InstalledBins, YoinkError> {
+    let key = state_key(repo, &Options::default())?;
+    let state = load_state()?;
+    let entry = state
+        .installs
+        .get(&key)
+        .with_context(|| format!("{key} not installed"))?;
+    Ok(InstalledBins {
+        primary: entry.bin.clone(),
+        bins: entry.all_bins().cloned().collect(),
+        repo: key,
     })
 }
 
-fn ensure_install_dir(install_dir: &Path) -> Result<()> {
-    if let Err(err) = fs::create_dir_all(install_dir) {
-        if err.kind() == io::ErrorKind::PermissionDenied {
-            create_dir_with_sudo(install_dir)
-                .with_context(|| format!("create install dir {}", install_dir.display()))?;
-            return Ok(());
-        }
-        return Err(err).with_context(|| format!("create install dir {}", install_dir.display()));
-    }
-    Ok(())
-}
-
-fn create_dir_with_sudo(install_dir: &Path) -> Result<()> {
-    #[cfg(unix)]
-    {
-        let status = Command::new("sudo")
-            .arg("mkdir")
-            .arg("-p")
-            .arg("--")
-            .arg(install_dir)
-            .status()
-            .with_context(|| format!("run sudo mkdir -p {}", install_dir.display()))?;
-        if !status.success() {
-            bail!("sudo mkdir failed with status {}", status);
-        }
-        Ok(())
-    }
-
-    #[cfg(not(unix))]
-    {
-        let _ = install_dir;
-        bail!("install location requires permissions not supported on this platform");
-    }
-}
-
-#[derive(Debug, Default, Serialize, Deserialize)]
-struct InstallState {
-    #[serde(default)]
-    installs: BTreeMap<String, InstallEntry>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct InstallEntry {
-    version: String,
-    bin: PathBuf,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    bins: Vec<PathBuf>,
-}
-
-impl InstallEntry {
-    fn all_bins(&self) -> impl Iterator<Item = &PathBuf> {
-        std::iter::once(&self.bin).chain(self.bins.iter())
-    }
-}
-
-fn record_install(repo: &str, version: &str, bins: &[PathBuf]) -> Result<()> {
+/// Moves every tracked binary into `new_dir` and points the install state at the new paths.
+/// Returns the new paths.
+pub fn migrate_dir(new_dir: &Path) -> Result<Vec<PathBuf>, YoinkError> {
     let state_path = state_path()?;
-    if let Some(parent) = state_path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("create state dir {}", parent.display()))?;
+    if !state_path.exists() {
+        return Ok(Vec::new());
     }
+    fs::create_dir_all(new_dir).with_context(|| format!("create {}", new_dir.display()))?;
 
     let mut file = fs::OpenOptions::new()
         .read(true)
         .write(true)
-        .create(true)
         .open(&state_path)
         .with_context(|| format!("open state file {}", state_path.display()))?;
     file.lock_exclusive()
         .with_context(|| format!("lock state file {}", state_path.display()))?;
 
-    let mut state = read_state_locked(&mut file)?;
-    let (primary, extras) = bins
-        .split_first()
-        .context("record install without binaries")?;
-    state.installs.insert(
-        repo.to_string(),
-        InstallEntry {
-            version: version.to_string(),
-            bin: primary.to_path_buf(),
-            bins: extras.to_vec(),
-        },
-    );
-    write_state_locked(&mut file, &state)?;
+    let mut state = read_state_locked(&mut file, StateFormat::of(&state_path))?;
+    let mut moved = Vec::new();
+    // Record every move that succeeded, even if a later one fails, so state matches disk.
+    let result = state.installs.values_mut().try_for_each(|entry| {
+        entry.bin = migrate_path(&entry.bin, new_dir)?;
+        entry.install_dir = Some(new_dir.to_path_buf());
+        moved.push(entry.bin.clone());
+        for bin in &mut entry.bins {
+            *bin = migrate_path(bin, new_dir)?;
+            moved.push(bin.clone());
+        }
+        Ok::<(), anyhow::Error>(())
+    });
+    write_state_locked(&mut file, &state, StateFormat::of(&state_path))?;
     file.unlock()
         .with_context(|| format!("unlock state file {}", state_path.display()))?;
-    Ok(())
+    Ok(result.map(|()| moved)?)
+}
+
+/// An install whose recorded binaries no longer match what's on disk.
+#[derive(Debug)]
+pub struct StaleInstall {
+    pub repo: String,
+    pub missing: Vec<PathBuf>,
+    /// The primary binary exists but has lost its executable bit.
+    pub not_executable: Vec<PathBuf>,
+    /// The primary binary is gone, so `doctor` with `fix` dropped the entry.
+    pub pruned: bool,
 }
 
-fn remove_install(repo: &str) -> Result<()> {
+/// Checks every recorded install's binaries. With `fix`, drops entries whose primary binary
+/// is gone from the install state, along with any dangling links left in its place.
+pub fn doctor(fix: bool) -> Result<Vec<StaleInstall>, YoinkError> {
     let state_path = state_path()?;
     if !state_path.exists() {
-        bail!("no installs recorded");
+        return Ok(Vec::new());
     }
-
     let mut file = fs::OpenOptions::new()
         .read(true)
         .write(true)
@@ -953,688 +1946,9171 @@ fn remove_install(repo: &str) -> Result<()> {
     file.lock_exclusive()
         .with_context(|| format!("lock state file {}", state_path.display()))?;
 
-    let mut state = read_state_locked(&mut file)?;
-    let entry = state
-        .installs
-        .remove(repo)
-        .with_context(|| format!("{} not installed", repo))?;
-
-    for bin in entry.all_bins() {
-        let result = if bin.is_symlink() {
-            fs::remove_file(bin)
-        } else if bin.is_dir() {
-            fs::remove_dir_all(bin)
-        } else {
-            fs::remove_file(bin)
-        };
-        match result {
-            Ok(()) => {}
-            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
-            Err(err) => {
-                file.unlock()
-                    .with_context(|| format!("unlock state file {}", state_path.display()))?;
-                return Err(err).with_context(|| format!("remove {}", bin.display()));
+    let mut state = read_state_locked(&mut file, StateFormat::of(&state_path))?;
+    let mut stale = Vec::new();
+    for (repo, entry) in &state.installs {
+        // Following links, so a link whose target is gone counts as missing too.
+        let missing: Vec<PathBuf> = entry
+            .all_bins()
+            .filter(|bin| fs::metadata(bin).is_err())
+            .cloned()
+            .collect();
+        let not_executable: Vec<PathBuf> = Some(&entry.bin)
+            .filter(|bin| bin.exists() && !is_executable(bin))
+            .cloned()
+            .into_iter()
+            .collect();
+        if missing.is_empty() && not_executable.is_empty() {
+            continue;
+        }
+        stale.push(StaleInstall {
+            repo: repo.clone(),
+            pruned: fix && missing.contains(&entry.bin),
+            missing,
+            not_executable,
+        });
+    }
+    if stale.iter().any(|install| install.pruned) {
+        for install in stale.iter().filter(|install| install.pruned) {
+            state.installs.remove(&install.repo);
+            // Nothing would ever clean up a dangling link once its entry is gone.
+            for path in &install.missing {
+                if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+                    let _ = fs::remove_file(path);
+                }
             }
         }
+        write_state_locked(&mut file, &state, StateFormat::of(&state_path))?;
     }
-
-    write_state_locked(&mut file, &state)?;
     file.unlock()
         .with_context(|| format!("unlock state file {}", state_path.display()))?;
-    Ok(())
+    Ok(stale)
 }
 
-fn load_state() -> Result<InstallState> {
-    let state_path = state_path()?;
-    if !state_path.exists() {
-        return Ok(InstallState::default());
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
     }
+}
 
-    let mut file = fs::File::open(&state_path)
-        .with_context(|| format!("open state file {}", state_path.display()))?;
-    file.lock_shared()
-        .with_context(|| format!("lock state file {}", state_path.display()))?;
-    let state = read_state_locked(&mut file)?;
-    file.unlock()
-        .with_context(|| format!("unlock state file {}", state_path.display()))?;
-    Ok(state)
+fn migrate_path(from: &Path, new_dir: &Path) -> Result<PathBuf> {
+    let name = from
+        .file_name()
+        .with_context(|| format!("{} has no filename", from.display()))?;
+    let to = new_dir.join(name);
+    if to == from {
+        return Ok(to);
+    }
+    if from.is_symlink() {
+        // Re-point links at their resolved target so relative links keep working.
+        let target = fs::read_link(from).with_context(|| format!("read {}", from.display()))?;
+        let target = match from.parent() {
+            Some(parent) if target.is_relative() => parent.join(target),
+            _ => target,
+        };
+        create_symlink(&target, &to)?;
+        fs::remove_file(from).with_context(|| format!("remove {}", from.display()))?;
+        return Ok(to);
+    }
+    if fs::rename(from, &to).is_err() {
+        // Most likely a cross-filesystem move; fall back to copy and delete.
+        fs::copy(from, &to)
+            .with_context(|| format!("copy {} to {}", from.display(), to.display()))?;
+        fs::remove_file(from).with_context(|| format!("remove {}", from.display()))?;
+    }
+    Ok(to)
 }
 
-fn read_state_locked(file: &mut fs::File) -> Result<InstallState> {
-    file.seek(SeekFrom::Start(0)).context("seek state file")?;
-    let mut buf = String::new();
-    file.read_to_string(&mut buf).context("read state file")?;
-    let state = if buf.trim().is_empty() {
-        InstallState::default()
-    } else {
-        serde_json::from_str(&buf).context("parse state json")?
-    };
-    Ok(state)
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(target, link);
+    #[cfg(windows)]
+    let result = std::os::windows::fs::symlink_file(target, link);
+    result.with_context(|| format!("link {} to {}", link.display(), target.display()))
 }
 
-fn write_state_locked(file: &mut fs::File, state: &InstallState) -> Result<()> {
-    file.set_len(0).context("truncate state file")?;
-    file.seek(SeekFrom::Start(0)).context("seek state file")?;
-    serde_json::to_writer_pretty(&mut *file, state).context("write state json")?;
-    file.write_all(b"\n").context("write state newline")?;
-    file.sync_all().context("sync state file")?;
-    Ok(())
+/// `repo` without a leading `gitlab:` or `github:`.
+fn strip_forge_prefix(repo: &str) -> &str {
+    repo.strip_prefix(GITLAB_PREFIX)
+        .or_else(|| repo.strip_prefix("github:"))
+        .unwrap_or(repo)
 }
 
-fn state_path() -> Result<PathBuf> {
-    let base = dirs_next::data_dir()
-        .or_else(|| dirs_next::home_dir().map(|dir| dir.join(".local").join("share")))
-        .context("determine data dir")?;
-    Ok(base.join("yoink").join("installed.json"))
+/// The install-state key for `repo`: `owner/name` behind its forge's prefix, e.g.
+/// `gitlab:owner/name`.
+fn state_key(repo: &str, options: &Options) -> Result<String> {
+    let (owner, name, _tag) = parse_repo(repo, options)?;
+    Ok(format!(
+        "{}{owner}/{name}",
+        repo_forge(repo, options).repo_prefix()
+    ))
 }
 
-fn display_version(version: &str) -> &str {
-    if let Some(stripped) = version.strip_prefix('v') {
-        if stripped
-            .chars()
-            .next()
-            .map(|ch| ch.is_ascii_digit())
-            .unwrap_or(false)
+/// Splits `owner/name` or `owner/name@tag` into its parts.
+fn parse_repo(repo: &str, options: &Options) -> Result<(String, String, Option<String>)> {
+    let repo = strip_forge_prefix(repo);
+    let (repo, tag) = match repo.split_once('@') {
+        Some((repo, tag)) if !tag.is_empty() => (repo, Some(tag.to_string())),
+        Some(_) => bail!("expected a tag after @"),
+        None => (repo, None),
+    };
+    if !repo.is_empty() && !repo.contains('/') {
+        // A bare name belongs to `YOINK_DEFAULT_OWNER`, when one is set.
+        if let Some(owner) = options
+            .env("YOINK_DEFAULT_OWNER")
+            .filter(|owner| !owner.is_empty())
         {
-            return stripped;
+            return Ok((owner, repo.to_string(), tag));
         }
     }
-    version
+    let mut parts = repo.split('/');
+    let owner = parts.next().unwrap_or("");
+    let name = parts.next().unwrap_or("");
+    if owner.is_empty() || name.is_empty() || parts.next().is_some() {
+        bail!("expected repo in owner/name form")
+    }
+    Ok((owner.to_string(), name.to_string(), tag))
 }
 
-fn exit_status_code(status: ExitStatus) -> i32 {
-    if let Some(code) = status.code() {
-        return code;
+struct PreparedBinary {
+    /// Prepended to `owner/name` in the install state, so upgrades find the same forge.
+    repo_prefix: &'static str,
+    /// The forge host, recorded so upgrades of `--host` installs don't fall back to the default.
+    host: Option<String>,
+    owner: String,
+    name: String,
+    tag: String,
+    asset_name: String,
+    asset_url: String,
+    path: PathBuf,
+    extra_paths: Vec<PathBuf>,
+    /// From `verify_exec_format`, for the caller to show.
+    warnings: Vec<String>,
+    _download_dir: TempDir,
+    _extracted: Option<ExtractedPaths>,
+}
+
+impl PreparedBinary {
+    /// Where `path` came from: its path inside the archive, or the asset name when the asset
+    /// is the binary itself.
+    fn source(&self, path: &Path) -> PathBuf {
+        match &self._extracted {
+            Some(extracted) => path
+                .strip_prefix(extracted._temp_dir.path())
+                .unwrap_or(path)
+                .to_path_buf(),
+            None => PathBuf::from(&self.asset_name),
+        }
     }
-    #[cfg(unix)]
-    {
-        use std::os::unix::process::ExitStatusExt;
-        if let Some(signal) = status.signal() {
-            return 128 + signal;
+
+    fn install_man_pages(&self, man_dir: &Path) -> Result<Vec<PathBuf>> {
+        match &self._extracted {
+            Some(extracted) => install_man_pages(extracted._temp_dir.path(), man_dir),
+            None => Ok(Vec::new()),
         }
     }
-    1
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serial_test::serial;
-    use std::collections::BTreeMap;
-    use std::ffi::OsString;
-    use std::io::{BufRead, BufReader, Cursor, Write};
-    use std::net::TcpListener;
-    use std::thread;
-
-    #[test]
-    fn state_write_roundtrip() {
-        let mut file = tempfile::tempfile().expect("create temp file");
-        let mut installs = BTreeMap::new();
-        installs.insert(
-            "mxcl/yoink".to_string(),
-            InstallEntry {
-                version: "v0.1.0".to_string(),
-                bin: PathBuf::from("/tmp/yoink"),
-                bins: Vec::new(),
-            },
-        );
-        let state = InstallState { installs };
-
-        write_state_locked(&mut file, &state).expect("write state");
-        let read_back = read_state_locked(&mut file).expect("read state");
+fn prepare_binary(repo: &str, options: &Options) -> Result<PreparedBinary> {
+    let (owner, name, tag) = parse_repo(repo, options)?;
+    let forge = repo_forge(repo, options);
+    let client = forge.client(&owner, options)?;
+    let host = cache_host(&*forge);
+    // With the download cache on, remember what each pick resolved to, so a cached asset can
+    // be installed again without reaching the forge.
+    let info_key = match options.cache && !options.isolated {
+        true => Some(format!("cache:{host}/{}", release_info_key(repo, options)?)),
+        false => None,
+    };
+    let cached_info = match &info_key {
+        Some(key) if !options.refresh => cached_release_info(key, Duration::MAX)?,
+        _ => None,
+    };
+    let info = match cached_info {
+        // A tagged release doesn't move, so there's no need to ask again.
+        Some(info) if tag.is_some() => info,
+        cached_info => {
+            match (
+                resolve_release_info(&*forge, &client, &owner, &name, tag.as_deref(), options),
+                cached_info,
+            ) {
+                (Err(err), _) if options.build && is_assetless(&err) => {
+                    return build_from_source(
+                        &*forge,
+                        &client,
+                        &owner,
+                        &name,
+                        tag.as_deref(),
+                        options,
+                    );
+                }
+                // Offline, the last resolution is the best guess at the latest release.
+                (Err(err), Some(info)) if is_unreachable(&err) => info,
+                (info, _) => info?,
+            }
+        }
+    };
+    if let Some(key) = &info_key {
+        store_release_info(key, &info)?;
+    }
+    let mut prepared = prepare_release_asset(&client, info, &*forge, options)?;
+    prepared.repo_prefix = forge.repo_prefix();
+    prepared.host = forge.host().map(str::to_string);
+    Ok(prepared)
+}
 
-        assert_eq!(read_back.installs.len(), 1);
-        let entry = read_back.installs.get("mxcl/yoink").expect("entry");
-        assert_eq!(entry.version, "v0.1.0");
-        assert_eq!(entry.bin, PathBuf::from("/tmp/yoink"));
+/// Files at a repo's root that `--build` knows how to build from, in order of preference.
+const BUILD_FILES: &[&str] = &["Cargo.toml", "go.mod"];
+
+/// Clones the assetless release `tag` (else the latest) of `owner/name` and builds it with
+/// the first toolchain in `BUILD_FILES` the repo has a file for.
+fn build_from_source(
+    forge: &dyn Forge,
+    client: &Client,
+    owner: &str,
+    name: &str,
+    tag: Option<&str>,
+    options: &Options,
+) -> Result<PreparedBinary> {
+    if options.checksum_required {
+        bail!("{owner}/{name} has no release assets to verify, so it can't be built (--checksum-required)");
+    }
+    // Build exactly the release that was asked for, not whatever the default branch holds now.
+    let release = load_release(forge, client, owner, name, tag, options)?;
+    let tag = release
+        .tag_name
+        .with_context(|| format!("{owner}/{name}'s release has no tag to build"))?;
+    let tag = tag.as_str();
+    let mut build_file = None;
+    for file in BUILD_FILES {
+        if forge.has_file(client, owner, name, file, Some(tag), options)? {
+            build_file = Some(*file);
+            break;
+        }
     }
+    let Some(build_file) = build_file else {
+        bail!(
+            "{owner}/{name} has no release assets and no {} to build from",
+            BUILD_FILES.join(" or ")
+        );
+    };
 
-    #[test]
-    fn parse_repo_validates_shape() {
-        let (owner, name) = parse_repo("mxcl/yoink").expect("parse repo");
-        assert_eq!(owner, "mxcl");
-        assert_eq!(name, "yoink");
-        assert!(parse_repo("mxcl").is_err());
-        assert!(parse_repo("mxcl/yoink/extra").is_err());
-        assert!(parse_repo("/yoink").is_err());
+    let temp_dir = scratch_dir(options)?;
+    let checkout = temp_dir.path().join("src");
+    let url = forge.clone_url(owner, name, options);
+    let mut clone = Command::new("git");
+    clone.args(["clone", "--quiet", "--depth", "1", "--branch", tag]);
+    run_build_step(clone.arg(&url).arg(&checkout))?;
+
+    let out = temp_dir.path().join("out");
+    let bin_dir = out.join("bin");
+    match build_file {
+        "Cargo.toml" => {
+            let mut cargo = Command::new("cargo");
+            cargo.arg("install").arg("--path").arg(&checkout);
+            run_build_step(cargo.arg("--root").arg(&out))?;
+        }
+        _ => {
+            fs::create_dir_all(&bin_dir)
+                .with_context(|| format!("create {}", bin_dir.display()))?;
+            let mut go = Command::new("go");
+            go.arg("build")
+                .arg("-o")
+                .arg(bin_dir.join(binary_name(name)));
+            run_build_step(go.arg(".").current_dir(&checkout))?;
+        }
     }
+    let (path, extra_paths) = find_binaries(&bin_dir, name, options)?;
 
-    #[test]
+    Ok(PreparedBinary {
+        repo_prefix: forge.repo_prefix(),
+        host: forge.host().map(str::to_string),
+        owner: owner.to_string(),
+        name: name.to_string(),
+        tag: tag.to_string(),
+        asset_name: build_file.to_string(),
+        asset_url: url,
+        path,
+        extra_paths,
+        warnings: Vec::new(),
+        _download_dir: temp_dir,
+        _extracted: None,
+    })
+}
+
+/// Asks the contents API whether `file` exists at the root of `owner/name`.
+fn repo_has_file(
+    client: &Client,
+    owner: &str,
+    name: &str,
+    file: &str,
+    tag: Option<&str>,
+    options: &Options,
+) -> Result<bool> {
+    let mut url = format!(
+        "{}/repos/{owner}/{name}/contents/{file}",
+        github_api_base(options)
+    );
+    if let Some(tag) = tag {
+        url.push_str(&format!("?ref={tag}"));
+    }
+    let response = get_with_retry(client, &url, options)
+        .with_context(|| format!("look for {file} in {owner}/{name}"))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(false);
+    }
+    response
+        .error_for_status()
+        .with_context(|| format!("bad response looking for {file} in {owner}/{name}"))?;
+    Ok(true)
+}
+
+fn run_build_step(command: &mut Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command.status().with_context(|| format!("run {program}"))?;
+    if !status.success() {
+        bail!("{program} failed with status {status}");
+    }
+    Ok(())
+}
+
+fn github_git_base(options: &Options) -> String {
+    options
+        .env("YOINK_GITHUB_GIT_BASE")
+        .unwrap_or_else(|| "https://github.com".to_string())
+}
+
+fn prepare_release_asset(
+    client: &Client,
+    info: ReleaseInfo,
+    forge: &dyn Forge,
+    options: &Options,
+) -> Result<PreparedBinary> {
+    let configured;
+    let options = match &options.binary_path {
+        Some(_) => options,
+        None => match load_config(options)?
+            .extract
+            .remove(&format!("{}/{}", info.owner, info.name))
+        {
+            Some(binary_path) => {
+                configured = Options {
+                    binary_path: Some(binary_path),
+                    ..options.clone()
+                };
+                &configured
+            }
+            None => options,
+        },
+    };
+    let temp_dir = scratch_dir(options)?;
+    let fetched = fetch_payload(client, temp_dir.path(), &info, forge, options);
+    let (payload_path, extra_paths, extracted) = match fetched {
+        Ok(fetched) => fetched,
+        Err(err) => return Err(keep_temp_dir(err, temp_dir, options)),
+    };
+    let mut warnings = Vec::new();
+    for path in std::iter::once(&payload_path).chain(&extra_paths) {
+        match verify_exec_format(path, options) {
+            Ok(warning) => warnings.extend(warning),
+            Err(err) => return Err(keep_temp_dir(err, temp_dir, options)),
+        }
+    }
+
+    Ok(PreparedBinary {
+        repo_prefix: "",
+        host: None,
+        owner: info.owner,
+        name: info.name,
+        tag: info.tag,
+        asset_name: info.asset_name,
+        asset_url: info.asset_url,
+        path: payload_path,
+        extra_paths,
+        warnings,
+        _download_dir: temp_dir,
+        _extracted: extracted,
+    })
+}
+
+/// Downloads, verifies and unpacks the asset into `download_dir`.
+fn fetch_payload(
+    client: &Client,
+    download_dir: &Path,
+    info: &ReleaseInfo,
+    forge: &dyn Forge,
+    options: &Options,
+) -> Result<(PathBuf, Vec<PathBuf>, Option<ExtractedPaths>)> {
+    let (asset_name, name) = (info.asset_name.as_str(), info.name.as_str());
+    let download_path = download_dir.join(asset_name);
+    let checksum_required = options.checksum_required;
+    let unverifiable =
+        || anyhow!("no checksum to verify {asset_name} against (--checksum-required)");
+    if checksum_required && (info.checksum_url.is_none() || options.no_verify) {
+        return Err(unverifiable());
+    }
+    let cached = fetch_cached_asset(client, info, cache_host(forge), &download_path, options)?;
+    if let Some(checksum_url) = info.checksum_url.as_ref().filter(|_| !options.no_verify) {
+        let cached_list = cached.as_deref().map(checksum_cache_path);
+        let contents = fetch_checksum_list(client, checksum_url, cached_list.as_deref(), options)?;
+        // A shared checksums list may simply not cover this asset; only a listed hash is binding.
+        if let Some(expected) = expected_checksum(&contents, asset_name) {
+            if let Err(err) = verify_checksum(&download_path, &expected) {
+                // Don't keep handing out a bad copy; the next run downloads afresh.
+                if let Some(cached) = &cached {
+                    let _ = fs::remove_file(cached);
+                }
+                if let Some(cached_list) = &cached_list {
+                    let _ = fs::remove_file(cached_list);
+                }
+                return Err(err.into());
+            }
+        } else if checksum_required {
+            return Err(unverifiable());
+        }
+    }
+    verify_signature(client, &download_path, info, forge, options)?;
+
+    let mut extracted = None;
+    let (payload_path, extra_paths) = if is_archive_name(asset_name) {
+        let extracted_paths =
+            extract_archive(&download_path, name, options).map_err(ExtractFailed)?;
+        let primary = extracted_paths.primary.clone();
+        let extras = extracted_paths.extras.clone();
+        extracted = Some(extracted_paths);
+        (primary, extras)
+    } else if is_gzip_name(asset_name) {
+        let extracted_paths = extract_gzip(&download_path, name, options).map_err(ExtractFailed)?;
+        let primary = extracted_paths.primary.clone();
+        let extras = extracted_paths.extras.clone();
+        extracted = Some(extracted_paths);
+        (primary, extras)
+    } else if is_zstd_name(asset_name) {
+        let extracted_paths = extract_zstd(&download_path, name, options).map_err(ExtractFailed)?;
+        let primary = extracted_paths.primary.clone();
+        let extras = extracted_paths.extras.clone();
+        extracted = Some(extracted_paths);
+        (primary, extras)
+    } else {
+        (download_path, Vec::new())
+    };
+    Ok((payload_path, extra_paths, extracted))
+}
+
+/// Fails when `path` is an ELF, Mach-O, or PE executable for a different architecture than
+/// `--arch`/`--only-arch` (or the host) asks for. Under `--no-verify-exec-format` the mismatch
+/// is returned as a warning instead, unless `strict` is set. Other files, and unknown machine
+/// types, pass.
+fn verify_exec_format(path: &Path, options: &Options) -> Result<Option<String>> {
+    let requested = options.arch.as_deref().or(options.only_arch.as_deref());
+    let expected = normalize_arch(requested.unwrap_or(env::consts::ARCH));
+    if !KNOWN_ARCHES.contains(&expected) {
+        return Ok(None);
+    }
+    let Some(found) = exec_arches(&read_header(path)?) else {
+        return Ok(None);
+    };
+    if found.is_empty() || found.contains(&expected) {
+        return Ok(None);
+    }
+    let message = format!(
+        "{} is built for {}, not {expected}",
+        path.display(),
+        found.join("/")
+    );
+    if options.strict {
+        bail!("{message} (--strict)");
+    }
+    if !options.no_verify_exec_format {
+        bail!("{message}; pass --no-verify-exec-format to keep it anyway");
+    }
+    Ok(Some(format!("{message}; it may fail to run here")))
+}
+
+/// The architecture an ELF, Mach-O, or PE binary at `path` was built for. `None` for other
+/// files, unreadable ones, unknown machine types, and universal binaries.
+pub fn detect_binary_arch(path: &Path) -> Option<&'static str> {
+    match exec_arches(&read_header(path).ok()?)?.as_slice() {
+        [arch] => Some(arch),
+        _ => None,
+    }
+}
+
+fn read_header(path: &Path) -> Result<Vec<u8>> {
+    let mut header = Vec::new();
+    fs::File::open(path)
+        .with_context(|| format!("open {}", path.display()))?
+        .take(4096)
+        .read_to_end(&mut header)
+        .with_context(|| format!("read {}", path.display()))?;
+    Ok(header)
+}
+
+/// Architectures named by an executable header, or `None` when `header` isn't ELF, Mach-O,
+/// or PE. Machine types outside `KNOWN_ARCHES` (plus `x86`) are left out.
+fn exec_arches(header: &[u8]) -> Option<Vec<&'static str>> {
+    let u16_at = |offset: usize, big: bool| {
+        let bytes = [*header.get(offset)?, *header.get(offset + 1)?];
+        Some(if big {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |offset: usize, big: bool| {
+        let bytes: [u8; 4] = header.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if big {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+    let elf_machine = |machine: u16| match machine {
+        0x3e => Some("x86_64"),
+        0xb7 => Some("aarch64"),
+        0x28 => Some("arm"),
+        0x03 => Some("x86"),
+        _ => None,
+    };
+    let macho_cpu = |cpu: u32| match cpu {
+        0x0100_0007 => Some("x86_64"),
+        0x0100_000c => Some("aarch64"),
+        0x0000_000c => Some("arm"),
+        0x0000_0007 => Some("x86"),
+        _ => None,
+    };
+
+    if header.starts_with(b"\x7fELF") {
+        let big = *header.get(5)? == 2;
+        return Some(elf_machine(u16_at(18, big)?).into_iter().collect());
+    }
+    match u32_at(0, false)? {
+        0xfeed_face | 0xfeed_facf => {
+            return Some(macho_cpu(u32_at(4, false)?).into_iter().collect())
+        }
+        0xcefa_edfe | 0xcffa_edfe => {
+            return Some(macho_cpu(u32_at(4, true)?).into_iter().collect())
+        }
+        _ => {}
+    }
+    if u32_at(0, true)? == 0xcafe_babe {
+        // Java class files share the universal-binary magic but have a large version here.
+        let count = u32_at(4, true)? as usize;
+        if count == 0 || count > 20 {
+            return None;
+        }
+        return Some(
+            (0..count)
+                .filter_map(|index| macho_cpu(u32_at(8 + index * 20, true)?))
+                .collect(),
+        );
+    }
+    if header.starts_with(b"MZ") {
+        let pe = u32_at(0x3c, false)? as usize;
+        if header.get(pe..pe + 4)? != b"PE\0\0" {
+            return None;
+        }
+        let arch = match u16_at(pe + 4, false)? {
+            0x8664 => Some("x86_64"),
+            0xaa64 => Some("aarch64"),
+            0x01c4 => Some("arm"),
+            0x014c => Some("x86"),
+            _ => None,
+        };
+        return Some(arch.into_iter().collect());
+    }
+    None
+}
+
+fn github_client(owner: &str, options: &Options) -> Result<Client> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::USER_AGENT,
+        reqwest::header::HeaderValue::from_static("yoink"),
+    );
+    headers.insert(
+        reqwest::header::ACCEPT,
+        reqwest::header::HeaderValue::from_static("application/vnd.github+json"),
+    );
+    headers.insert(
+        "X-GitHub-Api-Version",
+        reqwest::header::HeaderValue::from_static("2022-11-28"),
+    );
+
+    if let Some(token) = github_token(owner, options)? {
+        let value = format!("token {}", token);
+        let header =
+            reqwest::header::HeaderValue::from_str(&value).context("parse GitHub token header")?;
+        headers.insert(reqwest::header::AUTHORIZATION, header);
+    }
+
+    Client::builder()
+        .default_headers(headers)
+        .timeout(network_config(options)?.timeout())
+        .build()
+        .context("build http client")
+}
+
+/// Picks the token mapped to `owner`, falling back to the global token.
+fn github_token(owner: &str, options: &Options) -> Result<Option<String>> {
+    let owner_tokens = match &options.owner_token_map {
+        Some(path) => read_owner_token_map(path)?,
+        None => load_config(options)?.owner_tokens,
+    };
+    if let Some(token) = owner_tokens
+        .iter()
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(owner))
+        .map(|(_, token)| token.clone())
+    {
+        return Ok(Some(token));
+    }
+    Ok(options
+        .env("YOINK_GITHUB_TOKEN")
+        .or_else(|| env::var("GITHUB_TOKEN").ok()))
+}
+
+fn read_owner_token_map(path: &Path) -> Result<BTreeMap<String, String>> {
+    let contents = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    toml::from_str(&contents).with_context(|| format!("parse owner token map {}", path.display()))
+}
+
+/// Settings read from `config.toml` in the yoink config dir (or `YOINK_CONFIG`).
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Config {
+    /// GitHub tokens keyed by repo owner.
+    #[serde(default)]
+    owner_tokens: BTreeMap<String, String>,
+    /// Archive-relative binary paths keyed by `owner/repo`.
+    #[serde(default)]
+    extract: BTreeMap<String, String>,
+    #[serde(default)]
+    network: NetworkConfig,
+    #[serde(default)]
+    asset: AssetPreferences,
+    /// `download` or `install`; see `default_action`.
+    #[serde(default)]
+    default_action: Option<String>,
+}
+
+/// `[asset]` in `config.toml`: name substrings that nudge `pick_asset`'s scoring.
+#[derive(Debug, Default, Deserialize)]
+struct AssetPreferences {
+    #[serde(default)]
+    prefer: Vec<String>,
+    #[serde(default)]
+    avoid: Vec<String>,
+}
+
+fn config_path(options: &Options) -> Option<PathBuf> {
+    if options.isolated {
+        return None;
+    }
+    if let Some(path) = options.env("YOINK_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    dirs_next::config_dir()
+        .or_else(|| dirs_next::home_dir().map(|dir| dir.join(".config")))
+        .map(|dir| dir.join("yoink").join("config.toml"))
+}
+
+fn load_config(options: &Options) -> Result<Config> {
+    let Some(path) = config_path(options) else {
+        return Ok(Config::default());
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => {
+            toml::from_str(&contents).with_context(|| format!("parse {}", path.display()))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(err).with_context(|| format!("read {}", path.display())),
+    }
+}
+
+fn github_api_base(options: &Options) -> String {
+    options
+        .env("YOINK_GITHUB_API_BASE")
+        .unwrap_or_else(|| "https://api.github.com".to_string())
+}
+
+fn resolve_release_info(
+    forge: &dyn Forge,
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    tag: Option<&str>,
+    options: &Options,
+) -> Result<ReleaseInfo> {
+    let release = load_release(forge, client, owner, repo, tag, options)?;
+    let configured;
+    let options = match with_asset_preferences(options)? {
+        Some(with_preferences) => {
+            configured = with_preferences;
+            &configured
+        }
+        None => options,
+    };
+    let asset = pick_asset(&release.assets, repo, release.tag_name.as_deref(), options)?;
+    let tag = release.tag_name.as_deref().unwrap_or("unknown").to_string();
+
+    Ok(ReleaseInfo {
+        owner: owner.to_string(),
+        name: repo.to_string(),
+        tag,
+        checksum_url: checksum_asset(&release.assets, &asset.name),
+        signature_urls: signature_assets(&release.assets, &asset.name),
+        asset_name: asset.name,
+        asset_url: asset.browser_download_url,
+    })
+}
+
+/// `options` with `config.toml`'s asset preferences filled in, unless the caller set its own
+/// or the config has none.
+fn with_asset_preferences(options: &Options) -> Result<Option<Options>> {
+    if !options.asset_prefer.is_empty() || !options.asset_avoid.is_empty() {
+        return Ok(None);
+    }
+    let preferences = load_config(options)?.asset;
+    if preferences.prefer.is_empty() && preferences.avoid.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Options {
+        asset_prefer: preferences.prefer,
+        asset_avoid: preferences.avoid,
+        ..options.clone()
+    }))
+}
+
+/// Whole-string glob match where `*` spans any run of characters and `?` exactly one.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+/// Finds `<asset>.sha256`/`<asset>.sha256sum`, else a release-wide checksums list.
+fn checksum_asset(assets: &[Asset], asset_name: &str) -> Option<String> {
+    let lower = asset_name.to_lowercase();
+    let dedicated = [format!("{lower}.sha256"), format!("{lower}.sha256sum")];
+    assets
+        .iter()
+        .find(|asset| dedicated.contains(&asset.name.to_lowercase()))
+        .or_else(|| {
+            assets.iter().find(|asset| {
+                let name = asset.name.to_lowercase();
+                (name.contains("checksums") || name.contains("sha256sums"))
+                    && !name.ends_with(".sig")
+                    && !name.ends_with(".asc")
+                    && !name.ends_with(".pem")
+            })
+        })
+        .map(|asset| asset.browser_download_url.clone())
+}
+
+/// Finds the cosign `<asset>.sig`, `<asset>.pem`, and `<asset>.bundle` siblings.
+fn signature_assets(assets: &[Asset], asset_name: &str) -> Vec<String> {
+    let lower = asset_name.to_lowercase();
+    let siblings = COSIGN_SUFFIXES.map(|suffix| format!("{lower}{suffix}"));
+    assets
+        .iter()
+        .filter(|asset| siblings.contains(&asset.name.to_lowercase()))
+        .map(|asset| asset.browser_download_url.clone())
+        .collect()
+}
+
+const COSIGN_SUFFIXES: [&str; 3] = [".sig", ".pem", ".bundle"];
+
+/// Escapes `text` to match itself literally in a cosign (Go RE2) regexp.
+fn regex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// With `YOINK_VERIFY=cosign`, fetches the asset's signature siblings and checks them with
+/// `cosign verify-blob`, expecting a keyless signature from the repo's CI on its forge.
+fn verify_signature(
+    client: &Client,
+    download_path: &Path,
+    info: &ReleaseInfo,
+    forge: &dyn Forge,
+    options: &Options,
+) -> Result<()> {
+    if options.env("YOINK_VERIFY").as_deref() != Some("cosign") || info.signature_urls.is_empty() {
+        return Ok(());
+    }
+    let dir = download_path.parent().unwrap_or(Path::new("."));
+    let mut files = BTreeMap::new();
+    for url in &info.signature_urls {
+        let file_name = url.rsplit('/').next().unwrap_or(url);
+        let Some(suffix) = COSIGN_SUFFIXES
+            .iter()
+            .find(|suffix| file_name.to_lowercase().ends_with(*suffix))
+        else {
+            continue;
+        };
+        let bytes = get_with_retry(client, url, options)
+            .with_context(|| format!("download signature {url}"))?
+            .error_for_status()
+            .with_context(|| format!("bad signature response {url}"))?
+            .bytes()
+            .with_context(|| format!("read signature {url}"))?;
+        let path = dir.join(file_name);
+        fs::write(&path, &bytes).with_context(|| format!("write {}", path.display()))?;
+        files.insert(*suffix, path);
+    }
+
+    let mut command = Command::new("cosign");
+    command.arg("verify-blob");
+    if let Some(bundle) = files.get(".bundle") {
+        command.arg("--bundle").arg(bundle);
+    } else if let (Some(sig), Some(pem)) = (files.get(".sig"), files.get(".pem")) {
+        command
+            .arg("--signature")
+            .arg(sig)
+            .arg("--certificate")
+            .arg(pem);
+    } else {
+        bail!(
+            "cannot verify {}: release has a signature but no certificate or bundle",
+            info.asset_name
+        );
+    }
+    let (identity, issuer) = forge.signing_identity(&info.owner, &info.name);
+    command
+        .arg("--certificate-identity-regexp")
+        .arg(identity)
+        .arg("--certificate-oidc-issuer")
+        .arg(issuer)
+        .arg(download_path);
+    let output = command
+        .output()
+        .context("run cosign verify-blob; is cosign installed?")?;
+    if !output.status.success() {
+        bail!(
+            "cosign verification failed for {}: {}",
+            info.asset_name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Picks the sha256 for `asset_name` out of a checksum file: either a bare hash or
+/// `sha256sum`-style `<hash>  <name>` lines.
+fn expected_checksum(contents: &str, asset_name: &str) -> Option<String> {
+    let is_sha256 =
+        |token: &str| token.len() == 64 && token.chars().all(|ch| ch.is_ascii_hexdigit());
+    let lines = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    let mut single = None;
+    for (i, line) in lines.enumerate() {
+        let mut tokens = line.split_whitespace();
+        let Some(hash) = tokens.next().filter(|token| is_sha256(token)) else {
+            continue;
+        };
+        match tokens.next().map(|name| name.trim_start_matches('*')) {
+            Some(name) if name.rsplit('/').next() == Some(asset_name) => {
+                return Some(hash.to_lowercase())
+            }
+            None if i == 0 => single = Some(hash.to_lowercase()),
+            _ => {}
+        }
+    }
+    single
+}
+
+/// Fails unless the sha256 of `path` equals `expected` (hex, any case).
+pub fn verify_checksum(path: &Path, expected: &str) -> Result<(), YoinkError> {
+    let actual = sha256_file(path)?;
+    let expected = expected.trim();
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(anyhow!(
+            "checksum mismatch for {}: expected {}, got {actual}",
+            path.display(),
+            expected.to_lowercase()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Fetches `tag` (or the latest release), unless `--release-json` supplies the release.
+fn load_release(
+    forge: &dyn Forge,
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    tag: Option<&str>,
+    options: &Options,
+) -> Result<Release> {
+    match options.release_json.as_deref() {
+        Some("-") => {
+            serde_json::from_reader(io::stdin().lock()).context("parse release from stdin")
+        }
+        // The URL may be any host, so don't hand it the GitHub token.
+        Some(url) => Client::builder()
+            .user_agent("yoink")
+            .timeout(network_config(options)?.timeout())
+            .build()
+            .context("build http client")?
+            .get(url)
+            .send()
+            .with_context(|| format!("fetch release json from {url}"))?
+            .error_for_status()
+            .with_context(|| format!("bad response for {url}"))?
+            .json::<Release>()
+            .with_context(|| format!("parse release json from {url}")),
+        None => wait_for_release(options, || {
+            fetch_release(forge, client, owner, repo, tag, options)
+        }),
+    }
+}
+
+const GITLAB_PREFIX: &str = "gitlab:";
+
+/// A code host that yoink can resolve releases from.
+trait Forge {
+    /// An HTTP client carrying this forge's headers and `owner`'s token.
+    fn client(&self, owner: &str, options: &Options) -> Result<Client>;
+
+    /// Fetches the release the forge marks as latest, as a GitHub-shaped `Release`.
+    fn fetch_latest_release(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        options: &Options,
+    ) -> Result<Release>;
+
+    /// Fetches the release tagged exactly `tag`.
+    fn fetch_release_by_tag(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        tag: &str,
+        options: &Options,
+    ) -> Result<Release>;
+
+    /// Lists `owner/repo`'s releases, drafts and prereleases included.
+    fn fetch_releases(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        options: &Options,
+    ) -> Result<Vec<Release>>;
+
+    /// Whether `owner/repo` exists, to tell a missing repo from one without releases.
+    fn repo_exists(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        options: &Options,
+    ) -> Result<bool>;
+
+    /// Whether `file` exists at the root of `owner/repo`, at `tag` or else the default branch.
+    fn has_file(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        file: &str,
+        tag: Option<&str>,
+        options: &Options,
+    ) -> Result<bool>;
+
+    /// Where `git clone` fetches `owner/repo` from.
+    fn clone_url(&self, owner: &str, repo: &str, options: &Options) -> String;
+
+    /// The host to remember in the install state; `None` when the prefix implies it.
+    fn host(&self) -> Option<&str>;
+
+    /// The certificate identity regexp and OIDC issuer of a keyless signature made by
+    /// `owner/repo`'s own CI.
+    fn signing_identity(&self, owner: &str, repo: &str) -> (String, String);
+
+    /// Marks `owner/name` as belonging to this forge, e.g. in the install state.
+    fn repo_prefix(&self) -> &'static str;
+}
+
+/// Fetches release `tag`, or the latest release as `--pre` and `--semver-latest` pick it.
+/// A tag with no exact release falls back to the newest release it prefixes.
+fn fetch_release(
+    forge: &dyn Forge,
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    tag: Option<&str>,
+    options: &Options,
+) -> Result<Release> {
+    match tag {
+        Some(tag) => match forge.fetch_release_by_tag(client, owner, repo, tag, options) {
+            Err(err) if is_release_missing(&err) => {
+                fetch_release_by_tag_prefix(forge, client, owner, repo, tag, options)
+            }
+            release => release,
+        },
+        None if options.pre => fetch_newest_release(forge, client, owner, repo, options),
+        None if options.semver_latest => fetch_semver_latest(forge, client, owner, repo, options),
+        None => forge.fetch_latest_release(client, owner, repo, options),
+    }
+}
+
+struct GitHub;
+
+impl Forge for GitHub {
+    fn client(&self, owner: &str, options: &Options) -> Result<Client> {
+        github_client(owner, options)
+    }
+
+    fn fetch_latest_release(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        options: &Options,
+    ) -> Result<Release> {
+        fetch_latest_release(client, owner, repo, options)
+    }
+
+    fn fetch_release_by_tag(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        tag: &str,
+        options: &Options,
+    ) -> Result<Release> {
+        fetch_release_by_tag(client, owner, repo, tag, options)
+    }
+
+    fn fetch_releases(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        options: &Options,
+    ) -> Result<Vec<Release>> {
+        fetch_releases(client, owner, repo, options)
+    }
+
+    fn repo_exists(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        options: &Options,
+    ) -> Result<bool> {
+        let base = github_api_base(options);
+        let url = format!("{}/repos/{owner}/{repo}", base.trim_end_matches('/'));
+        let response = get_with_retry(client, &url, options)
+            .with_context(|| format!("fetch {owner}/{repo}"))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        response
+            .error_for_status()
+            .with_context(|| format!("bad response for {owner}/{repo}"))?;
+        Ok(true)
+    }
+
+    fn has_file(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        file: &str,
+        tag: Option<&str>,
+        options: &Options,
+    ) -> Result<bool> {
+        repo_has_file(client, owner, repo, file, tag, options)
+    }
+
+    fn clone_url(&self, owner: &str, repo: &str, options: &Options) -> String {
+        format!("{}/{owner}/{repo}.git", github_git_base(options))
+    }
+
+    fn host(&self) -> Option<&str> {
+        None
+    }
+
+    fn signing_identity(&self, owner: &str, repo: &str) -> (String, String) {
+        (
+            format!(
+                "^https://github\\.com/{}/{}/",
+                regex_escape(owner),
+                regex_escape(repo)
+            ),
+            "https://token.actions.githubusercontent.com".to_string(),
+        )
+    }
+
+    fn repo_prefix(&self) -> &'static str {
+        ""
+    }
+}
+
+struct GitLab {
+    host: String,
+}
+
+impl GitLab {
+    fn api_base(&self, options: &Options) -> String {
+        options
+            .env("YOINK_GITLAB_API_BASE")
+            .unwrap_or_else(|| format!("https://{}/api/v4", self.host))
+    }
+
+    /// The API URL for `owner/repo`, which GitLab takes as one URL-encoded project id.
+    fn project_url(&self, owner: &str, repo: &str, options: &Options) -> String {
+        format!(
+            "{}/projects/{}",
+            self.api_base(options).trim_end_matches('/'),
+            encode_url_component(&format!("{owner}/{repo}"))
+        )
+    }
+
+    fn fetch_release_at(
+        &self,
+        client: &Client,
+        url: &str,
+        label: &str,
+        options: &Options,
+    ) -> Result<Release> {
+        let release = get_with_retry(client, url, options)
+            .with_context(|| format!("fetch GitLab release for {label}"))?
+            .error_for_status()
+            .with_context(|| format!("bad response for {label}"))?
+            .json::<GitLabRelease>()
+            .with_context(|| format!("parse GitLab release for {label}"))?;
+        Ok(release.into())
+    }
+}
+
+impl Forge for GitLab {
+    fn client(&self, _owner: &str, options: &Options) -> Result<Client> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            reqwest::header::USER_AGENT,
+            reqwest::header::HeaderValue::from_static("yoink"),
+        );
+        if let Some(token) = gitlab_token(options) {
+            let header = reqwest::header::HeaderValue::from_str(&token)
+                .context("parse GitLab token header")?;
+            headers.insert("PRIVATE-TOKEN", header);
+        }
+        Client::builder()
+            .default_headers(headers)
+            .timeout(network_config(options)?.timeout())
+            .build()
+            .context("build http client")
+    }
+
+    fn fetch_latest_release(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        options: &Options,
+    ) -> Result<Release> {
+        let url = format!(
+            "{}/releases/permalink/latest",
+            self.project_url(owner, repo, options)
+        );
+        self.fetch_release_at(client, &url, &format!("{owner}/{repo}"), options)
+    }
+
+    fn fetch_release_by_tag(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        tag: &str,
+        options: &Options,
+    ) -> Result<Release> {
+        let url = format!(
+            "{}/releases/{}",
+            self.project_url(owner, repo, options),
+            encode_url_component(tag)
+        );
+        self.fetch_release_at(client, &url, &format!("{owner}/{repo}@{tag}"), options)
+    }
+
+    fn fetch_releases(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        options: &Options,
+    ) -> Result<Vec<Release>> {
+        let url = format!(
+            "{}/releases?per_page={RELEASES_PER_PAGE}",
+            self.project_url(owner, repo, options)
+        );
+        let releases = fetch_all_pages(&url, |url| {
+            get_with_retry(client, url, options)
+                .with_context(|| format!("fetch GitLab releases for {owner}/{repo}"))?
+                .error_for_status()
+                .with_context(|| format!("bad response for {owner}/{repo}"))?
+                .json::<Vec<GitLabRelease>>()
+                .with_context(|| format!("parse GitLab releases for {owner}/{repo}"))
+        })?;
+        Ok(releases.into_iter().map(Release::from).collect())
+    }
+
+    fn repo_exists(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        options: &Options,
+    ) -> Result<bool> {
+        let response = get_with_retry(client, &self.project_url(owner, repo, options), options)
+            .with_context(|| format!("fetch GitLab project {owner}/{repo}"))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        response
+            .error_for_status()
+            .with_context(|| format!("bad response for {owner}/{repo}"))?;
+        Ok(true)
+    }
+
+    fn has_file(
+        &self,
+        client: &Client,
+        owner: &str,
+        repo: &str,
+        file: &str,
+        tag: Option<&str>,
+        options: &Options,
+    ) -> Result<bool> {
+        // GitLab wants a ref; `HEAD` is the default branch.
+        let url = format!(
+            "{}/repository/files/{}?ref={}",
+            self.project_url(owner, repo, options),
+            encode_url_component(file),
+            encode_url_component(tag.unwrap_or("HEAD"))
+        );
+        let response = get_with_retry(client, &url, options)
+            .with_context(|| format!("look for {file} in {owner}/{repo}"))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        response
+            .error_for_status()
+            .with_context(|| format!("bad response looking for {file} in {owner}/{repo}"))?;
+        Ok(true)
+    }
+
+    fn clone_url(&self, owner: &str, repo: &str, options: &Options) -> String {
+        let base = options
+            .env("YOINK_GITLAB_GIT_BASE")
+            .unwrap_or_else(|| format!("https://{}", self.host));
+        format!("{}/{owner}/{repo}.git", base.trim_end_matches('/'))
+    }
+
+    fn host(&self) -> Option<&str> {
+        Some(&self.host)
+    }
+
+    // GitLab CI signs as `https://{host}/{project}//.gitlab-ci.yml@{ref}`.
+    fn signing_identity(&self, owner: &str, repo: &str) -> (String, String) {
+        (
+            format!(
+                "^https://{}/{}/{}//",
+                regex_escape(&self.host),
+                regex_escape(owner),
+                regex_escape(repo)
+            ),
+            format!("https://{}", self.host),
+        )
+    }
+
+    fn repo_prefix(&self) -> &'static str {
+        GITLAB_PREFIX
+    }
+}
+
+/// Percent-encodes everything but unreserved characters, for one URL path segment or
+/// query value.
+fn encode_url_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Mirrors `github_token`: `YOINK_GITLAB_TOKEN`, then `GITLAB_TOKEN`.
+fn gitlab_token(options: &Options) -> Option<String> {
+    options
+        .env("YOINK_GITLAB_TOKEN")
+        .or_else(|| env::var("GITLAB_TOKEN").ok())
+}
+
+/// The forge for `repo`: `--host` when given, else GitLab for a `gitlab:` prefix, else GitHub.
+fn repo_forge(repo: &str, options: &Options) -> Box<dyn Forge> {
+    match options.host.as_deref() {
+        Some(host) if !host.eq_ignore_ascii_case("github.com") => Box::new(GitLab {
+            host: host.to_string(),
+        }),
+        Some(_) => Box::new(GitHub),
+        None if repo.starts_with(GITLAB_PREFIX) => Box::new(GitLab {
+            host: "gitlab.com".to_string(),
+        }),
+        None => Box::new(GitHub),
+    }
+}
+
+#[derive(Deserialize)]
+struct GitLabRelease {
+    tag_name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    released_at: Option<String>,
+    #[serde(default)]
+    upcoming_release: bool,
+    #[serde(default)]
+    assets: GitLabAssets,
+}
+
+#[derive(Default, Deserialize)]
+struct GitLabAssets {
+    #[serde(default)]
+    links: Vec<GitLabLink>,
+}
+
+#[derive(Deserialize)]
+struct GitLabLink {
+    name: String,
+    url: String,
+    #[serde(default)]
+    direct_asset_url: Option<String>,
+}
+
+impl From<GitLabRelease> for Release {
+    fn from(release: GitLabRelease) -> Self {
+        Release {
+            assets: release
+                .assets
+                .links
+                .into_iter()
+                .map(|link| Asset {
+                    name: link.name,
+                    browser_download_url: link.direct_asset_url.unwrap_or(link.url),
+                })
+                .collect(),
+            tag_name: Some(release.tag_name),
+            body: release.description,
+            draft: false,
+            prerelease: release.upcoming_release,
+            published_at: release.released_at,
+        }
+    }
+}
+
+/// GitHub answered 403 or 429 with `X-RateLimit-Remaining: 0`; holds `X-RateLimit-Reset`,
+/// in Unix seconds, when the response had one.
+#[derive(Debug)]
+struct RateLimitExceeded(Option<u64>);
+
+impl std::fmt::Display for RateLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("GitHub API rate limit exceeded")?;
+        if let Some(reset) = self.0 {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|now| now.as_secs())
+                .unwrap_or(0);
+            let minutes = reset.saturating_sub(now).div_ceil(60);
+            let (hour, minute) = (reset % 86_400 / 3600, reset % 3600 / 60);
+            write!(
+                f,
+                "; it resets at {hour:02}:{minute:02} UTC (in {minutes}m)"
+            )?;
+        }
+        f.write_str("; set YOINK_GITHUB_TOKEN to raise the limit")
+    }
+}
+
+impl std::error::Error for RateLimitExceeded {}
+
+/// Fails with `RateLimitExceeded` when `response` is GitHub refusing a rate-limited request.
+fn check_rate_limit(response: &reqwest::blocking::Response) -> Result<()> {
+    let status = response.status();
+    if status != reqwest::StatusCode::FORBIDDEN && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+    {
+        return Ok(());
+    }
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    };
+    if header("x-ratelimit-remaining") != Some("0") {
+        return Ok(());
+    }
+    let reset = header("x-ratelimit-reset").and_then(|value| value.parse().ok());
+    Err(RateLimitExceeded(reset).into())
+}
+
+/// Retries `fetch` while the release is missing, until `--wait-for-release` runs out. Polls
+/// every `WAIT_POLL_INTERVAL`, or a tenth of the wait when that is shorter.
+fn wait_for_release(options: &Options, fetch: impl Fn() -> Result<Release>) -> Result<Release> {
+    let Some(wait) = options.wait_for_release else {
+        return fetch();
+    };
+    let deadline = std::time::Instant::now() + wait;
+    let interval = WAIT_POLL_INTERVAL.min(wait / 10);
+    loop {
+        match fetch() {
+            Err(err) if is_release_missing(&err) && std::time::Instant::now() < deadline => {
+                std::thread::sleep(interval);
+            }
+            Err(err) if is_release_missing(&err) => {
+                return Err(err).with_context(|| {
+                    format!("release did not appear within {}s", wait.as_secs_f32())
+                })
+            }
+            result => return result,
+        }
+    }
+}
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// No release matched a tag prefix.
+#[derive(Debug)]
+struct NoMatchingRelease(String);
+
+impl std::fmt::Display for NoMatchingRelease {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no release matches {}", self.0)
+    }
+}
+
+impl std::error::Error for NoMatchingRelease {}
+
+/// No release asset fits the platform filters or `--asset` pattern.
+#[derive(Debug)]
+struct NoSuitableAsset(String);
+
+impl std::fmt::Display for NoSuitableAsset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NoSuitableAsset {}
+
+const NO_ASSETS: &str = "release has no assets";
+
+fn is_assetless(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<NoSuitableAsset>()
+        .is_some_and(|err| err.0 == NO_ASSETS)
+}
+
+/// Whether `err` came from never getting a response at all, as when offline.
+fn is_unreachable(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|http| http.is_connect() || http.is_timeout())
+    })
+}
+
+fn is_release_missing(err: &anyhow::Error) -> bool {
+    err.is::<NoMatchingRelease>()
+        || err.chain().any(|cause| {
+            cause
+                .downcast_ref::<reqwest::Error>()
+                .and_then(reqwest::Error::status)
+                == Some(reqwest::StatusCode::NOT_FOUND)
+        })
+}
+
+/// The published release with the highest semver tag, or the forge's latest when no tag
+/// parses.
+fn fetch_semver_latest(
+    forge: &dyn Forge,
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    options: &Options,
+) -> Result<Release> {
+    let highest = forge
+        .fetch_releases(client, owner, repo, options)?
+        .into_iter()
+        .filter(|release| !release.draft && !release.prerelease)
+        .filter(|release| release.tag_name.as_deref().and_then(version_key).is_some())
+        .max_by(|a, b| {
+            compare_versions(
+                a.tag_name.as_deref().unwrap_or(""),
+                b.tag_name.as_deref().unwrap_or(""),
+            )
+        });
+    match highest {
+        Some(release) => Ok(release),
+        None => forge.fetch_latest_release(client, owner, repo, options),
+    }
+}
+
+/// The most recently published non-draft release, prereleases included, which
+/// `/releases/latest` leaves out.
+fn fetch_newest_release(
+    forge: &dyn Forge,
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    options: &Options,
+) -> Result<Release> {
+    forge
+        .fetch_releases(client, owner, repo, options)?
+        .into_iter()
+        .filter(|release| !release.draft)
+        .max_by(|a, b| a.published_at.cmp(&b.published_at))
+        .ok_or_else(|| NoMatchingRelease(format!("{owner}/{repo}")).into())
+}
+
+fn fetch_latest_release(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    options: &Options,
+) -> Result<Release> {
+    let base = github_api_base(options);
+    let base = base.trim_end_matches('/');
+    let url = format!("{base}/repos/{owner}/{repo}/releases/latest");
+    let response = get_with_retry(client, &url, options)
+        .with_context(|| format!("fetch latest release for {owner}/{repo}"))?;
+    check_rate_limit(&response)
+        .with_context(|| format!("fetch latest release for {owner}/{repo}"))?;
+    let response = response
+        .error_for_status()
+        .with_context(|| format!("bad response for {owner}/{repo}"))?;
+    response
+        .json::<Release>()
+        .with_context(|| format!("parse release for {owner}/{repo}"))
+}
+
+fn fetch_release_by_tag(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    tag: &str,
+    options: &Options,
+) -> Result<Release> {
+    let base = github_api_base(options);
+    let base = base.trim_end_matches('/');
+    let url = format!("{base}/repos/{owner}/{repo}/releases/tags/{tag}");
+    let response = get_with_retry(client, &url, options)
+        .with_context(|| format!("fetch release {tag} for {owner}/{repo}"))?;
+    let response = response
+        .error_for_status()
+        .with_context(|| format!("bad response for {owner}/{repo}@{tag}"))?;
+    response
+        .json::<Release>()
+        .with_context(|| format!("parse release {tag} for {owner}/{repo}"))
+}
+
+/// The newest published release whose tag starts with `prefix` at a version boundary, so
+/// `1.2` matches `v1.2.5` but not `v1.20.0`.
+fn fetch_release_by_tag_prefix(
+    forge: &dyn Forge,
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    prefix: &str,
+    options: &Options,
+) -> Result<Release> {
+    forge
+        .fetch_releases(client, owner, repo, options)?
+        .into_iter()
+        .filter(|release| !release.draft && !release.prerelease)
+        .filter(|release| {
+            release
+                .tag_name
+                .as_deref()
+                .is_some_and(|tag| tag_has_prefix(tag, prefix))
+        })
+        .max_by(|a, b| {
+            compare_versions(
+                a.tag_name.as_deref().unwrap_or(""),
+                b.tag_name.as_deref().unwrap_or(""),
+            )
+        })
+        .ok_or_else(|| NoMatchingRelease(format!("{owner}/{repo}@{prefix}")).into())
+}
+
+fn tag_has_prefix(tag: &str, prefix: &str) -> bool {
+    let tag = tag.strip_prefix('v').unwrap_or(tag);
+    let prefix = prefix.strip_prefix('v').unwrap_or(prefix);
+    match tag.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with(['.', '-', '+']),
+        None => false,
+    }
+}
+
+/// How many releases a listing asks for per page; the forges cap it at 100.
+const RELEASES_PER_PAGE: usize = 100;
+
+fn fetch_releases(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    options: &Options,
+) -> Result<Vec<Release>> {
+    let base = github_api_base(options);
+    let base = base.trim_end_matches('/');
+    let url = format!("{base}/repos/{owner}/{repo}/releases?per_page={RELEASES_PER_PAGE}");
+    fetch_all_pages(&url, |url| {
+        get_with_retry(client, url, options)
+            .with_context(|| format!("fetch releases for {owner}/{repo}"))?
+            .error_for_status()
+            .with_context(|| format!("bad response for {owner}/{repo}"))?
+            .json::<Vec<Release>>()
+            .with_context(|| format!("parse releases for {owner}/{repo}"))
+    })
+}
+
+/// Collects a listing that starts at `url`, asking for the next page for as long as pages
+/// come back full.
+fn fetch_all_pages<T>(
+    url: &str,
+    mut fetch_page: impl FnMut(&str) -> Result<Vec<T>>,
+) -> Result<Vec<T>> {
+    let mut items = fetch_page(url)?;
+    let mut page = 1;
+    while items.len() == page * RELEASES_PER_PAGE {
+        page += 1;
+        items.extend(fetch_page(&format!("{url}&page={page}"))?);
+    }
+    Ok(items)
+}
+
+fn pick_asset(
+    assets: &[Asset],
+    repo_name: &str,
+    tag: Option<&str>,
+    options: &Options,
+) -> Result<Asset> {
+    if assets.is_empty() {
+        return Err(NoSuitableAsset(NO_ASSETS.to_string()).into());
+    }
+
+    if let Some(pattern) = &options.asset {
+        let matching: Vec<&Asset> = assets
+            .iter()
+            .filter(|asset| glob_matches(pattern, &asset.name))
+            .collect();
+        return match matching.as_slice() {
+            [asset] => Ok((*asset).clone()),
+            [] => {
+                let names: Vec<&str> = assets.iter().map(|asset| asset.name.as_str()).collect();
+                let message = format!(
+                    "no assets match {pattern:?}; release has: {}",
+                    names.join(", ")
+                );
+                Err(NoSuitableAsset(message).into())
+            }
+            _ => {
+                let names: Vec<&str> = matching.iter().map(|asset| asset.name.as_str()).collect();
+                let message = format!("{pattern:?} matches several assets: {}", names.join(", "));
+                Err(NoSuitableAsset(message).into())
+            }
+        };
+    }
+
+    let mut candidates: Vec<&Asset> = assets
+        .iter()
+        .filter(|asset| !is_ignored_asset(&asset.name))
+        .collect();
+    if candidates.is_empty() {
+        candidates = assets.iter().collect();
+    }
+
+    let mut prefer_shorter = false;
+    let repo_tokens = tokenize_name(repo_name);
+    if !repo_tokens.is_empty() {
+        let repo_candidates: Vec<&Asset> = candidates
+            .iter()
+            .copied()
+            .filter(|asset| asset_matches_repo(&asset.name, &repo_tokens))
+            .collect();
+        if !repo_candidates.is_empty() {
+            candidates = repo_candidates;
+            prefer_shorter = true;
+        }
+    }
+
+    // With `tag_tokens`, a name without platform tokens is read alongside the release tag, for
+    // projects that cut a release per platform and keep asset names generic.
+    let platform_name = |asset: &Asset| match tag.filter(|_| options.tag_tokens) {
+        Some(tag) if asset_os(&asset.name).is_none() && asset_arch(&asset.name).is_none() => {
+            format!("{tag} {}", asset.name)
+        }
+        _ => asset.name.clone(),
+    };
+
+    if options.only_os.is_some() || options.only_arch.is_some() {
+        let matching: Vec<&Asset> = candidates
+            .iter()
+            .copied()
+            .filter(|asset| {
+                let name = platform_name(asset);
+                options
+                    .only_os
+                    .as_deref()
+                    .is_none_or(|os| asset_matches_os(&name, os))
+                    && options
+                        .only_arch
+                        .as_deref()
+                        .is_none_or(|arch| asset_matches_arch(&name, arch))
+            })
+            .collect();
+        if matching.is_empty() {
+            let wanted: Vec<&str> = [options.only_os.as_deref(), options.only_arch.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect();
+            let names: Vec<&str> = candidates.iter().map(|asset| asset.name.as_str()).collect();
+            let message = format!(
+                "no assets for {}; release has: {}",
+                wanted.join("/"),
+                names.join(", ")
+            );
+            return Err(NoSuitableAsset(message).into());
+        }
+        candidates = matching;
+    }
+
+    if options.ignore_arch {
+        let generic: Vec<&Asset> = candidates
+            .iter()
+            .copied()
+            .filter(|asset| asset_arch(&asset.name).is_none())
+            .collect();
+        if !generic.is_empty() {
+            candidates = generic;
+        }
+    }
+
+    let os_tokens = match options.only_os.as_deref() {
+        Some(os) => os_tokens_for(normalize_os(os)),
+        None => os_tokens(),
+    };
+    let arch_tokens = match options.arch.as_deref().or(options.only_arch.as_deref()) {
+        _ if options.ignore_arch => Vec::new(),
+        Some(arch) => arch_tokens_for(normalize_arch(arch)),
+        None => arch_tokens(),
+    };
+    let libc_tokens = match options.only_os.as_deref() {
+        Some(os) if normalize_os(os) != "linux" => LibcTokens::default(),
+        _ => libc_tokens(options),
+    };
+    // A glibc build won't start on a musl system however well its other tokens match, so
+    // drop those whenever anything else is left.
+    if !libc_tokens.avoid.is_empty() {
+        let runnable: Vec<&Asset> = candidates
+            .iter()
+            .copied()
+            .filter(|asset| !contains_any(&asset.name.to_lowercase(), &libc_tokens.avoid))
+            .collect();
+        if !runnable.is_empty() {
+            candidates = runnable;
+        }
+    }
+
+    let mut best: Option<(&Asset, i32, usize)> = None;
+    for asset in candidates {
+        let score = asset_score(
+            &platform_name(asset),
+            &os_tokens,
+            &arch_tokens,
+            &libc_tokens,
+        ) + preference_score(&asset.name, options);
+        let stem_len = asset_stem(&asset.name).len();
+        if best
+            .map(|(_, best_score, best_len)| {
+                score > best_score || (prefer_shorter && score == best_score && stem_len < best_len)
+            })
+            .unwrap_or(true)
+        {
+            best = Some((asset, score, stem_len));
+        }
+    }
+
+    best.map(|(asset, _, _)| asset.clone())
+        .context("no suitable assets")
+}
+
+fn asset_score(
+    name: &str,
+    os_tokens: &[&str],
+    arch_tokens: &[&str],
+    libc_tokens: &LibcTokens,
+) -> i32 {
+    let lower = name.to_lowercase();
+    let mut score = 0;
+
+    if contains_any(&lower, os_tokens) {
+        score += 2;
+    }
+    if contains_any(&lower, arch_tokens) {
+        score += 2;
+    }
+    if contains_any(&lower, &libc_tokens.prefer) {
+        score += 1;
+    }
+    if contains_any(&lower, &libc_tokens.avoid) {
+        score -= 1;
+    }
+    if is_archive_name(&lower) && (cfg!(windows) || !lower.ends_with(".cab")) {
+        score += 1;
+    }
+    if lower.ends_with(".exe") {
+        score += 1;
+    }
+    score
+}
+
+/// One point per `asset_prefer` substring in `name`, minus one per `asset_avoid` substring.
+fn preference_score(name: &str, options: &Options) -> i32 {
+    let lower = name.to_lowercase();
+    let hits = |tokens: &[String]| {
+        tokens
+            .iter()
+            .filter(|token| lower.contains(&token.to_lowercase()))
+            .count() as i32
+    };
+    hits(&options.asset_prefer) - hits(&options.asset_avoid)
+}
+
+fn contains_any(haystack: &str, tokens: &[&str]) -> bool {
+    tokens.iter().any(|token| haystack.contains(token))
+}
+
+fn tokenize_name(name: &str) -> Vec<String> {
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_string())
+        .collect()
+}
+
+fn asset_matches_repo(asset_name: &str, repo_tokens: &[String]) -> bool {
+    let stem = asset_stem(asset_name);
+    let tokens = tokenize_name(stem);
+    tokens_match_sequence(&tokens, repo_tokens)
+}
+
+fn tokens_match_sequence(haystack: &[String], needle: &[String]) -> bool {
+    if needle.is_empty() {
+        return false;
+    }
+    if needle.len() > haystack.len() {
+        return false;
+    }
+    for start in 0..=(haystack.len() - needle.len()) {
+        if haystack[start..start + needle.len()] == *needle {
+            return true;
+        }
+    }
+    false
+}
+
+fn os_tokens() -> Vec<&'static str> {
+    os_tokens_for(env::consts::OS)
+}
+
+fn os_tokens_for(os: &str) -> Vec<&str> {
+    match os {
+        "macos" => vec!["darwin", "macos", "osx", "mac", "apple-darwin"],
+        "linux" => vec!["linux", "gnu", "unknown-linux"],
+        "windows" => vec!["windows", "win", "mingw", "msvc"],
+        other => vec![other],
+    }
+}
+
+/// Asset-name tokens to favor and to penalize for the running system's C library.
+#[derive(Debug, Default)]
+struct LibcTokens {
+    prefer: Vec<&'static str>,
+    avoid: Vec<&'static str>,
+}
+
+fn libc_tokens(options: &Options) -> LibcTokens {
+    libc_tokens_for(detect_libc(options))
+}
+
+/// glibc systems run static musl builds fine, so only musl needs steering away from `gnu`.
+fn libc_tokens_for(libc: Option<&str>) -> LibcTokens {
+    match libc {
+        Some("musl") => LibcTokens {
+            prefer: vec!["musl"],
+            avoid: vec!["gnu"],
+        },
+        _ => LibcTokens::default(),
+    }
+}
+
+/// `musl` or `gnu` on Linux, from `YOINK_LIBC` or the presence of a musl dynamic loader.
+fn detect_libc(options: &Options) -> Option<&'static str> {
+    if env::consts::OS != "linux" {
+        return None;
+    }
+    match options.env("YOINK_LIBC").as_deref() {
+        Some("musl") => return Some("musl"),
+        Some("gnu" | "glibc") => return Some("gnu"),
+        _ => {}
+    }
+    let has_musl_loader = fs::read_dir("/lib")
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|entry| entry.file_name().to_string_lossy().starts_with("ld-musl-"))
+        })
+        .unwrap_or(false);
+    if cfg!(target_env = "musl") || has_musl_loader {
+        Some("musl")
+    } else {
+        Some("gnu")
+    }
+}
+
+/// Operating systems `asset_os` tells apart; Windows comes before Linux so `windows-gnu`
+/// isn't read as Linux.
+const KNOWN_OSES: [&str; 3] = ["macos", "windows", "linux"];
+
+/// Maps common aliases like `darwin` or `win` onto Rust's OS names.
+fn normalize_os(os: &str) -> &str {
+    match os.to_lowercase().as_str() {
+        "macos" | "darwin" | "osx" | "mac" => "macos",
+        "windows" | "win" => "windows",
+        "linux" => "linux",
+        _ => os,
+    }
+}
+
+fn asset_os(name: &str) -> Option<&'static str> {
+    let tokens = tokenize_name(name);
+    KNOWN_OSES
+        .into_iter()
+        .find(|os| has_token_run(&tokens, &os_tokens_for(os)))
+}
+
+fn asset_matches_os(name: &str, os: &str) -> bool {
+    let os = normalize_os(os);
+    if KNOWN_OSES.contains(&os) {
+        asset_os(name) == Some(os)
+    } else {
+        name.to_lowercase().contains(&os.to_lowercase())
+    }
+}
+
+fn asset_matches_arch(name: &str, arch: &str) -> bool {
+    let arch = normalize_arch(arch);
+    if KNOWN_ARCHES.contains(&arch) {
+        asset_arch(name) == Some(arch)
+    } else {
+        name.to_lowercase().contains(&arch.to_lowercase())
+    }
+}
+
+/// Architectures `download_all_arches` knows how to tell apart, most specific first.
+const KNOWN_ARCHES: [&str; 3] = ["aarch64", "x86_64", "arm"];
+
+fn arch_tokens() -> Vec<&'static str> {
+    arch_tokens_for(env::consts::ARCH)
+}
+
+fn arch_tokens_for(arch: &str) -> Vec<&str> {
+    match arch {
+        "x86_64" => vec!["x86_64", "amd64", "x64"],
+        "aarch64" => vec!["aarch64", "arm64"],
+        "arm" => vec!["armv7", "armv7l", "armv6", "arm"],
+        other => vec![other],
+    }
+}
+
+/// Maps common aliases like `amd64` or `arm64` onto Rust's architecture names.
+fn normalize_arch(arch: &str) -> &str {
+    match arch.to_lowercase().as_str() {
+        "x86_64" | "amd64" | "x64" => "x86_64",
+        "aarch64" | "arm64" => "aarch64",
+        "arm" | "armv7" | "armv7l" | "armv6" => "arm",
+        _ => arch,
+    }
+}
+
+fn asset_arch(name: &str) -> Option<&'static str> {
+    let tokens = tokenize_name(name);
+    KNOWN_ARCHES
+        .into_iter()
+        .find(|arch| has_token_run(&tokens, &arch_tokens_for(arch)))
+}
+
+/// Whether any of `aliases` appears in `tokens` as whole tokens, so `arm` isn't found in
+/// `arm64` nor `win` in `darwin`.
+fn has_token_run(tokens: &[String], aliases: &[&str]) -> bool {
+    aliases
+        .iter()
+        .any(|alias| tokens_match_sequence(tokens, &tokenize_name(alias)))
+}
+
+fn is_ignored_asset(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    lower.ends_with(".sha256")
+        || lower.ends_with(".sha256sum")
+        || lower.ends_with(".sha512")
+        || lower.ends_with(".sig")
+        || lower.ends_with(".pem")
+        || lower.ends_with(".bundle")
+        || lower.ends_with(".asc")
+        || lower.ends_with(".md5")
+        || lower.contains("checksum")
+        || lower.contains("checksums")
+        || lower.contains("sbom")
+}
+
+fn is_archive_name(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    lower.ends_with(".zip")
+        || lower.ends_with(".tar.gz")
+        || lower.ends_with(".tgz")
+        || lower.ends_with(".tar.xz")
+        || lower.ends_with(".tar.bz2")
+        || lower.ends_with(".tar.zst")
+        || lower.ends_with(".cab")
+        || lower.ends_with(".7z")
+}
+
+fn is_gzip_name(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    lower.ends_with(".gz") && !lower.ends_with(".tar.gz")
+}
+
+fn is_zstd_name(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    lower.ends_with(".zst") && !lower.ends_with(".tar.zst")
+}
+
+/// `name` without `suffix`, matched case-insensitively like `is_gzip_name` and friends.
+fn strip_suffix_ignore_case<'a>(name: &'a str, suffix: &str) -> &'a str {
+    let split = name.len().saturating_sub(suffix.len());
+    match name.get(split..) {
+        Some(tail) if tail.eq_ignore_ascii_case(suffix) => &name[..split],
+        _ => name,
+    }
+}
+
+fn asset_stem(name: &str) -> &str {
+    let lower = name.to_lowercase();
+    if lower.ends_with(".tar.gz") {
+        return &name[..name.len().saturating_sub(7)];
+    }
+    if lower.ends_with(".tar.xz") {
+        return &name[..name.len().saturating_sub(7)];
+    }
+    if lower.ends_with(".tar.bz2") || lower.ends_with(".tar.zst") {
+        return &name[..name.len().saturating_sub(8)];
+    }
+    if lower.ends_with(".zip") {
+        return &name[..name.len().saturating_sub(4)];
+    }
+    if lower.ends_with(".tgz") || lower.ends_with(".cab") || lower.ends_with(".zst") {
+        return &name[..name.len().saturating_sub(4)];
+    }
+    if lower.ends_with(".gz") || lower.ends_with(".7z") {
+        return &name[..name.len().saturating_sub(3)];
+    }
+    if lower.ends_with(".exe") {
+        return &name[..name.len().saturating_sub(4)];
+    }
+    name
+}
+
+fn fetch_asset(client: &Client, url: &str, dest: &Path, options: &Options) -> Result<()> {
+    if let Some(parts) = options.parallel_download.filter(|parts| *parts > 1) {
+        if download_asset_parallel(client, url, dest, parts, options)? {
+            return Ok(());
+        }
+    }
+    download_asset(client, url, dest, options)
+}
+
+/// Size the download cache is pruned back to after each addition, unless
+/// `YOINK_CACHE_MAX_BYTES` says otherwise.
+const DOWNLOAD_CACHE_MAX_BYTES: u64 = 1024 * 1024 * 1024;
+
+fn download_cache_root() -> Result<PathBuf> {
+    Ok(data_dir()?.join("cache"))
+}
+
+/// Where `info`'s asset is cached: `{host}/{owner}/{repo}/{tag}/{asset_name}` under the cache
+/// root, so the same repo on two forges never shares entries.
+fn download_cache_path(info: &ReleaseInfo, host: &str) -> Result<PathBuf> {
+    let tag = info.tag.replace(['/', '\\'], "_");
+    Ok(download_cache_root()?
+        .join(host)
+        .join(&info.owner)
+        .join(&info.name)
+        .join(tag)
+        .join(&info.asset_name))
+}
+
+/// The forge's host, as the download cache files its entries under.
+fn cache_host(forge: &dyn Forge) -> &str {
+    forge.host().unwrap_or("github.com")
+}
+
+/// Where the checksum list for the asset cached at `cached` is kept.
+fn checksum_cache_path(cached: &Path) -> PathBuf {
+    let mut path = cached.as_os_str().to_owned();
+    path.push(".checksums");
+    PathBuf::from(path)
+}
+
+/// Fetches the checksum list at `url`, reusing the copy at `cached` when there is one so a
+/// cached asset can still be verified offline.
+fn fetch_checksum_list(
+    client: &Client,
+    url: &str,
+    cached: Option<&Path>,
+    options: &Options,
+) -> Result<String> {
+    if let Some(cached) = cached.filter(|_| !options.refresh) {
+        if let Ok(contents) = fs::read_to_string(cached) {
+            return Ok(contents);
+        }
+    }
+    let contents = get_with_retry(client, url, options)
+        .with_context(|| format!("download checksum {url}"))?
+        .error_for_status()
+        .with_context(|| format!("bad checksum response {url}"))?
+        .text()
+        .with_context(|| format!("read checksum {url}"))?;
+    if let Some(cached) = cached {
+        fs::write(cached, &contents).with_context(|| format!("write {}", cached.display()))?;
+    }
+    Ok(contents)
+}
+
+/// Fetches the asset into `dest`, going through the download cache when `options.cache` is
+/// set. Returns the cached copy's path, if there is one.
+fn fetch_cached_asset(
+    client: &Client,
+    info: &ReleaseInfo,
+    host: &str,
+    dest: &Path,
+    options: &Options,
+) -> Result<Option<PathBuf>> {
+    if !options.cache || options.isolated {
+        fetch_asset(client, &info.asset_url, dest, options)?;
+        return Ok(None);
+    }
+    let cached = download_cache_path(info, host)?;
+    if !options.refresh && cached.is_file() {
+        fs::copy(&cached, dest).with_context(|| format!("copy {}", cached.display()))?;
+        // Bump the mtime so pruning evicts the least recently used assets first.
+        if let Ok(file) = fs::File::options().write(true).open(&cached) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        return Ok(Some(cached));
+    }
+    fetch_asset(client, &info.asset_url, dest, options)?;
+    let parent = cached.parent().context("cache path has no parent")?;
+    fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+    let mut staged = tempfile::NamedTempFile::new_in(parent).context("create cache file")?;
+    let mut source = fs::File::open(dest).with_context(|| format!("open {}", dest.display()))?;
+    io::copy(&mut source, &mut staged).context("write cache file")?;
+    staged
+        .persist(&cached)
+        .with_context(|| format!("write {}", cached.display()))?;
+    let max_bytes = match options.env("YOINK_CACHE_MAX_BYTES") {
+        Some(value) => value
+            .parse()
+            .with_context(|| format!("YOINK_CACHE_MAX_BYTES must be a byte count, not {value}"))?,
+        None => DOWNLOAD_CACHE_MAX_BYTES,
+    };
+    prune_download_cache(max_bytes)?;
+    Ok(Some(cached))
+}
+
+/// Deletes the least recently used cached assets until the cache holds at most `max_bytes`.
+/// Returns how many bytes were freed.
+pub fn prune_download_cache(max_bytes: u64) -> Result<u64, YoinkError> {
+    let root = download_cache_root()?;
+    if !root.exists() {
+        return Ok(0);
+    }
+    let mut files = Vec::new();
+    for entry in WalkDir::new(&root) {
+        let entry = entry.context("walk download cache")?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let metadata = entry.metadata().context("stat cached asset")?;
+        let used = metadata.modified().unwrap_or(UNIX_EPOCH);
+        files.push((used, metadata.len(), entry.into_path()));
+    }
+    files.sort();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    let mut freed = 0;
+    for (_, len, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+        total -= len;
+        freed += len;
+    }
+    Ok(freed)
+}
+
+/// Sends a GET, retrying connection errors and 5xx responses up to `retries` times and
+/// 404s up to `retry_404` times, with exponential backoff.
+fn get_with_retry(
+    client: &Client,
+    url: &str,
+    options: &Options,
+) -> Result<reqwest::blocking::Response> {
+    send_with_retry(options, || client.get(url))
+}
+
+/// `get_with_retry` for any request; `build` makes a fresh copy for each attempt.
+fn send_with_retry(
+    options: &Options,
+    build: impl Fn() -> reqwest::blocking::RequestBuilder,
+) -> Result<reqwest::blocking::Response> {
+    let network = network_config(options)?;
+    let retries = network.retries.unwrap_or(0);
+    let retry_404 = network.retry_404.unwrap_or(0);
+    let (mut failures, mut not_found) = (0, 0);
+    loop {
+        match build().send() {
+            Ok(response)
+                if response.status() == reqwest::StatusCode::NOT_FOUND && not_found < retry_404 =>
+            {
+                not_found += 1
+            }
+            Ok(response) if response.status().is_server_error() && failures < retries => {
+                failures += 1
+            }
+            Ok(response) => return Ok(response),
+            Err(_) if failures < retries => failures += 1,
+            Err(err) => return Err(err.into()),
+        }
+        let attempt: u32 = failures + not_found - 1;
+        std::thread::sleep(Duration::from_millis(250 << attempt.min(5)));
+    }
+}
+
+/// Downloads `url` as `parts` concurrent byte ranges into `dest.part`, renaming it to `dest`
+/// once complete. Returns `false` without leaving anything behind when the server can't serve
+/// ranges: the HEAD probe fails, range support isn't advertised, or a Range request is ignored.
+fn download_asset_parallel(
+    client: &Client,
+    url: &str,
+    dest: &Path,
+    parts: usize,
+    options: &Options,
+) -> Result<bool> {
+    // Some CDNs refuse HEAD outright, so a failed probe only rules out the parallel path.
+    let response = match send_with_retry(options, || client.head(url)) {
+        Ok(response) if response.status().is_success() => response,
+        _ => return Ok(false),
+    };
+    let headers = response.headers();
+    let accepts_ranges = headers
+        .get(reqwest::header::ACCEPT_RANGES)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.eq_ignore_ascii_case("bytes"))
+        .unwrap_or(false);
+    let len = headers
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(0);
+    if !accepts_ranges || len < parts as u64 {
+        return Ok(false);
+    }
+
+    let part = part_path(dest);
+    let file = fs::File::create(&part)
+        .with_context(|| format!("create download file {}", part.display()))?;
+    file.set_len(len)
+        .with_context(|| format!("size download file {}", part.display()))?;
+    drop(file);
+
+    let progress = Mutex::new(ProgressReader::new(
+        io::empty(),
+        options.progress,
+        Some(len),
+        io::stderr(),
+    ));
+    let chunk = len.div_ceil(parts as u64);
+    let ranged = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..len)
+            .step_by(chunk as usize)
+            .map(|start| {
+                let end = (start + chunk).min(len) - 1;
+                let (part, progress) = (&part, &progress);
+                scope
+                    .spawn(move || download_range(client, url, part, start, end, progress, options))
+            })
+            .collect();
+        let mut ranged = true;
+        for handle in handles {
+            ranged &= handle
+                .join()
+                .map_err(|_| anyhow::anyhow!("download thread panicked"))??;
+        }
+        Ok(ranged)
+    });
+    // A sparse, partly written `.part` can't be resumed by `download_asset`, so never keep one.
+    match ranged {
+        Ok(true) => {
+            progress
+                .into_inner()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .finish();
+            fs::rename(&part, dest)
+                .with_context(|| format!("rename download to {}", dest.display()))?;
+            Ok(true)
+        }
+        Ok(false) => {
+            let _ = fs::remove_file(&part);
+            Ok(false)
+        }
+        Err(err) => {
+            let _ = fs::remove_file(&part);
+            Err(err)
+        }
+    }
+}
+
+/// Writes bytes `start..=end` of `url` into `part`. Returns `false` when the server answers
+/// with the whole body instead of the range.
+fn download_range(
+    client: &Client,
+    url: &str,
+    part: &Path,
+    start: u64,
+    end: u64,
+    progress: &Mutex<ProgressReader<io::Empty, io::Stderr>>,
+    options: &Options,
+) -> Result<bool> {
+    let response = send_with_retry(options, || {
+        client
+            .get(url)
+            .header(reqwest::header::RANGE, format!("bytes={start}-{end}"))
+    })
+    .with_context(|| format!("download range {start}-{end} of {url}"))?
+    .error_for_status()
+    .with_context(|| format!("bad download response {url}"))?;
+    if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        return Ok(false);
+    }
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(part)
+        .with_context(|| format!("open download file {}", part.display()))?;
+    file.seek(SeekFrom::Start(start))
+        .with_context(|| format!("seek download file {}", part.display()))?;
+    let expected = end - start + 1;
+    let mut body = response.take(expected);
+    let mut buf = vec![0; 64 * 1024];
+    let mut written = 0;
+    loop {
+        let read = body
+            .read(&mut buf)
+            .with_context(|| format!("write download to {}", part.display()))?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buf[..read])
+            .with_context(|| format!("write download to {}", part.display()))?;
+        written += read as u64;
+        progress
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .advance(read as u64);
+    }
+    if written != expected {
+        bail!("range {start}-{end} of {url} ended after {written} bytes");
+    }
+    Ok(true)
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut part = dest.as_os_str().to_os_string();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+/// Streams `url` into `dest.part`, renaming it to `dest` once complete. A body that breaks
+/// off is resumed with a Range request, up to `retries` times.
+fn download_asset(client: &Client, url: &str, dest: &Path, options: &Options) -> Result<()> {
+    let part = part_path(dest);
+    let retries = network_config(options)?.retries.unwrap_or(0);
+    let mut attempt = 0;
+    while let Err(err) = download_part(client, url, &part, options) {
+        if attempt >= retries || !err.is::<DownloadInterrupted>() {
+            return Err(err);
+        }
+        std::thread::sleep(Duration::from_millis(250 << attempt.min(5)));
+        attempt += 1;
+    }
+    fs::rename(&part, dest).with_context(|| format!("rename download to {}", dest.display()))
+}
+
+/// Marks a download whose body stopped partway, which `download_asset` can resume.
+#[derive(Debug)]
+struct DownloadInterrupted(PathBuf);
+
+impl std::fmt::Display for DownloadInterrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "write download to {}", self.0.display())
+    }
+}
+
+/// Fetches the rest of `url` into `part`, starting over when the server ignores the Range
+/// request and answers 200 instead of 206.
+fn download_part(client: &Client, url: &str, part: &Path, options: &Options) -> Result<()> {
+    let existing = fs::metadata(part).map_or(0, |metadata| metadata.len());
+    let response = send_with_retry(options, || match existing {
+        0 => client.get(url),
+        from => client
+            .get(url)
+            .header(reqwest::header::RANGE, format!("bytes={from}-")),
+    })
+    .with_context(|| format!("download asset {url}"))?;
+    if existing > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        fs::remove_file(part).with_context(|| format!("remove {}", part.display()))?;
+        return download_part(client, url, part, options);
+    }
+    let response = response
+        .error_for_status()
+        .with_context(|| format!("bad download response {url}"))?;
+    let resumed = existing > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    if resumed {
+        let start = response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("bytes "))
+            .and_then(|value| value.split_once('-'))
+            .and_then(|(start, _)| start.parse::<u64>().ok());
+        if start != Some(existing) {
+            bail!("{url} resumed at the wrong offset; expected byte {existing}");
+        }
+    }
+    let total = response
+        .content_length()
+        .map(|len| if resumed { len + existing } else { len });
+    let mut reader = ProgressReader::new(response, options.progress, total, io::stderr());
+    let mut file = if resumed {
+        reader.resume(existing);
+        fs::OpenOptions::new().append(true).open(part)
+    } else {
+        fs::File::create(part)
+    }
+    .with_context(|| format!("create download file {}", part.display()))?;
+    io::copy(&mut reader, &mut file).with_context(|| DownloadInterrupted(part.to_path_buf()))?;
+    reader.finish();
+    Ok(())
+}
+
+/// Bytes per dot in `ProgressMode::Dots`.
+const DOT_BYTES: u64 = 256 * 1024;
+
+/// Wraps a download stream and reports how much has been read to `out`.
+struct ProgressReader<R, W> {
+    inner: R,
+    out: W,
+    mode: ProgressMode,
+    total: Option<u64>,
+    read: u64,
+    shown: u64,
+    /// Bytes already on disk when a download resumed, left out of the rate.
+    resumed: u64,
+    started: Instant,
+}
+
+impl<R, W: Write> ProgressReader<R, W> {
+    fn new(inner: R, mode: ProgressMode, total: Option<u64>, out: W) -> Self {
+        Self {
+            inner,
+            out,
+            mode,
+            total,
+            read: 0,
+            shown: 0,
+            resumed: 0,
+            started: Instant::now(),
+        }
+    }
+
+    fn resume(&mut self, bytes: u64) {
+        self.resumed = bytes;
+        self.advance(bytes);
+    }
+
+    fn rate(&self) -> String {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed <= 0.0 {
+            return String::new();
+        }
+        let per_second = (self.read - self.resumed) as f64 / elapsed;
+        format!(" {}/s", format_bytes(per_second as u64))
+    }
+
+    fn advance(&mut self, bytes: u64) {
+        self.read += bytes;
+        // Progress output is best effort; a closed stderr must not fail the download.
+        match self.mode {
+            ProgressMode::None => {}
+            ProgressMode::Dots => {
+                let dots = self.read / DOT_BYTES;
+                if dots > self.shown {
+                    let _ = self
+                        .out
+                        .write_all(".".repeat((dots - self.shown) as usize).as_bytes());
+                    let _ = self.out.flush();
+                    self.shown = dots;
+                }
+            }
+            ProgressMode::Bar => {
+                let line = match self.total.filter(|total| *total > 0) {
+                    Some(total) => {
+                        let percent = (self.read.min(total) * 100 / total).max(1);
+                        if percent == self.shown {
+                            return;
+                        }
+                        self.shown = percent;
+                        let filled = (percent / 5) as usize;
+                        format!(
+                            "\r[{}{}] {percent:>3}% {} / {}{}",
+                            "#".repeat(filled),
+                            " ".repeat(20 - filled),
+                            format_bytes(self.read),
+                            format_bytes(total),
+                            self.rate()
+                        )
+                    }
+                    None => format!("\r{}{}", format_bytes(self.read), self.rate()),
+                };
+                let _ = self.out.write_all(line.as_bytes());
+                let _ = self.out.flush();
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.mode != ProgressMode::None && self.read > 0 {
+            let _ = self.out.write_all(b"\n");
+            let _ = self.out.flush();
+        }
+    }
+}
+
+impl<R: Read, W: Write> Read for ProgressReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.advance(count as u64);
+        Ok(count)
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    format!("{:.1} MiB", bytes as f64 / MIB)
+}
+
+struct ExtractedPaths {
+    primary: PathBuf,
+    extras: Vec<PathBuf>,
+    _temp_dir: TempDir,
+}
+
+fn extract_archive(
+    archive_path: &Path,
+    repo_name: &str,
+    options: &Options,
+) -> Result<ExtractedPaths> {
+    let temp_dir = scratch_dir(options)?;
+    match unpack_archive(archive_path, temp_dir.path())
+        .and_then(|()| find_binaries(temp_dir.path(), repo_name, options))
+        .and_then(|found| {
+            install_config_files(temp_dir.path(), options)?;
+            Ok(found)
+        }) {
+        Ok((primary, extras)) => Ok(ExtractedPaths {
+            primary,
+            extras,
+            _temp_dir: temp_dir,
+        }),
+        Err(err) => Err(keep_temp_dir(err, temp_dir, options)),
+    }
+}
+
+/// Archive-relative paths treated as config under `--install-config` without `--config-glob`.
+const DEFAULT_CONFIG_GLOBS: [&str; 6] = [
+    "*config/*.toml",
+    "*config/*.yaml",
+    "*config/*.yml",
+    "*config/*.json",
+    "*.conf",
+    "*.ini",
+];
+
+/// Copies config files from an extracted archive into `options.install_config`. Existing
+/// files are left alone, since the user may have edited them.
+fn install_config_files(root: &Path, options: &Options) -> Result<Vec<PathBuf>> {
+    let Some(config_dir) = &options.install_config else {
+        return Ok(Vec::new());
+    };
+    let matches = |relative: &str| match &options.config_glob {
+        Some(pattern) => glob_matches(pattern, relative),
+        None => DEFAULT_CONFIG_GLOBS
+            .iter()
+            .any(|pattern| glob_matches(pattern, relative)),
+    };
+    let mut installed = Vec::new();
+    for entry in WalkDir::new(root) {
+        let entry = entry.context("walk archive")?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        let Some(name) = path.file_name().filter(|_| matches(&relative)) else {
+            continue;
+        };
+        let dest = config_dir.join(name);
+        if fs::symlink_metadata(&dest).is_ok() {
+            continue;
+        }
+        fs::create_dir_all(config_dir)
+            .with_context(|| format!("create {}", config_dir.display()))?;
+        fs::copy(path, &dest).with_context(|| format!("copy config to {}", dest.display()))?;
+        installed.push(dest);
+    }
+    Ok(installed)
+}
+
+fn unpack_archive(archive_path: &Path, extract_root: &Path) -> Result<()> {
+    let name = archive_path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .unwrap_or("")
+        .to_lowercase();
+
+    let result = if name.ends_with(".zip") {
+        extract_zip(archive_path, extract_root)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        extract_tar_gz(archive_path, extract_root)
+    } else if name.ends_with(".tar.xz") {
+        extract_tar_xz(archive_path, extract_root)
+    } else if name.ends_with(".tar.bz2") {
+        extract_tar_bz2(archive_path, extract_root)
+    } else if name.ends_with(".tar.zst") {
+        extract_tar_zst(archive_path, extract_root)
+    } else if name.ends_with(".cab") {
+        extract_cab(archive_path, extract_root)
+    } else if name.ends_with(".7z") {
+        // Reports encrypted archives itself, which would be misread as corruption.
+        return extract_7z(archive_path, extract_root);
+    } else {
+        bail!("unsupported archive format: {}", archive_path.display());
+    };
+    match result {
+        Err(err) if err.is::<UnsafeEntryPath>() => Err(err),
+        result => result.with_context(|| corrupt_download(archive_path)),
+    }
+}
+
+/// Tags an unpacking failure for `YoinkError::Extract`; reads exactly like the wrapped error.
+#[derive(Debug)]
+struct ExtractFailed(anyhow::Error);
+
+impl std::fmt::Display for ExtractFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ExtractFailed {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.chain().nth(1)
+    }
+}
+
+/// An archive entry whose path would land outside the extraction dir.
+#[derive(Debug)]
+struct UnsafeEntryPath(String);
+
+impl std::fmt::Display for UnsafeEntryPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "refusing archive entry {:?}: it would be written outside the extraction dir",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnsafeEntryPath {}
+
+/// Joins an archive entry name onto `dest`, failing on absolute paths, `..` segments, and
+/// paths that resolve outside `dest` through already-extracted symlinks.
+fn contained_entry_path(dest: &Path, name: &str) -> Result<PathBuf> {
+    let unsafe_entry = || UnsafeEntryPath(name.to_string());
+    let segments: Vec<&str> = name.split(['/', '\\']).collect();
+    let has_drive = name.len() >= 2 && name.as_bytes()[1] == b':';
+    if name.starts_with(['/', '\\']) || has_drive || segments.contains(&"..") {
+        return Err(unsafe_entry().into());
+    }
+    let out_path = segments
+        .iter()
+        .filter(|segment| !segment.is_empty() && **segment != ".")
+        .fold(dest.to_path_buf(), |path, segment| path.join(segment));
+    let root = dest
+        .canonicalize()
+        .with_context(|| format!("resolve {}", dest.display()))?;
+    let existing = out_path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(dest);
+    let resolved = existing
+        .canonicalize()
+        .with_context(|| format!("resolve {}", existing.display()))?;
+    if !resolved.starts_with(&root) {
+        return Err(unsafe_entry().into());
+    }
+    Ok(out_path)
+}
+
+/// Copies man pages (`man/man1/tool.1`, `man/tool.conf.5.gz`, ...) under `root` into
+/// `man_dir/manN` by section, replacing older copies so upgrades refresh them.
+fn install_man_pages(root: &Path, man_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut installed = Vec::new();
+    for entry in WalkDir::new(root) {
+        let entry = entry.context("walk archive")?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        let Some(section) = man_section(relative) else {
+            continue;
+        };
+        let name = entry.file_name();
+        let section_dir = man_dir.join(format!("man{section}"));
+        fs::create_dir_all(&section_dir)
+            .with_context(|| format!("create {}", section_dir.display()))?;
+        let dest = section_dir.join(name);
+        fs::copy(entry.path(), &dest)
+            .with_context(|| format!("copy man page to {}", dest.display()))?;
+        installed.push(dest);
+    }
+    Ok(installed)
+}
+
+/// The section digit of a man page, read from its extension, e.g. `1` for `man/tool.1` or
+/// `3` for `man3/tool.3p.gz`. Only files in a `man` dir, or a `manN` dir of the same section,
+/// count; a `tool.1` anywhere else is as likely a version-suffixed binary.
+fn man_section(path: &Path) -> Option<char> {
+    let lower = path.file_name()?.to_str()?.to_lowercase();
+    let name = lower.strip_suffix(".gz").unwrap_or(&lower);
+    let (stem, ext) = name.rsplit_once('.')?;
+    let section = ext.chars().next().filter(|ch| matches!(ch, '1'..='9'))?;
+    // A stem ending in a digit is a version number such as `tool-1.2.1`, not a page.
+    let versioned = stem.chars().last().is_none_or(|ch| ch.is_ascii_digit());
+    if versioned || ext.len() > 3 || !ext[1..].chars().all(|ch| ch.is_ascii_lowercase()) {
+        return None;
+    }
+    let dir = path.parent()?.file_name()?.to_str()?.to_lowercase();
+    let dir_section = dir.strip_prefix("man")?;
+    (dir_section.is_empty() || dir_section.starts_with(section)).then_some(section)
+}
+
+fn corrupt_download(path: &Path) -> String {
+    format!(
+        "{} may be corrupt or an incomplete download; retry, or check it against the release checksum",
+        path.display()
+    )
+}
+
+fn extract_gzip(gzip_path: &Path, repo_name: &str, options: &Options) -> Result<ExtractedPaths> {
+    extract_single(gzip_path, ".gz", repo_name, options, |file| {
+        Ok(Box::new(flate2::read::GzDecoder::new(file)))
+    })
+}
+
+fn extract_zstd(zstd_path: &Path, repo_name: &str, options: &Options) -> Result<ExtractedPaths> {
+    extract_single(zstd_path, ".zst", repo_name, options, |file| {
+        Ok(Box::new(zstd::Decoder::new(file)?))
+    })
+}
+
+/// Decompresses a single-file download, named after the asset minus `suffix`.
+fn extract_single(
+    path: &Path,
+    suffix: &str,
+    repo_name: &str,
+    options: &Options,
+    decoder: impl FnOnce(fs::File) -> io::Result<Box<dyn Read>>,
+) -> Result<ExtractedPaths> {
+    let temp_dir = scratch_dir(options)?;
+    match decompress_binaries(path, suffix, temp_dir.path(), repo_name, options, decoder) {
+        Ok((primary, extras)) => Ok(ExtractedPaths {
+            primary,
+            extras,
+            _temp_dir: temp_dir,
+        }),
+        Err(err) => Err(keep_temp_dir(err, temp_dir, options)),
+    }
+}
+
+fn decompress_binaries(
+    path: &Path,
+    suffix: &str,
+    extract_root: &Path,
+    repo_name: &str,
+    options: &Options,
+    decoder: impl FnOnce(fs::File) -> io::Result<Box<dyn Read>>,
+) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let filename = path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .unwrap_or("download");
+    let dest_name = strip_suffix_ignore_case(filename, suffix);
+    let dest = extract_root.join(dest_name);
+
+    let input = fs::File::open(path).with_context(|| format!("open {}", path.display()))?;
+    let mut decoder = decoder(input)
+        .with_context(|| format!("open {}", path.display()))
+        .with_context(|| corrupt_download(path))?;
+    let mut output =
+        fs::File::create(&dest).with_context(|| format!("create {}", dest.display()))?;
+    io::copy(&mut decoder, &mut output)
+        .with_context(|| format!("write {}", dest.display()))
+        .with_context(|| corrupt_download(path))?;
+
+    if dest_name == repo_name {
+        Ok((dest, Vec::new()))
+    } else {
+        find_binaries(extract_root, repo_name, options)
+    }
+}
+
+/// A fresh temp dir under `options.temp_dir`, or the system temp dir (`TMPDIR`) by default.
+fn scratch_dir(options: &Options) -> Result<TempDir> {
+    let Some(base) = &options.temp_dir else {
+        return tempfile::tempdir().context("create temp dir");
+    };
+    fs::create_dir_all(base).with_context(|| format!("create {}", base.display()))?;
+    tempfile::tempdir_in(base).with_context(|| format!("create temp dir in {}", base.display()))
+}
+
+/// Under `--keep-temp`, persists `temp_dir` past a failure and names it in the error.
+fn keep_temp_dir(err: anyhow::Error, temp_dir: TempDir, options: &Options) -> anyhow::Error {
+    if !options.keep_temp {
+        return err;
+    }
+    let path = temp_dir.keep();
+    err.context(format!("kept temp dir {}", path.display()))
+}
+
+fn extract_zip(archive_path: &Path, dest: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .with_context(|| format!("open zip {}", archive_path.display()))?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .with_context(|| format!("read zip entry {i}"))?;
+        let out_path = contained_entry_path(dest, &zip_entry_name(&entry))?;
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path)
+                .with_context(|| format!("create {}", out_path.display()))?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+        }
+        let mut outfile = fs::File::create(&out_path)
+            .with_context(|| format!("create {}", out_path.display()))?;
+        io::copy(&mut entry, &mut outfile)
+            .with_context(|| format!("write {}", out_path.display()))?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&out_path, fs::Permissions::from_mode(mode & 0o777))
+                .with_context(|| format!("chmod {}", out_path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Names without the zip UTF-8 flag are CP437 by spec, which the zip crate decodes, but
+/// plenty of Windows tools write UTF-8 without setting the flag; raw bytes that are valid
+/// UTF-8 are taken as UTF-8.
+fn zip_entry_name(entry: &zip::read::ZipFile) -> String {
+    match std::str::from_utf8(entry.name_raw()) {
+        Ok(name) => name.to_string(),
+        Err(_) => entry.name().to_string(),
+    }
+}
+
+fn extract_tar_gz(archive_path: &Path, dest: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
+    unpack_tar(tar::Archive::new(flate2::read::GzDecoder::new(file)), dest)
+        .with_context(|| format!("unpack {}", archive_path.display()))
+}
+
+fn extract_tar_xz(archive_path: &Path, dest: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
+    unpack_tar(tar::Archive::new(xz2::read::XzDecoder::new(file)), dest)
+        .with_context(|| format!("unpack {}", archive_path.display()))
+}
+
+fn extract_tar_bz2(archive_path: &Path, dest: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
+    unpack_tar(tar::Archive::new(bzip2::read::BzDecoder::new(file)), dest)
+        .with_context(|| format!("unpack {}", archive_path.display()))
+}
+
+fn extract_tar_zst(archive_path: &Path, dest: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
+    let decoder = zstd::Decoder::new(file)
+        .with_context(|| format!("open zstd {}", archive_path.display()))?;
+    unpack_tar(tar::Archive::new(decoder), dest)
+        .with_context(|| format!("unpack {}", archive_path.display()))
+}
+
+/// Unpacks entry by entry so each path is checked with `contained_entry_path` first.
+fn unpack_tar(mut archive: tar::Archive<impl Read>, dest: &Path) -> Result<()> {
+    for entry in archive.entries().context("read tar entries")? {
+        let mut entry = entry.context("read tar entry")?;
+        let name = entry
+            .path()
+            .context("read tar entry path")?
+            .to_string_lossy()
+            .into_owned();
+        contained_entry_path(dest, &name)?;
+        entry
+            .unpack_in(dest)
+            .with_context(|| format!("unpack {name}"))?;
+    }
+    Ok(())
+}
+
+fn extract_cab(archive_path: &Path, dest: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
+    let mut cabinet =
+        cab::Cabinet::new(file).with_context(|| format!("open cab {}", archive_path.display()))?;
+
+    let names: Vec<String> = cabinet
+        .folder_entries()
+        .flat_map(|folder| folder.file_entries())
+        .map(|entry| entry.name().to_string())
+        .collect();
+    for name in names {
+        let out_path = contained_entry_path(dest, &name)?;
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+        }
+        let mut reader = cabinet
+            .read_file(&name)
+            .with_context(|| format!("read cab entry {name}"))?;
+        let mut outfile = fs::File::create(&out_path)
+            .with_context(|| format!("create {}", out_path.display()))?;
+        io::copy(&mut reader, &mut outfile)
+            .with_context(|| format!("write {}", out_path.display()))?;
+    }
+
+    Ok(())
+}
+
+fn extract_7z(archive_path: &Path, dest: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive_path).with_context(|| format!("open {}", archive_path.display()))?;
+    let mut refused = None;
+    let result = sevenz_rust::decompress_with_extract_fn(file, dest, |entry, reader, _| {
+        match contained_entry_path(dest, entry.name()) {
+            Ok(out_path) => sevenz_rust::default_entry_extract_fn(entry, reader, &out_path),
+            Err(err) => {
+                refused = Some(err);
+                Err(sevenz_rust::Error::other("unsafe archive entry"))
+            }
+        }
+    });
+    if let Some(err) = refused {
+        return Err(err);
+    }
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) if is_7z_encryption_error(&err) => bail!(
+            "{} is encrypted; password-protected .7z archives are not supported",
+            archive_path.display()
+        ),
+        Err(err) => Err(anyhow::Error::new(err))
+            .with_context(|| format!("unpack {}", archive_path.display()))
+            .with_context(|| corrupt_download(archive_path)),
+    }
+}
+
+fn is_7z_encryption_error(err: &sevenz_rust::Error) -> bool {
+    match err {
+        sevenz_rust::Error::PasswordRequired | sevenz_rust::Error::MaybeBadPassword(_) => true,
+        sevenz_rust::Error::UnsupportedCompressionMethod(method) => {
+            method.to_uppercase().contains("AES")
+        }
+        _ => false,
+    }
+}
+
+/// Turns an archive entry name into a relative path, dropping `..` and root components.
+fn sanitized_entry_path(name: &str) -> Option<PathBuf> {
+    let relative: PathBuf = name
+        .split(['\\', '/'])
+        .filter(|segment| !segment.is_empty() && *segment != "." && *segment != "..")
+        .collect();
+    if relative.as_os_str().is_empty() {
+        None
+    } else {
+        Some(relative)
+    }
+}
+
+fn find_binaries(
+    root: &Path,
+    repo_name: &str,
+    options: &Options,
+) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let target = binary_name(repo_name).to_lowercase();
+    let fallback = repo_name.to_lowercase();
+    // Windows archives name the binary `Tool.exe`, `tool.EXE`, or plain `tool`; all count.
+    let strip_exe = |name: &str| -> String {
+        match name.strip_suffix(".exe").filter(|_| cfg!(windows)) {
+            Some(stem) => stem.to_string(),
+            None => name.to_string(),
+        }
+    };
+    let fallback_stem = strip_exe(&fallback);
+
+    let mut exact_matches = Vec::new();
+    let mut candidates = Vec::new();
+    let mut probable_matches = Vec::new();
+
+    let canonical_root = root
+        .canonicalize()
+        .with_context(|| format!("resolve {}", root.display()))?;
+    let inside_root = |path: &Path| {
+        path.canonicalize()
+            .is_ok_and(|resolved| resolved.starts_with(&canonical_root))
+    };
+
+    // Symlinks are skipped unless followed, and followed ones must stay inside the archive.
+    for entry in WalkDir::new(root).follow_links(options.follow_symlinks) {
+        let entry = match entry {
+            Err(err) if err.loop_ancestor().is_some() => continue,
+            entry => entry.context("walk archive")?,
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        if options.follow_symlinks && !inside_root(path) {
+            continue;
+        }
+        let name = path
+            .file_name()
+            .and_then(OsStr::to_str)
+            .unwrap_or("")
+            .to_lowercase();
+        if name == target || name == fallback || strip_exe(&name) == fallback_stem {
+            exact_matches.push(path.to_path_buf());
+        }
+        candidates.push(path.to_path_buf());
+        if (options.include_hidden || !name.starts_with('.')) && is_probable_binary_candidate(path)
+        {
+            probable_matches.push(path.to_path_buf());
+        }
+    }
+
+    let primary = if let Some(inner) = &options.binary_path {
+        sanitized_entry_path(inner)
+            .map(|relative| root.join(relative))
+            .filter(|path| path.is_file() && inside_root(path))
+            .filter(|path| options.follow_symlinks || !path.is_symlink())
+            .with_context(|| format!("{inner} not found in archive"))?
+    } else if let Some(bin) = &options.bin {
+        let wanted = bin.to_lowercase();
+        let mut named: Vec<&PathBuf> = candidates
+            .iter()
+            .filter(|path| {
+                let name = path.file_name().and_then(OsStr::to_str).unwrap_or("");
+                let stem = path.file_stem().and_then(OsStr::to_str).unwrap_or("");
+                name.to_lowercase() == wanted || stem.to_lowercase() == wanted
+            })
+            .collect();
+        named.sort_by_key(|path| path.to_string_lossy().len());
+        match named.first() {
+            Some(path) => (*path).clone(),
+            None => bail!(
+                "no binary named {bin} in archive; found: {}",
+                relative_names(root, &probable_matches).join(", ")
+            ),
+        }
+    } else if exact_matches.len() == 1 {
+        exact_matches.remove(0)
+    } else if exact_matches.len() > 1 {
+        exact_matches.sort_by_key(|path| path.to_string_lossy().len());
+        exact_matches.remove(0)
+    } else if probable_matches.len() == 1 {
+        probable_matches[0].clone()
+    } else if probable_matches.len() > 1 && options.fail_on_multiple_candidates {
+        let mut names = relative_names(root, &probable_matches);
+        names.sort();
+        bail!(
+            "multiple candidate binaries found: {}; none matches {}",
+            names.join(", "),
+            repo_name
+        );
+    } else if probable_matches.len() > 1 && options.prompt_bin {
+        probable_matches.sort_by_key(|path| path.to_string_lossy().len());
+        let names = relative_names(root, &probable_matches);
+        let choice = prompt_for_binary(&mut io::stdin().lock(), &mut io::stderr(), &names)?;
+        probable_matches[choice].clone()
+    } else if probable_matches.len() > 1 {
+        let mut bin_matches: Vec<PathBuf> = BINARY_DIRS
+            .iter()
+            .map(|dir| {
+                probable_matches
+                    .iter()
+                    .filter(|path| path_has_component(path, dir))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .find(|matches| !matches.is_empty())
+            .unwrap_or_default();
+        if bin_matches.len() == 1 {
+            bin_matches.remove(0)
+        } else {
+            let mut sorted = probable_matches.clone();
+            sorted.sort_by_key(|path| path.to_string_lossy().len());
+            sorted.remove(0)
+        }
+    } else if candidates.len() == 1 {
+        candidates.remove(0)
+    } else {
+        bail!("unable to locate extracted binary");
+    };
+
+    let mut extras = Vec::new();
+    if options.bin.is_none() && !probable_matches.is_empty() {
+        extras = probable_matches
+            .into_iter()
+            .filter(|path| *path != primary)
+            .collect();
+        extras.sort_by_key(|path| path.to_string_lossy().len());
+    }
+
+    Ok((primary, extras))
+}
+
+/// Archive directories that hold executables, in the order `find_binaries` prefers them.
+const BINARY_DIRS: [&str; 3] = ["bin", "sbin", "libexec"];
+
+fn relative_names(root: &Path, paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| {
+            path.strip_prefix(root)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Lists `names` on `output` and reads a 1-based choice from `input`; an empty answer takes
+/// the first.
+fn prompt_for_binary(
+    input: &mut impl io::BufRead,
+    output: &mut impl Write,
+    names: &[String],
+) -> Result<usize> {
+    writeln!(output, "yoink: the archive holds several binaries:").context("write prompt")?;
+    for (index, name) in names.iter().enumerate() {
+        writeln!(output, "  {}) {name}", index + 1).context("write prompt")?;
+    }
+    write!(output, "install which? [1] ").context("write prompt")?;
+    output.flush().context("flush prompt")?;
+    let mut answer = String::new();
+    input.read_line(&mut answer).context("read choice")?;
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Ok(0);
+    }
+    match answer.parse::<usize>() {
+        Ok(choice) if (1..=names.len()).contains(&choice) => Ok(choice - 1),
+        _ => bail!("invalid choice {answer:?}; pass --bin to pick a binary"),
+    }
+}
+
+fn path_has_component(path: &Path, needle: &str) -> bool {
+    path.components().any(|component| {
+        component
+            .as_os_str()
+            .to_str()
+            .map(|segment| segment.eq_ignore_ascii_case(needle))
+            .unwrap_or(false)
+    })
+}
+
+fn is_probable_binary_candidate(path: &Path) -> bool {
+    let name = match path.file_name().and_then(OsStr::to_str) {
+        Some(name) => name.to_lowercase(),
+        None => return false,
+    };
+
+    if name.starts_with("readme")
+        || name.starts_with("license")
+        || name.starts_with("changelog")
+        || name.starts_with("notice")
+        || name.starts_with("copying")
+    {
+        return false;
+    }
+
+    if path_has_component(path, "share")
+        || path_has_component(path, "doc")
+        || path_has_component(path, "docs")
+        || path_has_component(path, "man")
+        || path_has_component(path, "completions")
+        || path_has_component(path, "completion")
+    {
+        return false;
+    }
+
+    if let Some(ext) = Path::new(&name).extension().and_then(OsStr::to_str) {
+        let ext = ext.to_lowercase();
+        if matches!(
+            ext.as_str(),
+            "md" | "txt"
+                | "rst"
+                | "json"
+                | "yaml"
+                | "yml"
+                | "toml"
+                | "ini"
+                | "cfg"
+                | "conf"
+                | "1"
+                | "2"
+                | "3"
+                | "4"
+                | "5"
+                | "6"
+                | "7"
+                | "8"
+                | "9"
+                | "asc"
+                | "sig"
+                | "sha256"
+                | "sha512"
+                | "md5"
+        ) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Returns whether the copy needed the sudo fallback.
+fn install_payload(payload_path: &Path, dest: &Path, executable: bool) -> Result<bool> {
+    if let Err(err) = try_install_binary(payload_path, dest, executable) {
+        if is_permission_denied(&err) {
+            install_with_sudo(payload_path, dest, executable)?;
+            return Ok(true);
+        }
+        return Err(err);
+    }
+    Ok(false)
+}
+
+/// Makes `try_install_binary` fail as though the destination were unwritable, so tests that
+/// run as root still reach the sudo fallback.
+#[cfg(test)]
+static FORCE_SUDO: AtomicBool = AtomicBool::new(false);
+
+fn try_install_binary(payload_path: &Path, dest: &Path, executable: bool) -> Result<()> {
+    #[cfg(test)]
+    if FORCE_SUDO.load(atomic::Ordering::SeqCst) {
+        return Err(io::Error::from(io::ErrorKind::PermissionDenied).into());
+    }
+    install_binary(payload_path, dest, executable)
+}
+
+/// The `path_warning` for an install, given only the first time per process that
+/// `install_dir` is missing from `path_var`. Set `YOINK_NO_PATH_CHECK` to silence it.
+fn path_warning_once(install_dir: &Path, path_var: &OsStr, options: &Options) -> Option<String> {
+    if options.env("YOINK_NO_PATH_CHECK").is_some() || PATH_WARNED.load(atomic::Ordering::SeqCst) {
+        return None;
+    }
+    let warning = path_warning(install_dir, path_var)?;
+    (!PATH_WARNED.swap(true, atomic::Ordering::SeqCst)).then_some(warning)
+}
+
+fn path_warning(install_dir: &Path, path_var: &OsStr) -> Option<String> {
+    if env::split_paths(path_var).any(|dir| dir == install_dir) {
+        return None;
+    }
+    let line = path_setup_line(None)
+        .or_else(|_| path_setup_line(Some("bash")))
+        .ok()?;
+    Some(format!(
+        "{} is not on your PATH; add it with:\n    {line}",
+        install_dir.display()
+    ))
+}
+
+/// Shell line that puts the install dir on `PATH`, for `bash`, `zsh`, `sh`, or `fish`.
+/// Without a shell, uses the basename of `$SHELL`, falling back to bash syntax.
+pub fn path_setup_line(shell: Option<&str>) -> Result<String> {
+    let from_env = env::var("SHELL").ok();
+    let shell = shell
+        .or_else(|| from_env.as_deref().and_then(|path| path.rsplit('/').next()))
+        .unwrap_or("bash");
+    let dir = default_install_dir(&Options::default())?;
+    let home = env::var("HOME").ok().map(PathBuf::from);
+    let dir = match home.as_deref().and_then(|home| dir.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "$HOME".to_string(),
+        Some(rest) => format!("$HOME/{}", rest.display()),
+        None => dir.display().to_string(),
+    };
+    match shell {
+        "bash" | "zsh" | "sh" => Ok(format!("export PATH=\"{dir}:$PATH\"")),
+        "fish" => Ok(format!("fish_add_path \"{dir}\"")),
+        _ => bail!("unsupported shell {shell}; expected bash, zsh, sh, or fish"),
+    }
+}
+
+fn default_install_dir(options: &Options) -> Result<PathBuf> {
+    if let Some(dir) = options.env("YOINKDIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Some(dir) = options.env("YOINK_BIN_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    if cfg!(windows) {
+        let base = dirs_next::data_local_dir().context("determine local data dir")?;
+        return Ok(base.join("Programs").join("yoink").join("bin"));
+    }
+    if let Some(dir) = env::var_os("XDG_BIN_HOME").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    let home = env::var("HOME")
+        .ok()
+        .map(PathBuf::from)
+        .or_else(dirs_next::home_dir)
+        .context("determine home dir")?;
+    Ok(home.join(".local").join("bin"))
+}
+
+fn binary_name(repo_name: &str) -> String {
+    if cfg!(windows) {
+        format!("{repo_name}.exe")
+    } else {
+        repo_name.to_string()
+    }
+}
+
+/// On Windows, gives a PE binary stored without an extension a `.exe` so it stays runnable
+/// once installed.
+fn with_exe_suffix(path: &Path, name: OsString) -> OsString {
+    if !cfg!(windows) || Path::new(&name).extension().is_some() {
+        return name;
+    }
+    if !read_header(path).is_ok_and(|header| header.starts_with(b"MZ")) {
+        return name;
+    }
+    let mut name = name;
+    name.push(".exe");
+    name
+}
+
+fn set_executable(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(path)
+            .with_context(|| format!("stat {}", path.display()))?
+            .permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(path, perms).with_context(|| format!("chmod {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Records which repo wrote each file `DestExists::Rename` placed in a dir, so downloading a
+/// repo again replaces its own file rather than being renamed away from it.
+const DEST_OWNERS_FILE: &str = ".yoink-owners.toml";
+
+/// Under `DestExists::Rename`, moves `dest` aside to `name-owner` (`name-owner.exe`) when
+/// another repo's file already holds its name. Fails rather than replace a `name-owner` that
+/// isn't `repo`'s either.
+fn unclaimed_dest(
+    payload_path: &Path,
+    dest: PathBuf,
+    repo: &str,
+    owner: &str,
+    policy: DestExists,
+) -> Result<PathBuf> {
+    if policy != DestExists::Rename {
+        return Ok(dest);
+    }
+    let (Some(dir), Some(name)) = (dest.parent(), dest.file_name().and_then(OsStr::to_str)) else {
+        return Ok(dest);
+    };
+    let renamed = match name.rsplit_once('.') {
+        Some((stem, ext)) if ext.eq_ignore_ascii_case("exe") => format!("{stem}-{owner}.{ext}"),
+        _ => format!("{name}-{owner}"),
+    };
+    with_dest_owners(dir, |owners| {
+        // Files yoink didn't record are ours only if they're what we'd write anyway.
+        let ours = |name: &str| -> Result<bool> {
+            let path = dir.join(name);
+            Ok(match owners.get(name) {
+                _ if !path.exists() => true,
+                Some(recorded) => recorded == repo,
+                None => same_contents(payload_path, &path)?,
+            })
+        };
+        let name = if ours(name)? {
+            name
+        } else if ours(&renamed)? {
+            &renamed
+        } else {
+            bail!(
+                "{} and {} already belong to other repos",
+                dest.display(),
+                dir.join(&renamed).display()
+            );
+        };
+        owners.insert(name.to_string(), repo.to_string());
+        Ok(dir.join(name))
+    })
+}
+
+/// Applies `update` to `dir`'s `DEST_OWNERS_FILE` under an exclusive lock.
+fn with_dest_owners<T>(
+    dir: &Path,
+    update: impl FnOnce(&mut BTreeMap<String, String>) -> Result<T>,
+) -> Result<T> {
+    let path = dir.join(DEST_OWNERS_FILE);
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("open {}", path.display()))?;
+    file.lock_exclusive()
+        .with_context(|| format!("lock {}", path.display()))?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .with_context(|| format!("read {}", path.display()))?;
+    let mut owners: BTreeMap<String, String> =
+        toml::from_str(&contents).with_context(|| format!("parse {}", path.display()))?;
+    let result = update(&mut owners)?;
+    let contents = toml::to_string(&owners).context("serialize dest owners")?;
+    file.set_len(0)
+        .and_then(|()| file.seek(SeekFrom::Start(0)))
+        .and_then(|_| file.write_all(contents.as_bytes()))
+        .with_context(|| format!("write {}", path.display()))?;
+    file.unlock()
+        .with_context(|| format!("unlock {}", path.display()))?;
+    Ok(result)
+}
+
+fn same_contents(a: &Path, b: &Path) -> Result<bool> {
+    let len = |path: &Path| {
+        fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .with_context(|| format!("stat {}", path.display()))
+    };
+    if len(a)? != len(b)? {
+        return Ok(false);
+    }
+    let read = |path: &Path| fs::read(path).with_context(|| format!("read {}", path.display()));
+    Ok(read(a)? == read(b)?)
+}
+
+/// Writes a downloaded binary to `dest`, applying `policy` if something is already there.
+fn place_binary(
+    payload_path: &Path,
+    dest: &Path,
+    policy: DestExists,
+    executable: bool,
+) -> Result<()> {
+    if fs::symlink_metadata(dest).is_ok() {
+        match policy {
+            // `unclaimed_dest` has already moved aside from files that aren't ours.
+            DestExists::Overwrite | DestExists::Rename => {}
+            DestExists::Error => bail!("{} already exists", dest.display()),
+            DestExists::Skip => return Ok(()),
+            DestExists::Backup => {
+                let mut backup = dest.as_os_str().to_os_string();
+                backup.push(".bak");
+                let mut backup = PathBuf::from(backup);
+                if fs::symlink_metadata(&backup).is_ok() {
+                    let mut stamped = dest.as_os_str().to_os_string();
+                    stamped.push(format!(".{}.bak", unix_now()));
+                    backup = PathBuf::from(stamped);
+                }
+                fs::rename(dest, &backup).with_context(|| {
+                    format!("back up {} to {}", dest.display(), backup.display())
+                })?;
+            }
+        }
+    }
+    install_binary(payload_path, dest, executable)
+}
+
+/// Copies into a temp file beside `dest` and renames it into place, so readers never see a
+/// half-written binary. Without `executable`, the payload's own mode (e.g. from the archive)
+/// is kept.
+fn install_binary(payload_path: &Path, dest: &Path, executable: bool) -> Result<()> {
+    let dir = match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let staged = tempfile::Builder::new()
+        .prefix(".yoink-")
+        .tempfile_in(dir)
+        .with_context(|| format!("create temp file in {}", dir.display()))?;
+    fs::copy(payload_path, staged.path())
+        .with_context(|| format!("copy to {}", staged.path().display()))?;
+    if executable {
+        set_executable(staged.path())?;
+    }
+
+    let staged = match staged.persist(dest) {
+        Ok(_) => return Ok(()),
+        // Windows won't rename over a running exe, but will rename the running exe aside.
+        Err(err) if cfg!(windows) && dest.exists() => {
+            let mut aside = dest.as_os_str().to_os_string();
+            aside.push(".old");
+            let _ = fs::remove_file(&aside);
+            fs::rename(dest, &aside).map_err(|_| err.error)?;
+            err.file
+        }
+        Err(err) => return Err(err.error).with_context(|| format!("rename to {}", dest.display())),
+    };
+    if let Err(err) = staged.persist(dest) {
+        if is_in_use_error(&err.error) {
+            return Err(err.error).with_context(|| {
+                format!(
+                    "{} is in use by a running process; close it and try again",
+                    dest.display()
+                )
+            });
+        }
+        return Err(err.error).with_context(|| format!("rename to {}", dest.display()));
+    }
+    Ok(())
+}
+
+/// Points `dest` at `stored` through a symlink renamed into place, falling back to `sudo mv`
+/// like `install_payload`. Windows gets a copy instead. Returns whether sudo was needed.
+fn link_payload(stored: &Path, dest: &Path) -> Result<bool> {
+    #[cfg(unix)]
+    {
+        let dir = match dest.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let staged = tempfile::Builder::new()
+            .prefix(".yoink-")
+            .make_in(dir, |path| std::os::unix::fs::symlink(stored, path));
+        match staged {
+            Ok(link) => {
+                link.persist(dest)
+                    .map_err(|err| err.error)
+                    .with_context(|| format!("rename link to {}", dest.display()))?;
+                Ok(false)
+            }
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                let temp = tempfile::tempdir().context("create link dir")?;
+                let link = temp.path().join("link");
+                std::os::unix::fs::symlink(stored, &link)
+                    .with_context(|| format!("link {}", stored.display()))?;
+                install_with_sudo(&link, dest, false)?;
+                Ok(true)
+            }
+            Err(err) => Err(err).with_context(|| format!("link {}", stored.display())),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        install_payload(stored, dest, true)
+    }
+}
+
+/// Windows refuses to overwrite an executable that is currently running.
+fn is_in_use_error(err: &io::Error) -> bool {
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    cfg!(windows)
+        && matches!(
+            err.raw_os_error(),
+            Some(ERROR_SHARING_VIOLATION) | Some(ERROR_LOCK_VIOLATION)
+        )
+}
+
+fn install_with_sudo(payload_path: &Path, dest: &Path, executable: bool) -> Result<()> {
+    #[cfg(unix)]
+    {
+        if executable {
+            set_executable(payload_path)?;
+        }
+        let status = Command::new("sudo")
+            .arg("mv")
+            .arg("--")
+            .arg(payload_path)
+            .arg(dest)
+            .status()
+            .with_context(|| {
+                format!("run sudo mv {} {}", payload_path.display(), dest.display())
+            })?;
+        if !status.success() {
+            bail!("sudo mv failed with status {}", status);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (payload_path, dest, executable);
+        bail!("install location requires permissions not supported on this platform");
+    }
+}
+
+fn is_permission_denied(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .map(|io_err| io_err.kind() == io::ErrorKind::PermissionDenied)
+            .unwrap_or(false)
+    })
+}
+
+/// Returns whether creating the dir needed the sudo fallback.
+fn ensure_install_dir(install_dir: &Path) -> Result<bool> {
+    if let Err(err) = fs::create_dir_all(install_dir) {
+        if err.kind() == io::ErrorKind::PermissionDenied {
+            create_dir_with_sudo(install_dir)
+                .with_context(|| format!("create install dir {}", install_dir.display()))?;
+            return Ok(true);
+        }
+        return Err(err).with_context(|| format!("create install dir {}", install_dir.display()));
+    }
+    Ok(false)
+}
+
+fn create_dir_with_sudo(install_dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let status = Command::new("sudo")
+            .arg("mkdir")
+            .arg("-p")
+            .arg("--")
+            .arg(install_dir)
+            .status()
+            .with_context(|| format!("run sudo mkdir -p {}", install_dir.display()))?;
+        if !status.success() {
+            bail!("sudo mkdir failed with status {}", status);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = install_dir;
+        bail!("install location requires permissions not supported on this platform");
+    }
+}
+
+/// Holds an exclusive lock on `install_dir` so concurrent yoinks don't race on the same
+/// files. The lock file lives in the data dir next to the install state, one per install dir,
+/// so the bin dir only ever holds binaries.
+fn lock_install_dir(install_dir: &Path) -> Result<fs::File> {
+    let install_dir = fs::canonicalize(install_dir).unwrap_or_else(|_| install_dir.to_path_buf());
+    let digest = Sha256::digest(install_dir.as_os_str().as_encoded_bytes());
+    let dir = data_dir()?.join("locks");
+    fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+    let path = dir.join(format!("{:x}.lock", digest));
+    let file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("open install lock for {}", install_dir.display()))?;
+    file.lock_exclusive()
+        .with_context(|| format!("lock {}", install_dir.display()))?;
+    Ok(file)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct InstallState {
+    #[serde(default)]
+    installs: BTreeMap<String, InstallEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InstallEntry {
+    version: String,
+    bin: PathBuf,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bins: Vec<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    installed_at: Option<u64>,
+    /// Installed as `owner/repo@tag`; `upgrade_all` leaves it alone.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pinned: bool,
+    /// Where the binaries were installed, so upgrades land there too. Entries recorded before
+    /// this was kept fall back to `bin`'s parent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    install_dir: Option<PathBuf>,
+    /// The versioned store dir the installed symlinks point into, for store installs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    store_path: Option<PathBuf>,
+    /// The forge host the install came from, so upgrades go back to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    host: Option<String>,
+    /// Man pages copied under `--man-dir`, removed along with the binaries.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    man_pages: Vec<PathBuf>,
+    /// Tags installed before `version`, oldest first, for `rollback`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<String>,
+}
+
+/// How many earlier versions `InstallEntry::history` keeps.
+const HISTORY_LIMIT: usize = 5;
+
+impl InstallEntry {
+    fn all_bins(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.bin).chain(self.bins.iter())
+    }
+
+    fn install_dir(&self) -> Option<&Path> {
+        self.install_dir.as_deref().or_else(|| self.bin.parent())
+    }
+}
+
+/// What `record_install` keeps about an install besides its binaries.
+#[derive(Debug, Default)]
+struct InstallMeta<'a> {
+    /// The versioned store dir the binaries link into, for store installs.
+    store_path: Option<&'a Path>,
+    /// The forge host the release came from, when it isn't implied by the repo.
+    host: Option<&'a str>,
+    /// Man pages copied alongside the binaries.
+    man_pages: &'a [PathBuf],
+}
+
+/// Records an install only once every one of `bins` is confirmed on disk, so the state
+/// never points at a file that isn't there.
+fn commit_install(
+    repo: &str,
+    version: &str,
+    bins: &[PathBuf],
+    pinned: bool,
+    meta: &InstallMeta,
+) -> Result<()> {
+    ensure_written(bins)?;
+    record_install(repo, version, bins, pinned, meta)
+}
+
+/// Fails unless each of `paths` is (or links to) a regular file.
+fn ensure_written(paths: &[PathBuf]) -> Result<()> {
+    for path in paths {
+        let metadata = fs::metadata(path)
+            .with_context(|| format!("{} missing after install", path.display()))?;
+        if !metadata.is_file() {
+            bail!("{} is not a regular file after install", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn record_install(
+    repo: &str,
+    version: &str,
+    bins: &[PathBuf],
+    pinned: bool,
+    meta: &InstallMeta,
+) -> Result<()> {
+    let state_path = state_path()?;
+    if let Some(parent) = state_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("create state dir {}", parent.display()))?;
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&state_path)
+        .with_context(|| format!("open state file {}", state_path.display()))?;
+    file.lock_exclusive()
+        .with_context(|| format!("lock state file {}", state_path.display()))?;
+
+    let mut state = read_state_locked(&mut file, StateFormat::of(&state_path))?;
+    let (primary, extras) = bins
+        .split_first()
+        .context("record install without binaries")?;
+    let mut history = Vec::new();
+    if let Some(previous) = state.installs.remove(repo) {
+        history = previous.history;
+        history.push(previous.version);
+        history.retain(|tag| tag != version);
+        let excess = history.len().saturating_sub(HISTORY_LIMIT);
+        history.drain(..excess);
+    }
+    state.installs.insert(
+        repo.to_string(),
+        InstallEntry {
+            version: version.to_string(),
+            bin: primary.to_path_buf(),
+            bins: extras.to_vec(),
+            installed_at: Some(unix_now()),
+            pinned,
+            install_dir: primary.parent().map(Path::to_path_buf),
+            store_path: meta.store_path.map(Path::to_path_buf),
+            host: meta.host.map(str::to_string),
+            man_pages: meta.man_pages.to_vec(),
+            history,
+        },
+    );
+    write_state_locked(&mut file, &state, StateFormat::of(&state_path))?;
+    file.unlock()
+        .with_context(|| format!("unlock state file {}", state_path.display()))?;
+    Ok(())
+}
+
+/// Applies `update` to a recorded install under the state lock.
+fn update_install(repo: &str, update: impl FnOnce(&mut InstallEntry)) -> Result<()> {
+    let state_path = state_path()?;
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&state_path)
+        .with_context(|| format!("open state file {}", state_path.display()))?;
+    file.lock_exclusive()
+        .with_context(|| format!("lock state file {}", state_path.display()))?;
+
+    let mut state = read_state_locked(&mut file, StateFormat::of(&state_path))?;
+    let entry = state
+        .installs
+        .get_mut(repo)
+        .with_context(|| format!("{repo} not installed"))?;
+    update(entry);
+    write_state_locked(&mut file, &state, StateFormat::of(&state_path))?;
+    file.unlock()
+        .with_context(|| format!("unlock state file {}", state_path.display()))?;
+    Ok(())
+}
+
+fn remove_install(repo: &str, prune_store: bool) -> Result<()> {
+    let state_path = state_path()?;
+    if !state_path.exists() {
+        bail!("no installs recorded");
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&state_path)
+        .with_context(|| format!("open state file {}", state_path.display()))?;
+    file.lock_exclusive()
+        .with_context(|| format!("lock state file {}", state_path.display()))?;
+
+    let mut state = read_state_locked(&mut file, StateFormat::of(&state_path))?;
+    let entry = state
+        .installs
+        .remove(repo)
+        .with_context(|| format!("{} not installed", repo))?;
+
+    for bin in entry.all_bins().chain(&entry.man_pages) {
+        let result = if bin.is_symlink() {
+            fs::remove_file(bin)
+        } else if bin.is_dir() {
+            fs::remove_dir_all(bin)
+        } else {
+            fs::remove_file(bin)
+        };
+        match result {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => {
+                file.unlock()
+                    .with_context(|| format!("unlock state file {}", state_path.display()))?;
+                return Err(err).with_context(|| format!("remove {}", bin.display()));
+            }
+        }
+    }
+    // The store keeps one dir per version under `store/owner/repo`; drop them all.
+    if let Some(repo_store) = entry
+        .store_path
+        .as_deref()
+        .and_then(Path::parent)
+        .filter(|_| prune_store)
+    {
+        if let Err(err) = fs::remove_dir_all(repo_store) {
+            if err.kind() != io::ErrorKind::NotFound {
+                file.unlock()
+                    .with_context(|| format!("unlock state file {}", state_path.display()))?;
+                return Err(err).with_context(|| format!("remove {}", repo_store.display()));
+            }
+        }
+    }
+
+    write_state_locked(&mut file, &state, StateFormat::of(&state_path))?;
+    file.unlock()
+        .with_context(|| format!("unlock state file {}", state_path.display()))?;
+    Ok(())
+}
+
+fn load_state() -> Result<InstallState> {
+    let state_path = state_path()?;
+    if !state_path.exists() {
+        return Ok(InstallState::default());
+    }
+
+    let mut file = fs::File::open(&state_path)
+        .with_context(|| format!("open state file {}", state_path.display()))?;
+    file.lock_shared()
+        .with_context(|| format!("lock state file {}", state_path.display()))?;
+    let state = read_state_locked(&mut file, StateFormat::of(&state_path))?;
+    file.unlock()
+        .with_context(|| format!("unlock state file {}", state_path.display()))?;
+    Ok(state)
+}
+
+/// On-disk encodings for the install state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StateFormat {
+    Json,
+    Toml,
+}
+
+impl StateFormat {
+    fn of(path: &Path) -> Self {
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            StateFormat::Toml
+        } else {
+            StateFormat::Json
+        }
+    }
+}
+
+fn read_state_locked(file: &mut fs::File, format: StateFormat) -> Result<InstallState> {
+    file.seek(SeekFrom::Start(0)).context("seek state file")?;
+    let mut buf = String::new();
+    file.read_to_string(&mut buf).context("read state file")?;
+    let state = if buf.trim().is_empty() {
+        InstallState::default()
+    } else {
+        match format {
+            StateFormat::Json => serde_json::from_str(&buf).context("parse state json")?,
+            StateFormat::Toml => toml::from_str(&buf).context("parse state toml")?,
+        }
+    };
+    Ok(state)
+}
+
+fn write_state_locked(
+    file: &mut fs::File,
+    state: &InstallState,
+    format: StateFormat,
+) -> Result<()> {
+    file.set_len(0).context("truncate state file")?;
+    file.seek(SeekFrom::Start(0)).context("seek state file")?;
+    match format {
+        StateFormat::Json => {
+            serde_json::to_writer_pretty(&mut *file, state).context("write state json")?;
+            file.write_all(b"\n").context("write state newline")?;
+        }
+        StateFormat::Toml => {
+            let toml = toml::to_string_pretty(state).context("encode state toml")?;
+            file.write_all(toml.as_bytes())
+                .context("write state toml")?;
+        }
+    }
+    file.sync_all().context("sync state file")?;
+    Ok(())
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct InfoCache {
+    #[serde(default)]
+    entries: BTreeMap<String, InfoCacheEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct InfoCacheEntry {
+    fetched_at: u64,
+    info: ReleaseInfo,
+}
+
+fn info_cache_path() -> Result<PathBuf> {
+    let base = dirs_next::cache_dir()
+        .or_else(|| dirs_next::home_dir().map(|dir| dir.join(".cache")))
+        .context("determine cache dir")?;
+    Ok(base.join("yoink").join("info.json"))
+}
+
+fn read_info_cache() -> Result<InfoCache> {
+    let path = info_cache_path()?;
+    match fs::read_to_string(&path) {
+        // A corrupt cache is only a missed optimization, so start over rather than fail.
+        Ok(buf) => Ok(serde_json::from_str(&buf).unwrap_or_default()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(InfoCache::default()),
+        Err(err) => Err(err).with_context(|| format!("read {}", path.display())),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+fn cached_release_info(key: &str, max_age: Duration) -> Result<Option<ReleaseInfo>> {
+    let mut cache = read_info_cache()?;
+    let Some(entry) = cache.entries.remove(key) else {
+        return Ok(None);
+    };
+    if unix_now().saturating_sub(entry.fetched_at) < max_age.as_secs() {
+        Ok(Some(entry.info))
+    } else {
+        Ok(None)
+    }
+}
+
+fn store_release_info(key: &str, info: &ReleaseInfo) -> Result<()> {
+    let path = info_cache_path()?;
+    let mut cache = read_info_cache()?;
+    cache.entries.insert(
+        key.to_string(),
+        InfoCacheEntry {
+            fetched_at: unix_now(),
+            info: info.clone(),
+        },
+    );
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+    }
+    let json = serde_json::to_string_pretty(&cache).context("encode info cache")?;
+    fs::write(&path, json).with_context(|| format!("write {}", path.display()))
+}
+
+fn data_dir() -> Result<PathBuf> {
+    let base = dirs_next::data_dir()
+        .or_else(|| dirs_next::home_dir().map(|dir| dir.join(".local").join("share")))
+        .context("determine data dir")?;
+    Ok(base.join("yoink"))
+}
+
+fn store_root() -> Result<PathBuf> {
+    Ok(data_dir()?.join("store"))
+}
+
+fn state_path() -> Result<PathBuf> {
+    let dir = data_dir()?;
+    let json = dir.join("installed.json");
+    let toml = dir.join("installed.toml");
+    // The state is shared with every other run, so even isolated ones keep to its format.
+    let (wanted, other) = match env::var("YOINK_STATE_FORMAT").ok().as_deref() {
+        Some("toml") => (toml, json),
+        Some("json") => (json, toml),
+        Some(other) => bail!("YOINK_STATE_FORMAT must be json or toml, not {other:?}"),
+        // Follow whichever file is already there, so switching formats doesn't lose installs.
+        None if toml.exists() && !json.exists() => return Ok(toml),
+        None => return Ok(json),
+    };
+    if !wanted.exists() && other.exists() {
+        migrate_state(&other, &wanted)?;
+    }
+    Ok(wanted)
+}
+
+/// Rewrites the state at `from` into `to`'s format and removes `from`, so asking for the
+/// other format carries every recorded install over instead of starting empty.
+fn migrate_state(from: &Path, to: &Path) -> Result<()> {
+    let mut file =
+        fs::File::open(from).with_context(|| format!("open state file {}", from.display()))?;
+    file.lock_exclusive()
+        .with_context(|| format!("lock state file {}", from.display()))?;
+    // Another run may have migrated while we waited for the lock.
+    if to.exists() {
+        return Ok(());
+    }
+    let state = read_state_locked(&mut file, StateFormat::of(from))?;
+    let parent = to.parent().context("state path has no parent")?;
+    let mut staged = tempfile::NamedTempFile::new_in(parent).context("create state file")?;
+    write_state_locked(staged.as_file_mut(), &state, StateFormat::of(to))?;
+    staged
+        .persist(to)
+        .with_context(|| format!("write state file {}", to.display()))?;
+    fs::remove_file(from).with_context(|| format!("remove state file {}", from.display()))
+}
+
+fn display_version(version: &str) -> &str {
+    if let Some(stripped) = version.strip_prefix('v') {
+        if stripped
+            .chars()
+            .next()
+            .map(|ch| ch.is_ascii_digit())
+            .unwrap_or(false)
+        {
+            return stripped;
+        }
+    }
+    version
+}
+
+/// Numeric components of a tag like `v1.2.3-rc.1`, plus its prerelease suffix if it has one.
+fn version_key(tag: &str) -> Option<(Vec<u64>, Option<&str>)> {
+    let version = display_version(tag);
+    let core_end = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, suffix) = version.split_at(core_end);
+    let parts = core
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    let pre = suffix
+        .strip_prefix('-')
+        .map(|pre| pre.split('+').next().unwrap_or(pre));
+    Some((parts, pre))
+}
+
+/// Orders prerelease suffixes the way semver does: field by field, numbers numerically and
+/// below words, and a shorter run of equal fields first.
+fn compare_prereleases(a: &str, b: &str) -> Ordering {
+    let mut a_fields = a.split('.');
+    let mut b_fields = b.split('.');
+    loop {
+        let ordering = match (a_fields.next(), b_fields.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Orders tags by version; tags that do not parse as versions sort below those that do.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (version_key(a), version_key(b)) {
+        (Some((a_parts, a_pre)), Some((b_parts, b_pre))) => {
+            let len = a_parts.len().max(b_parts.len());
+            for i in 0..len {
+                let a_part = a_parts.get(i).copied().unwrap_or(0);
+                let b_part = b_parts.get(i).copied().unwrap_or(0);
+                match a_part.cmp(&b_part) {
+                    Ordering::Equal => continue,
+                    other => return other,
+                }
+            }
+            match (a_pre, b_pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a_pre), Some(b_pre)) => compare_prereleases(a_pre, b_pre),
+            }
+        }
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => a.cmp(b),
+    }
+}
+
+fn exit_status_code(status: ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::collections::BTreeMap;
+    use std::ffi::OsString;
+    use std::io::{BufRead, BufReader, Cursor, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn state_write_roundtrip() {
+        let mut file = tempfile::tempfile().expect("create temp file");
+        let mut installs = BTreeMap::new();
+        installs.insert(
+            "mxcl/yoink".to_string(),
+            InstallEntry {
+                version: "v0.1.0".to_string(),
+                bin: PathBuf::from("/tmp/yoink"),
+                bins: Vec::new(),
+                installed_at: None,
+                pinned: false,
+                install_dir: None,
+                store_path: None,
+                host: None,
+                man_pages: Vec::new(),
+                history: Vec::new(),
+            },
+        );
+        let state = InstallState { installs };
+
+        write_state_locked(&mut file, &state, StateFormat::Json).expect("write state");
+        let read_back = read_state_locked(&mut file, StateFormat::Json).expect("read state");
+
+        assert_eq!(read_back.installs.len(), 1);
+        let entry = read_back.installs.get("mxcl/yoink").expect("entry");
+        assert_eq!(entry.version, "v0.1.0");
+        assert_eq!(entry.bin, PathBuf::from("/tmp/yoink"));
+    }
+
+    #[test]
+    #[serial]
+    fn parse_repo_validates_shape() {
+        let (owner, name, tag) = parse_repo("mxcl/yoink", &Options::default()).expect("parse repo");
+        assert_eq!(owner, "mxcl");
+        assert_eq!(name, "yoink");
+        assert_eq!(tag, None);
+        let (_, name, tag) =
+            parse_repo("mxcl/yoink@v1.2.3", &Options::default()).expect("parse pinned repo");
+        assert_eq!(name, "yoink");
+        assert_eq!(tag.as_deref(), Some("v1.2.3"));
+        assert!(parse_repo("mxcl/yoink@", &Options::default()).is_err());
+        assert!(parse_repo("mxcl", &Options::default()).is_err());
+        assert!(parse_repo("mxcl/yoink/extra", &Options::default()).is_err());
+        assert!(parse_repo("/yoink", &Options::default()).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn parse_repo_uses_default_owner_for_bare_names() {
+        let _owner = EnvGuard::set("YOINK_DEFAULT_OWNER", "myorg");
+        let (owner, name, tag) = parse_repo("tool", &Options::default()).expect("bare name");
+        assert_eq!((owner.as_str(), name.as_str()), ("myorg", "tool"));
+        assert_eq!(tag, None);
+        let (owner, _, tag) =
+            parse_repo("tool@v1.0.0", &Options::default()).expect("bare pinned name");
+        assert_eq!(owner, "myorg");
+        assert_eq!(tag.as_deref(), Some("v1.0.0"));
+        let (owner, _, _) = parse_repo("mxcl/tool", &Options::default()).expect("explicit owner");
+        assert_eq!(owner, "mxcl");
+        assert!(is_repo_shape("tool"));
+        assert!(!is_repo_shape("/tool"));
+    }
+
+    #[test]
+    #[serial]
     fn is_repo_shape_reports_validity() {
         assert!(is_repo_shape("mxcl/yoink"));
         assert!(!is_repo_shape("mxcl"));
     }
 
     #[test]
-    fn display_version_strips_v_prefix() {
-        assert_eq!(display_version("v1.2.3"), "1.2.3");
-        assert_eq!(display_version("vbeta"), "vbeta");
-        assert_eq!(display_version("1.2.3"), "1.2.3");
+    fn display_version_strips_v_prefix() {
+        assert_eq!(display_version("v1.2.3"), "1.2.3");
+        assert_eq!(display_version("vbeta"), "vbeta");
+        assert_eq!(display_version("1.2.3"), "1.2.3");
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn present_on_path_checks_min_version() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let bin = temp.path().join("tool");
+        fs::write(&bin, "#!/bin/sh\necho 'tool version v2.3.1 (abc)'\n").expect("write tool");
+        set_executable(&bin).expect("chmod");
+        let _path = EnvGuard::set("PATH", temp.path());
+
+        assert_eq!(
+            present_on_path("mxcl/tool", None).expect("probe"),
+            Some(bin.clone())
+        );
+        assert_eq!(
+            present_on_path("mxcl/tool", Some("2.3")).expect("probe"),
+            Some(bin)
+        );
+        assert_eq!(
+            present_on_path("mxcl/tool", Some("v3.0.0")).expect("probe"),
+            None
+        );
+        assert_eq!(present_on_path("mxcl/other", None).expect("probe"), None);
+
+        let options = Options {
+            bin: Some("tool".to_string()),
+            ..Options::default()
+        };
+        assert_eq!(
+            present_on_path_with("mxcl/other", None, &options).expect("probe"),
+            Some(temp.path().join("tool"))
+        );
+    }
+
+    #[test]
+    fn compare_versions_orders_semver_tags() {
+        assert_eq!(compare_versions("v1.10.0", "v1.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0", "v1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("v1.0.0-rc.1", "v1.0.0"), Ordering::Less);
+        assert_eq!(
+            compare_versions("v1.0.0-rc.1", "v1.0.0-rc.2"),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_versions("v1.0.0-rc.10", "v1.0.0-rc.2"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_versions("v1.0.0-beta", "v1.0.0-rc.1"),
+            Ordering::Less
+        );
+        assert_eq!(compare_versions("v1.0.0-rc", "v1.0.0-rc.1"), Ordering::Less);
+        assert_eq!(
+            compare_versions("v1.0.0-rc.1+build", "v1.0.0-rc.1"),
+            Ordering::Equal
+        );
+        assert_eq!(compare_versions("nightly", "v0.1.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn token_helpers_include_expected_tokens() {
+        let os = os_tokens();
+        match env::consts::OS {
+            "macos" => assert!(os.contains(&"macos")),
+            "linux" => assert!(os.contains(&"linux")),
+            "windows" => assert!(os.contains(&"windows")),
+            other => assert!(os.contains(&other)),
+        }
+
+        let arch = arch_tokens();
+        match env::consts::ARCH {
+            "x86_64" => assert!(arch.contains(&"x86_64")),
+            "aarch64" => assert!(arch.contains(&"aarch64")),
+            "arm" => assert!(arch.contains(&"arm")),
+            other => assert!(arch.contains(&other)),
+        }
+    }
+
+    #[test]
+    fn asset_helpers_prefer_best_match() {
+        let os = os_tokens();
+        let arch = arch_tokens();
+        let best_name = format!("tool-{}-{}.tar.gz", os[0], arch[0]);
+        let assets = vec![
+            Asset {
+                name: "tool.sig".to_string(),
+                browser_download_url: "http://example.com/tool.sig".to_string(),
+            },
+            Asset {
+                name: format!("tool-{}", os[0]),
+                browser_download_url: "http://example.com/tool-os".to_string(),
+            },
+            Asset {
+                name: best_name.clone(),
+                browser_download_url: "http://example.com/tool-best".to_string(),
+            },
+        ];
+        let picked = pick_asset(&assets, "tool", None, &Options::default()).expect("pick asset");
+        assert_eq!(picked.name, best_name);
+        assert!(is_ignored_asset("foo.sha256"));
+        assert!(is_archive_name("foo.tar.gz"));
+        assert!(is_archive_name("Tool-Windows-x64.7Z"));
+        assert!(is_gzip_name("foo.gz"));
+        assert_eq!(strip_suffix_ignore_case("FOO.GZ", ".gz"), "FOO");
+        assert_eq!(strip_suffix_ignore_case("foo", ".gz"), "foo");
+        assert!(!is_gzip_name("foo.tar.gz"));
+        assert!(is_archive_name("foo.tar.zst"));
+        assert!(is_zstd_name("foo.zst"));
+        assert!(!is_zstd_name("foo.tar.zst"));
+    }
+
+    #[test]
+    fn pick_asset_errors_on_empty_assets() {
+        assert!(pick_asset(&[], "tool", None, &Options::default()).is_err());
+    }
+
+    #[test]
+    fn pick_asset_falls_back_to_ignored_assets() {
+        let assets = vec![
+            Asset {
+                name: "tool.sha256".to_string(),
+                browser_download_url: "http://example.com/tool.sha256".to_string(),
+            },
+            Asset {
+                name: "tool.sig".to_string(),
+                browser_download_url: "http://example.com/tool.sig".to_string(),
+            },
+        ];
+        let picked = pick_asset(&assets, "tool", None, &Options::default()).expect("pick asset");
+        assert!(picked.name.ends_with(".sha256") || picked.name.ends_with(".sig"));
+    }
+
+    #[test]
+    fn pick_asset_prefers_repo_stem() {
+        let os = os_tokens();
+        let arch = arch_tokens();
+        let name = format!("bun-{}-{}.zip", os[0], arch[0]);
+        let profile = format!("bun-profile-{}-{}.zip", os[0], arch[0]);
+        let assets = vec![
+            Asset {
+                name: profile.clone(),
+                browser_download_url: "http://example.com/bun-profile".to_string(),
+            },
+            Asset {
+                name: name.clone(),
+                browser_download_url: "http://example.com/bun".to_string(),
+            },
+        ];
+        let picked = pick_asset(&assets, "bun", None, &Options::default()).expect("pick asset");
+        assert_eq!(picked.name, name);
+    }
+
+    #[test]
+    fn asset_score_counts_exe() {
+        assert_eq!(asset_score("tool.exe", &[], &[], &LibcTokens::default()), 1);
+    }
+
+    #[test]
+    fn find_binaries_prefers_shortest_exact() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::create_dir_all(root.join("bin")).expect("mkdir");
+        fs::create_dir_all(root.join("docs")).expect("mkdir docs");
+        fs::write(root.join("tool"), b"bin").expect("write tool");
+        fs::write(root.join("bin").join("tool"), b"bin").expect("write tool bin");
+        fs::write(root.join("bin").join("helper"), b"bin").expect("write helper");
+        fs::write(root.join("docs").join("readme.md"), b"doc").expect("write doc");
+
+        let (primary, extras) =
+            find_binaries(root, "tool", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("tool"));
+        assert!(extras.contains(&root.join("bin").join("tool")));
+        assert!(extras.contains(&root.join("bin").join("helper")));
+    }
+
+    #[test]
+    fn find_binaries_prefers_bin_when_no_exact() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::create_dir_all(root.join("bin")).expect("mkdir");
+        fs::create_dir_all(root.join("alt")).expect("mkdir");
+        fs::write(root.join("bin").join("run"), b"bin").expect("write run");
+        fs::write(root.join("alt").join("tool"), b"bin").expect("write tool");
+
+        let (primary, _extras) =
+            find_binaries(root, "yoink", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("bin").join("run"));
+    }
+
+    #[test]
+    fn find_binaries_ranks_sbin_and_libexec_below_bin() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::create_dir_all(root.join("libexec")).expect("mkdir");
+        fs::write(root.join("libexec").join("helper"), b"bin").expect("write helper");
+        fs::write(root.join("AUTHORS"), b"doc").expect("write authors");
+
+        let (primary, _extras) =
+            find_binaries(root, "yoink", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("libexec").join("helper"));
+
+        fs::create_dir_all(root.join("sbin")).expect("mkdir");
+        fs::write(root.join("sbin").join("daemon"), b"bin").expect("write daemon");
+        let (primary, _extras) =
+            find_binaries(root, "yoink", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("sbin").join("daemon"));
+
+        fs::create_dir_all(root.join("bin")).expect("mkdir");
+        fs::write(root.join("bin").join("run"), b"bin").expect("write run");
+        let (primary, _extras) =
+            find_binaries(root, "yoink", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("bin").join("run"));
+    }
+
+    #[test]
+    fn find_binaries_prefers_shortest_probable() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::create_dir_all(root.join("a")).expect("mkdir");
+        fs::create_dir_all(root.join("longer").join("path")).expect("mkdir");
+        fs::write(root.join("a").join("run"), b"bin").expect("write run");
+        fs::write(root.join("longer").join("path").join("tool"), b"bin").expect("write tool");
+
+        let (primary, _extras) =
+            find_binaries(root, "yoink", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("a").join("run"));
+    }
+
+    #[test]
+    fn find_binaries_strict_mode_rejects_multiple_probable() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::write(root.join("run"), b"bin").expect("write run");
+        fs::write(root.join("tool"), b"bin").expect("write tool");
+
+        let (primary, _extras) =
+            find_binaries(root, "yoink", &Options::default()).expect("find binaries");
+        assert_eq!(primary.parent(), Some(root));
+
+        let strict = Options {
+            fail_on_multiple_candidates: true,
+            ..Options::default()
+        };
+        let err = find_binaries(root, "yoink", &strict).expect_err("strict should fail");
+        let message = err.to_string();
+        assert!(message.contains("run"));
+        assert!(message.contains("tool"));
+    }
+
+    #[test]
+    fn find_binaries_bin_picks_one_and_drops_extras() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::create_dir_all(root.join("dist")).expect("mkdir");
+        fs::write(root.join("dist").join("uv"), b"bin").expect("write uv");
+        fs::write(root.join("dist").join("uvx.exe"), b"bin").expect("write uvx");
+
+        let (primary, extras) =
+            find_binaries(root, "uv", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("dist").join("uv"));
+        assert_eq!(extras, vec![root.join("dist").join("uvx.exe")]);
+
+        let uvx = Options {
+            bin: Some("uvx".to_string()),
+            ..Options::default()
+        };
+        let (primary, extras) = find_binaries(root, "uv", &uvx).expect("find binaries");
+        assert_eq!(primary, root.join("dist").join("uvx.exe"));
+        assert!(extras.is_empty());
+
+        let missing = Options {
+            bin: Some("ruff".to_string()),
+            ..Options::default()
+        };
+        let err = find_binaries(root, "uv", &missing).expect_err("no ruff");
+        assert!(err.to_string().contains("no binary named ruff"), "{err}");
+    }
+
+    #[test]
+    fn prompt_for_binary_reads_choice() {
+        let names = vec!["bin/one".to_string(), "bin/two".to_string()];
+        let mut output = Vec::new();
+        let choice =
+            prompt_for_binary(&mut io::Cursor::new("2\n"), &mut output, &names).expect("choice");
+        assert_eq!(choice, 1);
+        let shown = String::from_utf8(output).expect("utf8 prompt");
+        assert!(shown.contains("2) bin/two"), "{shown}");
+
+        let mut sink = Vec::new();
+        assert_eq!(
+            prompt_for_binary(&mut io::Cursor::new("\n"), &mut sink, &names).expect("default"),
+            0
+        );
+        assert!(prompt_for_binary(&mut io::Cursor::new("3\n"), &mut sink, &names).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn find_binaries_skips_symlinks_out_of_archive() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let outside = temp.path().join("outside");
+        fs::write(&outside, b"secret").expect("write outside");
+        let archive = temp.path().join("tool.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            fs::File::create(&archive).expect("create archive"),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "bin/real", &b"bin"[..])
+            .expect("append real");
+        for (name, target) in [
+            ("tool", outside.as_path()),
+            ("alias", Path::new("bin/real")),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder
+                .append_link(&mut header, name, target)
+                .expect("append link");
+        }
+        builder
+            .into_inner()
+            .expect("finish tar")
+            .finish()
+            .expect("finish gzip");
+
+        let root = temp.path().join("extracted");
+        fs::create_dir(&root).expect("mkdir");
+        unpack_archive(&archive, &root).expect("unpack");
+        assert!(root.join("tool").is_symlink());
+
+        let (primary, extras) =
+            find_binaries(&root, "tool", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("bin").join("real"));
+        assert!(extras.is_empty());
+
+        let follow = Options {
+            follow_symlinks: true,
+            ..Options::default()
+        };
+        let (primary, extras) = find_binaries(&root, "tool", &follow).expect("find binaries");
+        assert_ne!(primary, root.join("tool"));
+        assert!(!extras.contains(&root.join("tool")));
+        assert!([primary]
+            .iter()
+            .chain(&extras)
+            .any(|path| path == &root.join("alias")));
+
+        let pinned = Options {
+            binary_path: Some("tool".to_string()),
+            ..follow
+        };
+        assert!(find_binaries(&root, "tool", &pinned).is_err());
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn find_binaries_matches_exe_regardless_of_case() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::create_dir_all(root.join("bin")).expect("create bin");
+        fs::write(root.join("bin").join("Tool.EXE"), b"MZbin").expect("write exe");
+        fs::write(root.join("tool-helper.exe"), b"MZbin").expect("write helper");
+
+        let (primary, _extras) =
+            find_binaries(root, "tool", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("bin").join("Tool.EXE"));
+        let (primary, _extras) =
+            find_binaries(root, "TOOL.exe", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("bin").join("Tool.EXE"));
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn with_exe_suffix_names_extensionless_pe_files() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let pe = temp.path().join("tool");
+        fs::write(&pe, b"MZbin").expect("write pe");
+        let script = temp.path().join("script");
+        fs::write(&script, b"echo hi").expect("write script");
+
+        assert_eq!(with_exe_suffix(&pe, OsString::from("tool")), "tool.exe");
+        assert_eq!(with_exe_suffix(&pe, OsString::from("tool.exe")), "tool.exe");
+        assert_eq!(with_exe_suffix(&script, OsString::from("script")), "script");
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn with_exe_suffix_is_windows_only() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let pe = temp.path().join("tool");
+        fs::write(&pe, b"MZbin").expect("write pe");
+        assert_eq!(with_exe_suffix(&pe, OsString::from("tool")), "tool");
+    }
+
+    #[test]
+    fn find_binaries_single_candidate() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::write(root.join("only"), b"bin").expect("write file");
+        let (primary, extras) =
+            find_binaries(root, "yoink", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("only"));
+        assert!(extras.is_empty());
+    }
+
+    #[test]
+    fn find_binaries_falls_back_to_single_candidate() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::write(root.join("notes.txt"), b"doc").expect("write file");
+
+        let (primary, _extras) =
+            find_binaries(root, "yoink", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("notes.txt"));
+    }
+
+    #[test]
+    fn find_binaries_includes_hidden_only_when_asked() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::write(root.join(".tool"), b"bin").expect("write dotfile");
+        fs::create_dir(root.join(".cache")).expect("create dot-dir");
+        fs::write(root.join(".cache").join("helper"), b"bin").expect("write helper");
+
+        let strict = Options {
+            fail_on_multiple_candidates: true,
+            ..Options::default()
+        };
+        let (primary, _extras) = find_binaries(root, "yoink", &strict).expect("find binaries");
+        assert_eq!(primary, root.join(".cache").join("helper"));
+
+        let hidden = Options {
+            include_hidden: true,
+            ..strict
+        };
+        let err = find_binaries(root, "yoink", &hidden).expect_err("two candidates");
+        assert!(
+            err.to_string()
+                .contains("multiple candidate binaries found: .cache/helper, .tool"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn find_binaries_errors_without_candidates() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        assert!(find_binaries(root, "yoink", &Options::default()).is_err());
+    }
+
+    #[test]
+    fn truncated_tar_gz_reports_corrupt_download() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.tar.gz");
+        let bytes = make_tar_gz_bytes(&[("tool", &[7u8; 4096])]);
+        fs::write(&archive, &bytes[..bytes.len() / 2]).expect("write archive");
+
+        let err = match extract_archive(&archive, "tool", &Options::default()) {
+            Ok(_) => panic!("truncated archive should fail"),
+            Err(err) => err,
+        };
+        let message = err.to_string();
+        assert!(
+            message.contains("corrupt or an incomplete download"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn keep_temp_leaves_failed_extraction_behind() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.zip");
+        write_zip(&archive, &[("README.md", b"docs"), ("LICENSE", b"mit")]);
+        let options = Options {
+            keep_temp: true,
+            ..Options::default()
+        };
+
+        let err = match extract_archive(&archive, "tool", &options) {
+            Ok(_) => panic!("extraction should fail without a binary"),
+            Err(err) => err,
+        };
+        let message = err.to_string();
+        let kept = PathBuf::from(
+            message
+                .strip_prefix("kept temp dir ")
+                .expect("error names the kept dir"),
+        );
+        assert!(kept.join("README.md").exists());
+        fs::remove_dir_all(&kept).expect("clean up kept dir");
+    }
+
+    #[test]
+    fn probable_binary_filters_docs_and_extensions() {
+        assert!(!is_probable_binary_candidate(Path::new("README.md")));
+        assert!(!is_probable_binary_candidate(Path::new("docs/tool")));
+        assert!(!is_probable_binary_candidate(Path::new("share/tool")));
+        assert!(is_probable_binary_candidate(Path::new("bin/tool")));
+    }
+
+    #[test]
+    fn probable_binary_handles_missing_filename() {
+        assert!(!is_probable_binary_candidate(Path::new("")));
+    }
+
+    #[test]
+    fn probable_binary_filters_extensions() {
+        assert!(!is_probable_binary_candidate(Path::new("notes.json")));
+    }
+
+    #[test]
+    fn path_has_component_handles_case() {
+        let path = Path::new("Foo").join("Bin").join("tool");
+        let path = path.as_path();
+        assert!(path_has_component(path, "bin"));
+        assert!(!path_has_component(path, "share"));
+    }
+
+    #[test]
+    fn verify_exec_format_rejects_wrong_machine() {
+        let elf = |machine: u16| {
+            let mut header = vec![0u8; 64];
+            header[..4].copy_from_slice(b"\x7fELF");
+            header[4] = 2;
+            header[5] = 1;
+            header[18..20].copy_from_slice(&machine.to_le_bytes());
+            header
+        };
+        let temp = tempfile::tempdir().expect("temp dir");
+        let binary = temp.path().join("tool");
+        let x86_64 = Options {
+            arch: Some("amd64".to_string()),
+            ..Options::default()
+        };
+
+        fs::write(&binary, elf(0xb7)).expect("write elf");
+        let err = verify_exec_format(&binary, &x86_64).expect_err("aarch64 binary");
+        assert!(
+            err.to_string().contains("built for aarch64, not x86_64"),
+            "{err}"
+        );
+
+        fs::write(&binary, elf(0x3e)).expect("write elf");
+        assert_eq!(
+            verify_exec_format(&binary, &x86_64).expect("matching machine"),
+            None
+        );
+
+        fs::write(&binary, b"#!/bin/sh\necho hi\n").expect("write script");
+        assert_eq!(
+            verify_exec_format(&binary, &x86_64).expect("scripts pass"),
+            None
+        );
+
+        let mut pe = vec![0u8; 0x90];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        pe[0x84..0x86].copy_from_slice(&0xaa64u16.to_le_bytes());
+        assert_eq!(exec_arches(&pe), Some(vec!["aarch64"]));
+
+        let mut macho = 0xfeed_facfu32.to_le_bytes().to_vec();
+        macho.extend(0x0100_0007u32.to_le_bytes());
+        assert_eq!(exec_arches(&macho), Some(vec!["x86_64"]));
+    }
+
+    #[test]
+    fn detect_binary_arch_reads_fixture_headers() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let write = |name: &str, bytes: &[u8]| {
+            let path = temp.path().join(name);
+            fs::write(&path, bytes).expect("write fixture");
+            path
+        };
+
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[5] = 2;
+        elf[18..20].copy_from_slice(&0xb7u16.to_be_bytes());
+        assert_eq!(detect_binary_arch(&write("elf", &elf)), Some("aarch64"));
+
+        let mut macho = 0xfeed_facfu32.to_le_bytes().to_vec();
+        macho.extend(0x0100_000cu32.to_le_bytes());
+        assert_eq!(detect_binary_arch(&write("macho", &macho)), Some("aarch64"));
+
+        let mut fat = 0xcafe_babeu32.to_be_bytes().to_vec();
+        fat.extend(2u32.to_be_bytes());
+        for cpu in [0x0100_0007u32, 0x0100_000c] {
+            fat.extend(cpu.to_be_bytes());
+            fat.extend([0u8; 16]);
+        }
+        assert_eq!(detect_binary_arch(&write("fat", &fat)), None);
+        assert_eq!(exec_arches(&fat), Some(vec!["x86_64", "aarch64"]));
+
+        let mut pe = vec![0u8; 0x90];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        pe[0x84..0x86].copy_from_slice(&0x8664u16.to_le_bytes());
+        assert_eq!(detect_binary_arch(&write("pe", &pe)), Some("x86_64"));
+
+        assert_eq!(detect_binary_arch(&write("script", b"#!/bin/sh\n")), None);
+        assert_eq!(detect_binary_arch(&temp.path().join("missing")), None);
+    }
+
+    #[test]
+    fn verify_exec_format_warns_only_when_told_to_keep_mismatches() {
+        let other = if normalize_arch(env::consts::ARCH) == "x86_64" {
+            0xb7u16
+        } else {
+            0x3e
+        };
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[5] = 1;
+        elf[18..20].copy_from_slice(&other.to_le_bytes());
+        let temp = tempfile::tempdir().expect("temp dir");
+        let binary = temp.path().join("tool");
+        fs::write(&binary, elf).expect("write elf");
+
+        let err = verify_exec_format(&binary, &Options::default()).expect_err("host mismatch");
+        assert!(err.to_string().contains("--no-verify-exec-format"), "{err}");
+
+        let keep = Options {
+            no_verify_exec_format: true,
+            ..Options::default()
+        };
+        let warning = verify_exec_format(&binary, &keep)
+            .expect("kept with a warning")
+            .expect("warning");
+        assert!(warning.contains("may fail to run here"), "{warning}");
+
+        let strict = Options {
+            strict: true,
+            ..keep
+        };
+        let err = verify_exec_format(&binary, &strict).expect_err("strict mismatch");
+        assert!(err.to_string().contains("is built for"), "{err}");
+    }
+
+    #[test]
+    #[serial]
+    fn download_returns_exec_format_warnings() {
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[5] = 1;
+        elf[18..20].copy_from_slice(&0xb7u16.to_le_bytes());
+        let server = TestServer::new(|base| {
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
+            );
+            BTreeMap::from([
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    body.into_bytes(),
+                ),
+                ("/download/tool".to_string(), elf),
+            ])
+        });
+
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            arch: Some("x86_64".to_string()),
+            no_verify_exec_format: true,
+            ..Options::default()
+        };
+        let summary = download_to_dir_with("mxcl/tool", temp.path(), &options).expect("download");
+        assert_eq!(summary.warnings.len(), 1, "{:?}", summary.warnings);
+        assert!(summary.warnings[0].contains("built for aarch64, not x86_64"));
+
+        server.finish();
+    }
+
+    #[test]
+    fn install_binary_replaces_by_rename() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let src = temp.path().join("src");
+        let dest = temp.path().join("dest");
+        fs::write(&src, b"new").expect("write");
+        fs::write(&dest, b"old").expect("write dest");
+        let old = fs::File::open(&dest).expect("open old");
+
+        install_binary(&src, &dest, true).expect("install binary");
+        assert_eq!(fs::read(&dest).expect("read dest"), b"new");
+        // An open handle keeps reading the complete old file rather than a truncated one.
+        #[cfg(unix)]
+        {
+            let mut contents = Vec::new();
+            io::Read::read_to_end(&mut &old, &mut contents).expect("read old");
+            assert_eq!(contents, b"old");
+        }
+        drop(old);
+        let leftovers = fs::read_dir(temp.path())
+            .expect("read dir")
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| name.starts_with(".yoink-"))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn install_binary_copies_and_sets_mode() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let src = temp.path().join("src");
+        let dest = temp.path().join("dest");
+        fs::write(&src, b"hello").expect("write");
+
+        install_binary(&src, &dest, true).expect("install binary");
+        let contents = fs::read(&dest).expect("read dest");
+        assert_eq!(contents, b"hello");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&dest).expect("stat").permissions().mode();
+            assert!(mode & 0o111 != 0);
+        }
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn is_in_use_error_detects_sharing_violation() {
+        assert!(is_in_use_error(&io::Error::from_raw_os_error(32)));
+        assert!(!is_in_use_error(&io::Error::from_raw_os_error(2)));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn is_in_use_error_is_windows_only() {
+        assert!(!is_in_use_error(&io::Error::from_raw_os_error(32)));
+    }
+
+    #[test]
+    fn install_payload_handles_normal_copy() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let src = temp.path().join("src");
+        let dest = temp.path().join("dest");
+        fs::write(&src, b"hello").expect("write");
+        let used_sudo = install_payload(&src, &dest, true).expect("install payload");
+        assert!(dest.exists());
+        assert!(!used_sudo);
+    }
+
+    #[test]
+    fn install_payload_errors_on_missing_dest_parent() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let src = temp.path().join("src");
+        let dest = temp.path().join("missing").join("dest");
+        fs::write(&src, b"hello").expect("write");
+        assert!(install_payload(&src, &dest, true).is_err());
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn install_payload_retries_with_sudo_on_permission_denied() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_sudo_dir, _path_guard) = setup_fake_sudo();
+        let temp = tempfile::tempdir().expect("temp dir");
+        let src = temp.path().join("src");
+        let locked = temp.path().join("locked");
+        let dest = locked.join("dest");
+        fs::write(&src, b"hello").expect("write");
+        fs::create_dir(&locked).expect("create locked dir");
+        fs::write(&dest, b"old").expect("write dest");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).expect("chmod");
+
+        // Root can write into the read-only dir directly, so sudo only kicks in otherwise.
+        let probe = locked.join("probe");
+        let privileged = fs::write(&probe, b"").is_ok();
+        let _ = fs::remove_file(&probe);
+        let used_sudo = install_payload(&src, &dest, true).expect("install payload");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).expect("chmod back");
+        assert_eq!(fs::read(&dest).expect("read"), b"hello");
+        assert_eq!(used_sudo, !privileged);
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn verify_signature_runs_cosign_when_requested() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bin = tempfile::tempdir().expect("bin dir");
+        let cosign = bin.path().join("cosign");
+        fs::write(
+            &cosign,
+            "#!/bin/sh\necho \"$@\" > \"$COSIGN_ARGS\"\nexit \"$COSIGN_EXIT\"\n",
+        )
+        .expect("write cosign");
+        fs::set_permissions(&cosign, fs::Permissions::from_mode(0o755)).expect("chmod");
+        let path = format!(
+            "{}:{}",
+            bin.path().display(),
+            env::var("PATH").unwrap_or_default()
+        );
+        let _path_guard = EnvGuard::set("PATH", path);
+
+        let temp = tempfile::tempdir().expect("temp dir");
+        let asset = temp.path().join("tool");
+        fs::write(&asset, b"bin").expect("write asset");
+        let args_path = temp.path().join("args");
+        let _args_guard = EnvGuard::set("COSIGN_ARGS", &args_path);
+        let info = |base: &str| ReleaseInfo {
+            owner: "cli".to_string(),
+            name: "cli".to_string(),
+            tag: "v1.0.0".to_string(),
+            asset_name: "tool".to_string(),
+            asset_url: format!("{base}/tool"),
+            checksum_url: None,
+            signature_urls: vec![format!("{base}/tool.bundle")],
+        };
+        let client = Client::new();
+
+        // Without YOINK_VERIFY=cosign nothing is fetched or run.
+        verify_signature(
+            &client,
+            &asset,
+            &info("http://127.0.0.1:9"),
+            &GitHub,
+            &Options::default(),
+        )
+        .expect("skipped");
+        assert!(!args_path.exists());
+
+        let _verify_guard = EnvGuard::set("YOINK_VERIFY", "cosign");
+        for (exit, ok) in [("0", true), ("1", false)] {
+            let _exit_guard = EnvGuard::set("COSIGN_EXIT", exit);
+            let server = TestServer::new(|_base| {
+                let mut responses = BTreeMap::new();
+                responses.insert("/tool.bundle".to_string(), b"{}".to_vec());
+                responses
+            });
+            let result = verify_signature(
+                &client,
+                &asset,
+                &info(&server.base),
+                &GitHub,
+                &Options::default(),
+            );
+            server.finish();
+            assert_eq!(result.is_ok(), ok, "{result:?}");
+            let args = fs::read_to_string(&args_path).expect("cosign args");
+            assert!(args.starts_with("verify-blob --bundle"), "{args}");
+            assert!(
+                args.contains(
+                    "--certificate-identity-regexp ^https://github\\.com/cli/cli/ \
+                     --certificate-oidc-issuer https://token.actions.githubusercontent.com"
+                ),
+                "{args}"
+            );
+            assert!(
+                args.trim_end().ends_with(&asset.display().to_string()),
+                "{args}"
+            );
+        }
+
+        // Self-hosted GitLab releases are signed by that instance's CI.
+        let _exit_guard = EnvGuard::set("COSIGN_EXIT", "0");
+        let server = TestServer::new(|_base| {
+            let mut responses = BTreeMap::new();
+            responses.insert("/tool.bundle".to_string(), b"{}".to_vec());
+            responses
+        });
+        let gitlab = GitLab {
+            host: "git.example.com".to_string(),
+        };
+        let info = ReleaseInfo {
+            name: "tool.rs".to_string(),
+            ..info(&server.base)
+        };
+        verify_signature(&client, &asset, &info, &gitlab, &Options::default()).expect("verify");
+        server.finish();
+        let args = fs::read_to_string(&args_path).expect("cosign args");
+        assert!(
+            args.contains(
+                "--certificate-identity-regexp ^https://git\\.example\\.com/cli/tool\\.rs// \
+                 --certificate-oidc-issuer https://git.example.com"
+            ),
+            "{args}"
+        );
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn install_with_sudo_uses_fake_sudo() {
+        let (_sudo_dir, _path_guard) = setup_fake_sudo();
+        let temp = tempfile::tempdir().expect("temp dir");
+        let src = temp.path().join("src");
+        let dest = temp.path().join("dest");
+        fs::write(&src, b"hello").expect("write");
+
+        install_with_sudo(&src, &dest, true).expect("install with sudo");
+        assert_eq!(fs::read(&dest).expect("read"), b"hello");
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn ensure_install_dir_uses_sudo_on_permission_denied() {
+        let (_sudo_dir, _path_guard) = setup_fake_sudo();
+        let temp = tempfile::tempdir().expect("temp dir");
+        let protected = temp.path().join("protected");
+        fs::create_dir_all(&protected).expect("mkdir");
+
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&protected).expect("stat").permissions();
+            perms.set_mode(0o500);
+            fs::set_permissions(&protected, perms).expect("chmod");
+        }
+
+        let install_dir = protected.join("bin");
+        ensure_install_dir(&install_dir).expect("ensure install dir");
+        assert!(install_dir.exists());
+
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&protected).expect("stat").permissions();
+            perms.set_mode(0o700);
+            fs::set_permissions(&protected, perms).expect("chmod");
+        }
+    }
+
+    #[test]
+    fn is_permission_denied_detects_nested_error() {
+        let err = anyhow::Error::new(io::Error::new(io::ErrorKind::PermissionDenied, "nope"));
+        assert!(is_permission_denied(&err));
+
+        let err = anyhow::Error::new(io::Error::other("nope"));
+        assert!(!is_permission_denied(&err));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn exit_status_code_reads_code() {
+        use std::os::unix::process::ExitStatusExt;
+        let status = ExitStatus::from_raw(9);
+        assert_eq!(exit_status_code(status), 137);
+
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg("exit 42")
+            .status()
+            .expect("run");
+        assert_eq!(exit_status_code(status), 42);
+    }
+
+    #[test]
+    #[serial]
+    fn default_install_dir_prefers_env_vars() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _guard = EnvGuard::set("YOINKDIR", temp.path());
+        let dir = default_install_dir(&Options::default()).expect("default install dir");
+        assert_eq!(dir, temp.path());
+    }
+
+    #[test]
+    #[serial]
+    fn default_install_dir_honors_xdg_bin_home() {
+        let yoink_dir = tempfile::tempdir().expect("temp dir");
+        let xdg = tempfile::tempdir().expect("xdg dir");
+        let _xdg_guard = EnvGuard::set("XDG_BIN_HOME", xdg.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", yoink_dir.path());
+        assert_eq!(
+            default_install_dir(&Options::default()).expect("default install dir"),
+            yoink_dir.path()
+        );
+
+        env::remove_var("YOINKDIR");
+        env::remove_var("YOINK_BIN_DIR");
+        if !cfg!(windows) {
+            assert_eq!(
+                default_install_dir(&Options::default()).expect("default install dir"),
+                xdg.path()
+            );
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn path_setup_line_names_install_dir_for_each_shell() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _guard = EnvGuard::set("YOINKDIR", temp.path());
+        let dir = temp.path().display().to_string();
+        for shell in ["bash", "zsh", "fish"] {
+            let line = path_setup_line(Some(shell)).expect("path setup");
+            assert!(line.contains(&dir), "{shell}: {line}");
+        }
+        assert_eq!(
+            path_setup_line(Some("bash")).expect("bash"),
+            format!("export PATH=\"{dir}:$PATH\"")
+        );
+        assert_eq!(
+            path_setup_line(Some("fish")).expect("fish"),
+            format!("fish_add_path \"{dir}\"")
+        );
+        assert!(path_setup_line(Some("tcsh")).is_err());
+
+        let _home = EnvGuard::set("HOME", temp.path().parent().expect("parent"));
+        let line = path_setup_line(Some("zsh")).expect("zsh");
+        let name = temp.path().file_name().expect("name").to_string_lossy();
+        assert_eq!(line, format!("export PATH=\"$HOME/{name}:$PATH\""));
+    }
+
+    #[test]
+    #[serial]
+    fn default_install_dir_uses_bin_env_when_set() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _guard = EnvGuard::set("YOINK_BIN_DIR", temp.path());
+        env::remove_var("YOINKDIR");
+        let dir = default_install_dir(&Options::default()).expect("default install dir");
+        assert_eq!(dir, temp.path());
+    }
+
+    #[test]
+    #[serial]
+    fn isolated_mode_ignores_yoink_env_vars() {
+        let home = tempfile::tempdir().expect("temp dir");
+        let custom = tempfile::tempdir().expect("custom dir");
+        let _home = EnvGuard::set("HOME", home.path());
+        let _dir = EnvGuard::set("YOINKDIR", custom.path());
+        let _bin = EnvGuard::set("YOINK_BIN_DIR", custom.path());
+        let _api = EnvGuard::set("YOINK_GITHUB_API_BASE", "http://127.0.0.1:1");
+        let options = Options {
+            isolated: true,
+            ..Options::default()
+        };
+
+        let dir = default_install_dir(&options).expect("default install dir");
+        if !cfg!(windows) {
+            assert_eq!(dir, home.path().join(".local").join("bin"));
+        }
+        assert_ne!(dir, custom.path());
+        assert_eq!(github_api_base(&options), "https://api.github.com");
+        assert_eq!(
+            default_install_dir(&Options::default()).unwrap(),
+            custom.path()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn install_path_joins_install_dir_and_repo_name() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _guard = EnvGuard::set("YOINKDIR", temp.path());
+        let path = install_path("mxcl/tool").expect("install path");
+        assert_eq!(path, temp.path().join(binary_name("tool")));
+
+        let options = Options {
+            bin: Some("helper".to_string()),
+            ..Options::default()
+        };
+        let path = install_path_with("mxcl/tool", &options).expect("install path");
+        assert_eq!(path, temp.path().join(binary_name("helper")));
+    }
+
+    #[test]
+    #[serial]
+    fn ensure_install_dir_creates_path() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let install_dir = temp.path().join("bin");
+        ensure_install_dir(&install_dir).expect("ensure install dir");
+        assert!(install_dir.exists());
+    }
+
+    #[test]
+    #[serial]
+    fn state_loads_empty_when_missing() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+
+        let state = load_state().expect("load state");
+        assert!(state.installs.is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn record_and_remove_install_updates_state() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+
+        let bin = temp.path().join("bin").join("yoink");
+        let extra = temp.path().join("bin").join("helper");
+        fs::create_dir_all(bin.parent().expect("bin parent")).expect("mkdir");
+        fs::write(&bin, b"bin").expect("write bin");
+        fs::write(&extra, b"bin").expect("write extra");
+
+        record_install(
+            "mxcl/yoink",
+            "v1.2.3",
+            &[bin.clone(), extra.clone()],
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+
+        let installs = list_installs().expect("list installs");
+        assert_eq!(installs.len(), 1);
+        assert_eq!(installs[0].version, "1.2.3");
+        assert_eq!(installs[0].bin, bin);
+        assert_eq!(installs[0].bins, vec![extra.clone()]);
+
+        remove_install("mxcl/yoink", true).expect("remove install");
+        assert!(!bin.exists());
+        assert!(!extra.exists());
+    }
+
+    #[test]
+    #[serial]
+    fn sort_installs_orders_by_installed_at() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let state = serde_json::json!({
+            "installs": {
+                "mxcl/alpha": {"version": "v2.0.0", "bin": "/bin/alpha", "installed_at": 300},
+                "mxcl/beta": {"version": "v10.0.0", "bin": "/bin/beta", "installed_at": 100},
+                "mxcl/gamma": {"version": "v1.0.0", "bin": "/bin/gamma", "installed_at": 200}
+            }
+        });
+        let path = state_path().expect("state path");
+        fs::create_dir_all(path.parent().expect("state dir")).expect("mkdir");
+        fs::write(&path, state.to_string()).expect("write state");
+
+        let repos = |sort: ListSort| {
+            let mut installs = list_installs().expect("list installs");
+            sort_installs(&mut installs, sort);
+            installs
+                .into_iter()
+                .map(|install| install.repo)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            repos(ListSort::InstalledAt),
+            ["mxcl/beta", "mxcl/gamma", "mxcl/alpha"]
+        );
+        assert_eq!(
+            repos(ListSort::Version),
+            ["mxcl/gamma", "mxcl/alpha", "mxcl/beta"]
+        );
+        assert_eq!(
+            repos(ListSort::Name),
+            ["mxcl/alpha", "mxcl/beta", "mxcl/gamma"]
+        );
+        assert!("size".parse::<ListSort>().is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn migrate_dir_moves_binaries_and_updates_state() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+
+        let old_dir = temp.path().join("old");
+        let new_dir = temp.path().join("new");
+        fs::create_dir_all(&old_dir).expect("mkdir");
+        let tool = old_dir.join("tool");
+        let helper = old_dir.join("helper");
+        let other = old_dir.join("other");
+        fs::write(&tool, b"tool").expect("write tool");
+        fs::write(&helper, b"helper").expect("write helper");
+        fs::write(&other, b"other").expect("write other");
+        record_install(
+            "mxcl/tool",
+            "v1.0.0",
+            &[tool.clone(), helper.clone()],
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record tool");
+        record_install(
+            "mxcl/other",
+            "v2.0.0",
+            std::slice::from_ref(&other),
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record other");
+
+        let moved = migrate_dir(&new_dir).expect("migrate");
+        assert_eq!(moved.len(), 3);
+        assert!(!tool.exists() && !helper.exists() && !other.exists());
+        assert_eq!(fs::read(new_dir.join("tool")).expect("read tool"), b"tool");
+        assert_eq!(
+            fs::read(new_dir.join("helper")).expect("read helper"),
+            b"helper"
+        );
+        assert_eq!(
+            fs::read(new_dir.join("other")).expect("read other"),
+            b"other"
+        );
+
+        let state = load_state().expect("load state");
+        let entry = &state.installs["mxcl/tool"];
+        assert_eq!(entry.bin, new_dir.join("tool"));
+        assert_eq!(entry.bins, [new_dir.join("helper")]);
+        assert_eq!(entry.install_dir.as_deref(), Some(new_dir.as_path()));
+        assert_eq!(state.installs["mxcl/other"].bin, new_dir.join("other"));
+        assert_eq!(
+            state.installs["mxcl/other"].install_dir(),
+            Some(new_dir.as_path())
+        );
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn migrate_dir_repoints_relative_symlinks() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+
+        let old_dir = temp.path().join("old");
+        let new_dir = temp.path().join("new");
+        fs::create_dir_all(old_dir.join("libexec")).expect("mkdir");
+        fs::write(old_dir.join("libexec").join("tool"), b"tool").expect("write target");
+        let link = old_dir.join("tool");
+        std::os::unix::fs::symlink("libexec/tool", &link).expect("symlink");
+        record_install(
+            "mxcl/tool",
+            "v1.0.0",
+            std::slice::from_ref(&link),
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record");
+
+        migrate_dir(&new_dir).expect("migrate");
+        assert!(!link.exists());
+        assert_eq!(
+            fs::read(new_dir.join("tool")).expect("read via link"),
+            b"tool"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn remove_install_errors_when_missing() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        assert!(remove_install("mxcl/yoink", true).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn commit_install_skips_state_for_missing_files() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let tool = temp.path().join("tool");
+        fs::write(&tool, b"bin").expect("write tool");
+        let helper = temp.path().join("helper");
+        let bins = [tool.clone(), helper.clone()];
+
+        let err = commit_install("mxcl/tool", "v1.0.0", &bins, false, &InstallMeta::default())
+            .expect_err("helper was never written");
+        assert!(err.to_string().contains("missing after install"), "{err}");
+        assert!(!load_state()
+            .expect("state")
+            .installs
+            .contains_key("mxcl/tool"));
+
+        fs::create_dir(&helper).expect("mkdir helper");
+        assert!(
+            commit_install("mxcl/tool", "v1.0.0", &bins, false, &InstallMeta::default()).is_err()
+        );
+
+        fs::remove_dir(&helper).expect("rmdir helper");
+        fs::write(&helper, b"bin").expect("write helper");
+        commit_install("mxcl/tool", "v1.0.0", &bins, false, &InstallMeta::default())
+            .expect("commit install");
+        assert!(load_state()
+            .expect("state")
+            .installs
+            .contains_key("mxcl/tool"));
+    }
+
+    #[test]
+    #[serial]
+    fn doctor_reports_and_prunes_stale_installs() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let tool = temp.path().join("tool");
+        let helper = temp.path().join("helper");
+        fs::write(&tool, b"bin").expect("write tool");
+        set_executable(&tool).expect("chmod tool");
+        record_install(
+            "mxcl/tool",
+            "v1.0.0",
+            &[tool.clone(), helper.clone()],
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record tool");
+        record_install(
+            "mxcl/gone",
+            "v1.0.0",
+            &[temp.path().join("gone")],
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record gone");
+
+        let stale = doctor(false).expect("doctor");
+        let summary: Vec<(&str, usize, bool)> = stale
+            .iter()
+            .map(|install| (install.repo.as_str(), install.missing.len(), install.pruned))
+            .collect();
+        assert_eq!(summary, [("mxcl/gone", 1, false), ("mxcl/tool", 1, false)]);
+        assert_eq!(stale[1].missing, [helper]);
+        assert_eq!(load_state().expect("state").installs.len(), 2);
+
+        let stale = doctor(true).expect("doctor --fix");
+        assert!(stale[0].pruned && !stale[1].pruned);
+        let installs = load_state().expect("state").installs;
+        assert_eq!(installs.keys().collect::<Vec<_>>(), ["mxcl/tool"]);
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn doctor_treats_dangling_symlinks_as_missing() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let link = temp.path().join("tool");
+        std::os::unix::fs::symlink(temp.path().join("store/tool"), &link).expect("symlink");
+        record_install(
+            "mxcl/tool",
+            "v1.0.0",
+            std::slice::from_ref(&link),
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record tool");
+
+        let stale = doctor(true).expect("doctor --fix");
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].missing, std::slice::from_ref(&link));
+        assert!(stale[0].pruned);
+        assert!(load_state().expect("state").installs.is_empty());
+        assert!(fs::symlink_metadata(&link).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn remove_install_handles_directories() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+
+        let bin_dir = temp.path().join("bin_dir");
+        fs::create_dir_all(&bin_dir).expect("mkdir");
+        record_install(
+            "mxcl/yoink",
+            "v1.0.0",
+            std::slice::from_ref(&bin_dir),
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+
+        assert!(remove_install("mxcl/yoink", true).is_ok());
+        assert!(!bin_dir.exists());
+    }
+
+    #[test]
+    #[serial]
+    fn uninstall_resolves_binary_names() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let gh = temp.path().join("gh");
+        let tool = temp.path().join("tool");
+        let other = temp.path().join("other").join("tool");
+        fs::create_dir_all(other.parent().expect("parent")).expect("mkdir");
+        for path in [&gh, &tool, &other] {
+            fs::write(path, b"bin").expect("write bin");
+        }
+        record_install(
+            "cli/cli",
+            "v2.0.0",
+            std::slice::from_ref(&gh),
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record");
+        record_install(
+            "mxcl/tool",
+            "v1.0.0",
+            std::slice::from_ref(&tool),
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record");
+        record_install(
+            "acme/tool",
+            "v1.0.0",
+            std::slice::from_ref(&other),
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record");
+
+        uninstall_with("gh", false).expect("uninstall by bin");
+        assert!(!gh.exists());
+        assert!(!load_state()
+            .expect("state")
+            .installs
+            .contains_key("cli/cli"));
+
+        let err = uninstall_with("tool", false).expect_err("ambiguous bin");
+        assert!(err.to_string().contains("acme/tool, mxcl/tool"), "{err}");
+        assert!(tool.exists() && other.exists());
+        assert!(uninstall_with("missing", false).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn uninstall_resolves_binary_names_under_a_default_owner() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let _owner = EnvGuard::set("YOINK_DEFAULT_OWNER", "myorg");
+        let gh = temp.path().join("gh");
+        let tool = temp.path().join("tool");
+        for path in [&gh, &tool] {
+            fs::write(path, b"bin").expect("write bin");
+        }
+        for (repo, bin) in [("cli/cli", &gh), ("myorg/tool", &tool)] {
+            record_install(
+                repo,
+                "v1.0.0",
+                std::slice::from_ref(bin),
+                false,
+                &InstallMeta::default(),
+            )
+            .expect("record");
+        }
+
+        uninstall_with("gh", false).expect("uninstall by bin");
+        assert!(!gh.exists());
+        uninstall_with("tool", false).expect("uninstall default owner's repo");
+        assert!(!tool.exists());
+        assert!(load_state().expect("state").installs.is_empty());
+    }
+
+    #[test]
+    fn read_repo_file_skips_comments_and_blank_lines() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let path = temp.path().join("repos.txt");
+        fs::write(&path, "# tools\nmxcl/tool\n\n  cli/cli  # gh\n").expect("write");
+        let repos = read_repo_file(&path).expect("read repo file");
+        assert_eq!(repos, ["mxcl/tool", "cli/cli"]);
+    }
+
+    #[test]
+    #[serial]
+    fn export_manifest_round_trips_through_repo_file() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+
+        record_install(
+            "mxcl/tool",
+            "v1.0.0",
+            &[temp.path().join("tool")],
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+        record_install(
+            "cli/cli",
+            "v2.0.0",
+            &[temp.path().join("gh")],
+            true,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+
+        let manifest = export_manifest().expect("export manifest");
+        assert!(manifest.contains("version = \"v2.0.0\""));
+        let path = temp.path().join("tools.toml");
+        fs::write(&path, manifest).expect("write manifest");
+        let repos = read_repo_file(&path).expect("read manifest");
+        assert_eq!(repos, ["cli/cli@v2.0.0", "mxcl/tool"]);
+    }
+
+    #[test]
+    #[serial]
+    fn toml_state_round_trips() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let _format = EnvGuard::set("YOINK_STATE_FORMAT", "toml");
+        let bin = temp.path().join("bin").join("tool");
+        let extra = temp.path().join("bin").join("helper");
+
+        record_install(
+            "mxcl/tool",
+            "v1.2.0",
+            &[bin.clone(), extra.clone()],
+            true,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+
+        let path = state_path().expect("state path");
+        assert_eq!(path.file_name(), Some(OsStr::new("installed.toml")));
+        let text = fs::read_to_string(&path).expect("read state");
+        assert!(text.contains("[installs.\"mxcl/tool\"]"), "{text}");
+        assert!(text.contains("pinned = true"), "{text}");
+
+        let state = load_state().expect("load state");
+        let entry = state.installs.get("mxcl/tool").expect("entry");
+        assert_eq!(entry.version, "v1.2.0");
+        assert_eq!(entry.bin, bin);
+        assert_eq!(entry.bins, vec![extra]);
+        assert!(entry.pinned);
+
+        drop(_format);
+        assert_eq!(state_path().expect("detected state path"), path);
+    }
+
+    #[test]
+    #[serial]
+    fn switching_state_format_migrates_existing_installs() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let bin = temp.path().join("bin").join("tool");
+        record_install(
+            "mxcl/tool",
+            "v1.2.0",
+            std::slice::from_ref(&bin),
+            true,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+        let json = state_path().expect("json state path");
+        assert_eq!(json.file_name(), Some(OsStr::new("installed.json")));
+
+        let format = EnvGuard::set("YOINK_STATE_FORMAT", "toml");
+        let state = load_state().expect("load migrated state");
+        let entry = state.installs.get("mxcl/tool").expect("entry");
+        assert_eq!(entry.version, "v1.2.0");
+        assert_eq!(entry.bin, bin);
+        assert!(entry.pinned);
+        assert!(!json.exists());
+        let toml = state_path().expect("toml state path");
+        assert_eq!(toml.file_name(), Some(OsStr::new("installed.toml")));
+        let text = fs::read_to_string(&toml).expect("read toml state");
+        assert!(text.contains("[installs.\"mxcl/tool\"]"), "{text}");
+
+        drop(format);
+        let _format = EnvGuard::set("YOINK_STATE_FORMAT", "json");
+        let state = load_state().expect("load state migrated back");
+        assert!(state.installs.contains_key("mxcl/tool"));
+        assert!(json.exists());
+        assert!(!toml.exists());
+    }
+
+    #[test]
+    fn read_state_defaults_on_empty_file() {
+        let mut file = tempfile::tempfile().expect("temp file");
+        let state = read_state_locked(&mut file, StateFormat::Json).expect("read state");
+        assert!(state.installs.is_empty());
+    }
+
+    #[test]
+    fn extract_zip_archive() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.zip");
+        write_zip(&archive, &[("tool", b"bin"), ("README.md", b"doc")]);
+
+        let extracted =
+            extract_archive(&archive, "tool", &Options::default()).expect("extract zip");
+        assert!(extracted.primary.ends_with("tool"));
+        assert!(extracted.primary.exists());
+    }
+
+    #[test]
+    fn extract_cab_and_7z_reject_path_traversal() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let dest = temp.path().join("dest");
+        fs::create_dir(&dest).expect("mkdir");
+        let payload = temp.path().join("payload");
+        fs::write(&payload, b"evil").expect("write payload");
+        for name in ["../evil", "/abs/evil", "bin/../../evil"] {
+            let cab = temp.path().join("tool.cab");
+            write_cab(&cab, &[("tool", b"bin"), (name, b"evil")]);
+            let sevenz = temp.path().join("tool.7z");
+            let mut writer = sevenz_rust::SevenZWriter::create(&sevenz).expect("create 7z");
+            let entry = sevenz_rust::SevenZArchiveEntry::from_path(&payload, name.to_string());
+            let reader = fs::File::open(&payload).expect("open payload");
+            writer
+                .push_archive_entry(entry, Some(reader))
+                .expect("write 7z entry");
+            writer.finish().expect("finish 7z");
+
+            for archive in [&cab, &sevenz] {
+                let err = unpack_archive(archive, &dest).expect_err("traversal should fail");
+                assert!(err.is::<UnsafeEntryPath>(), "{err:#}");
+                assert!(!format!("{err:#}").contains("may be corrupt"), "{err:#}");
+                assert!(!temp.path().join("evil").exists());
+            }
+        }
+    }
+
+    #[test]
+    fn extract_zip_rejects_path_traversal() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let dest = temp.path().join("dest");
+        fs::create_dir(&dest).expect("mkdir");
+        for name in ["../evil", "/abs/evil", "bin/../../evil"] {
+            let archive = temp.path().join("tool.zip");
+            write_zip(&archive, &[("tool", b"bin"), (name, b"evil")]);
+
+            let err = unpack_archive(&archive, &dest).expect_err("traversal should fail");
+            assert!(err.is::<UnsafeEntryPath>(), "{err:#}");
+            assert!(!format!("{err:#}").contains("may be corrupt"), "{err:#}");
+            assert!(!temp.path().join("evil").exists());
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn contained_entry_path_rejects_writes_through_symlinks() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let dest = temp.path().join("dest");
+        fs::create_dir(&dest).expect("mkdir");
+        std::os::unix::fs::symlink(temp.path(), dest.join("link")).expect("symlink");
+
+        assert!(contained_entry_path(&dest, "link/evil").is_err());
+        assert_eq!(
+            contained_entry_path(&dest, "./bin/tool").expect("safe path"),
+            dest.join("bin").join("tool")
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn extract_zip_keeps_unix_modes_for_extras() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&archive).expect("create zip"));
+        for (name, mode) in [("tool", 0o644), ("helper", 0o750), ("libtool.so", 0o644)] {
+            let options = zip::write::FileOptions::default().unix_permissions(mode);
+            zip.start_file(name, options).expect("start file");
+            zip.write_all(b"bin").expect("write file");
+        }
+        zip.finish().expect("finish zip");
+
+        let mode = |path: &Path| fs::metadata(path).expect("stat").permissions().mode() & 0o777;
+        let extracted =
+            extract_archive(&archive, "tool", &Options::default()).expect("extract zip");
+        let root = extracted.primary.parent().expect("extract root");
+        assert_eq!(mode(&root.join("helper")), 0o750);
+        assert_eq!(mode(&root.join("libtool.so")), 0o644);
+
+        let dest = temp.path().join("dest");
+        fs::create_dir(&dest).expect("mkdir");
+        install_binary(&extracted.primary, &dest.join("tool"), true).expect("install primary");
+        install_binary(&root.join("libtool.so"), &dest.join("libtool.so"), false)
+            .expect("install extra");
+        assert_eq!(mode(&dest.join("tool")), 0o755);
+        assert_eq!(mode(&dest.join("libtool.so")), 0o644);
+    }
+
+    #[test]
+    fn extract_archive_installs_config_files() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.tar.gz");
+        write_tar_gz(
+            &archive,
+            &[
+                ("tool-1.0/bin/tool", b"bin"),
+                ("tool-1.0/config/default.toml", b"level = 1"),
+                ("tool-1.0/README.md", b"doc"),
+            ],
+        );
+        let config_dir = temp.path().join("config");
+
+        extract_archive(&archive, "tool", &Options::default()).expect("extract without flag");
+        assert!(!config_dir.exists());
+
+        let options = Options {
+            install_config: Some(config_dir.clone()),
+            ..Options::default()
+        };
+        let extracted = extract_archive(&archive, "tool", &options).expect("extract");
+        assert!(extracted.primary.ends_with("bin/tool"));
+        assert_eq!(
+            fs::read(config_dir.join("default.toml")).expect("config installed"),
+            b"level = 1"
+        );
+        assert!(!config_dir.join("README.md").exists());
+
+        fs::write(config_dir.join("default.toml"), b"level = 2").expect("edit config");
+        extract_archive(&archive, "tool", &options).expect("extract again");
+        assert_eq!(
+            fs::read(config_dir.join("default.toml")).expect("config kept"),
+            b"level = 2"
+        );
+
+        let readme_only = Options {
+            config_glob: Some("*/README.md".to_string()),
+            ..options
+        };
+        extract_archive(&archive, "tool", &readme_only).expect("extract with glob");
+        assert!(config_dir.join("README.md").exists());
+    }
+
+    #[test]
+    fn extract_zip_directory_entries() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.zip");
+        let file = fs::File::create(&archive).expect("create zip");
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::FileOptions::default();
+        zip.add_directory("bin/", options).expect("add dir");
+        zip.start_file("bin/tool", options).expect("start file");
+        zip.write_all(b"bin").expect("write file");
+        zip.finish().expect("finish zip");
+
+        let extracted =
+            extract_archive(&archive, "tool", &Options::default()).expect("extract zip");
+        assert!(extracted.primary.ends_with("tool"));
+    }
+
+    #[test]
+    fn extract_tar_gz_archive() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.tar.gz");
+        write_tar_gz(&archive, &[("tool", b"bin")]);
+
+        let extracted =
+            extract_archive(&archive, "tool", &Options::default()).expect("extract tar.gz");
+        assert!(extracted.primary.ends_with("tool"));
+    }
+
+    #[test]
+    fn extract_tar_xz_archive() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.tar.xz");
+        write_tar_xz(&archive, &[("tool", b"bin")]);
+
+        let extracted =
+            extract_archive(&archive, "tool", &Options::default()).expect("extract tar.xz");
+        assert!(extracted.primary.ends_with("tool"));
+    }
+
+    #[test]
+    fn extract_tar_bz2_archive() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.tar.bz2");
+        write_tar_bz2(&archive, &[("tool", b"bin")]);
+
+        let extracted =
+            extract_archive(&archive, "tool", &Options::default()).expect("extract tar.bz2");
+        assert!(extracted.primary.ends_with("tool"));
+    }
+
+    #[test]
+    fn extract_tar_zst_archive() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.tar.zst");
+        write_tar_zst(&archive, &[("tool", b"bin")]);
+
+        let extracted =
+            extract_archive(&archive, "tool", &Options::default()).expect("extract tar.zst");
+        assert!(extracted.primary.ends_with("tool"));
+    }
+
+    #[test]
+    fn extract_cab_archive() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.cab");
+        write_cab(
+            &archive,
+            &[("bin\\tool.exe", b"bin"), ("README.txt", b"doc")],
+        );
+
+        let extracted =
+            extract_archive(&archive, "tool", &Options::default()).expect("extract cab");
+        assert!(extracted.primary.ends_with("bin/tool.exe"));
+        assert_eq!(fs::read(&extracted.primary).expect("read tool"), b"bin");
+    }
+
+    #[test]
+    fn progress_dots_mark_each_chunk() {
+        let body = vec![0u8; (DOT_BYTES * 4 + 10) as usize];
+        let mut err = Vec::new();
+        let mut reader = ProgressReader::new(Cursor::new(body), ProgressMode::Dots, None, &mut err);
+        io::copy(&mut reader, &mut io::sink()).expect("copy");
+        reader.finish();
+        assert_eq!(err, b"....\n");
+    }
+
+    #[test]
+    fn progress_bar_shows_total_and_rate() {
+        let body = vec![0u8; 1024 * 1024];
+        let mut err = Vec::new();
+        let mut reader = ProgressReader::new(
+            Cursor::new(body),
+            ProgressMode::Bar,
+            Some(1024 * 1024),
+            &mut err,
+        );
+        io::copy(&mut reader, &mut io::sink()).expect("copy");
+        reader.finish();
+        let err = String::from_utf8(err).expect("utf8");
+        let last = err.trim_end().rsplit('\r').next().expect("last line");
+        assert!(
+            last.starts_with("[####################] 100% 1.0 MiB / 1.0 MiB "),
+            "{last}"
+        );
+        assert!(last.ends_with("MiB/s"), "{last}");
+    }
+
+    #[test]
+    fn progress_none_is_silent() {
+        let mut err = Vec::new();
+        let mut reader = ProgressReader::new(
+            Cursor::new(vec![0u8; DOT_BYTES as usize * 2]),
+            ProgressMode::None,
+            None,
+            &mut err,
+        );
+        io::copy(&mut reader, &mut io::sink()).expect("copy");
+        reader.finish();
+        assert!(err.is_empty());
+    }
+
+    #[test]
+    fn extract_7z_archive() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let source = temp.path().join("source");
+        fs::create_dir_all(source.join("bin")).expect("mkdir");
+        fs::write(source.join("bin").join("tool"), b"bin").expect("write tool");
+        fs::write(source.join("README.md"), b"doc").expect("write readme");
+        let archive = temp.path().join("tool.7z");
+        sevenz_rust::compress_to_path(&source, &archive).expect("build 7z");
+
+        let extracted = extract_archive(&archive, "tool", &Options::default()).expect("extract 7z");
+        assert!(extracted.primary.ends_with("bin/tool"));
+        assert_eq!(fs::read(&extracted.primary).expect("read tool"), b"bin");
+    }
+
+    #[test]
+    fn asset_score_prefers_zip_over_cab_off_windows() {
+        let zip = asset_score("tool.zip", &[], &[], &LibcTokens::default());
+        let cab = asset_score("tool.cab", &[], &[], &LibcTokens::default());
+        if cfg!(windows) {
+            assert_eq!(zip, cab);
+        } else {
+            assert!(zip > cab);
+        }
+    }
+
+    #[test]
+    fn extract_archive_rejects_unknown_format() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.rar");
+        fs::write(&archive, b"bad").expect("write");
+        assert!(extract_archive(&archive, "tool", &Options::default()).is_err());
+    }
+
+    #[test]
+    fn extract_gzip_non_archive() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("payload.gz");
+        write_gzip(&archive, b"bin");
+
+        let extracted = extract_gzip(&archive, "tool", &Options::default()).expect("extract gzip");
+        assert!(extracted.primary.exists());
+    }
+
+    #[test]
+    fn extract_gzip_matches_repo_name() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.gz");
+        write_gzip(&archive, b"bin");
+
+        let extracted = extract_gzip(&archive, "tool", &Options::default()).expect("extract gzip");
+        assert!(extracted.primary.ends_with("tool"));
+    }
+
+    #[test]
+    fn extract_zstd_matches_repo_name() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.zst");
+        fs::write(
+            &archive,
+            zstd::encode_all(&b"bin"[..], 0).expect("encode zstd"),
+        )
+        .expect("write zstd");
+
+        let extracted = extract_zstd(&archive, "tool", &Options::default()).expect("extract zstd");
+        assert!(extracted.primary.ends_with("tool"));
+        assert_eq!(fs::read(&extracted.primary).expect("read"), b"bin");
+    }
+
+    #[test]
+    fn download_asset_from_local_server() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            responses.insert("/asset".to_string(), b"hello".to_vec());
+            let _ = base;
+            responses
+        });
+
+        let client = github_client("mxcl", &Options::default()).expect("client");
+        let temp = tempfile::tempdir().expect("temp dir");
+        let dest = temp.path().join("asset");
+        let url = format!("{}/asset", server.base);
+        download_asset(&client, &url, &dest, &Options::default()).expect("download asset");
+        assert_eq!(fs::read(&dest).expect("read"), b"hello");
+
+        server.finish();
+    }
+
+    /// Serves `mxcl/<name>` releases for `names`, holding each response briefly; returns the
+    /// most requests it ever had in flight at once.
+    fn spawn_overlap_server(names: &[&str]) -> (String, thread::JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base = format!("http://{}", listener.local_addr().expect("addr"));
+        let mut responses = BTreeMap::new();
+        for name in names {
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"{name}\",\"browser_download_url\":\"{base}/download/{name}\"}}]}}"
+            );
+            responses.insert(
+                format!("/repos/mxcl/{name}/releases/latest"),
+                body.into_bytes(),
+            );
+            responses.insert(format!("/download/{name}"), b"bin".to_vec());
+        }
+        let handle = thread::spawn(move || {
+            let responses = Arc::new(responses);
+            let in_flight = Arc::new(AtomicUsize::new(0));
+            let peak = Arc::new(AtomicUsize::new(0));
+            let connections: Vec<_> = (0..responses.len())
+                .map(|_| {
+                    let (mut stream, _) = listener.accept().expect("accept");
+                    let (responses, in_flight, peak) =
+                        (responses.clone(), in_flight.clone(), peak.clone());
+                    thread::spawn(move || {
+                        let now = in_flight.fetch_add(1, atomic::Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, atomic::Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(150));
+                        in_flight.fetch_sub(1, atomic::Ordering::SeqCst);
+                        respond(&mut stream, &responses);
+                    })
+                })
+                .collect();
+            for connection in connections {
+                connection.join().expect("connection thread");
+            }
+            peak.load(atomic::Ordering::SeqCst)
+        });
+        (base, handle)
+    }
+
+    #[test]
+    #[serial]
+    fn download_many_bounds_parallel_repos() {
+        let repos = vec!["mxcl/alpha".to_string(), "mxcl/beta".to_string()];
+        for (jobs, expected_peak) in [(1, 1), (2, 2)] {
+            let (base, server) = spawn_overlap_server(&["alpha", "beta"]);
+            let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &base);
+            let dest = tempfile::tempdir().expect("temp dir");
+            let options = Options {
+                max_parallel_repos: Some(jobs),
+                ..Options::default()
+            };
+            let results = download_many(&repos, dest.path(), &options);
+            let tags: Vec<String> = results
+                .into_iter()
+                .map(|result| result.expect("download").tag)
+                .collect();
+            assert_eq!(tags, ["v1.0.0", "v1.0.0"]);
+            assert!(dest.path().join("alpha").exists());
+            assert!(dest.path().join("beta").exists());
+            assert_eq!(server.join().expect("server"), expected_peak, "jobs={jobs}");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn upgrade_all_with_bounds_parallel_repos() {
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        for name in ["alpha", "beta"] {
+            record_install(
+                &format!("mxcl/{name}"),
+                "v0.1.0",
+                &[bin.path().join(name)],
+                false,
+                &InstallMeta::default(),
+            )
+            .expect("record install");
+        }
+
+        let (base, server) = spawn_overlap_server(&["alpha", "beta"]);
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &base);
+        let options = Options {
+            max_parallel_repos: Some(1),
+            ..Options::default()
+        };
+        let upgrades = upgrade_all_with(&options).expect("upgrade").upgraded;
+        assert_eq!(upgrades.len(), 2);
+        assert_eq!(server.join().expect("server"), 1);
+    }
+
+    /// Serves `body` once cut off halfway, then answers the retry with a 206 for the rest, or
+    /// with the whole body when `honor_range` is off.
+    fn spawn_flaky_server(body: Vec<u8>, honor_range: bool) -> (String, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base = format!("http://{}", listener.local_addr().expect("addr"));
+        let handle = thread::spawn(move || {
+            let half = body.len() / 2;
+            for attempt in 0..2 {
+                let (stream, _) = listener.accept().expect("accept");
+                let mut reader = BufReader::new(stream);
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    let bytes = reader.read_line(&mut line).expect("read header");
+                    if bytes == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("range: ") {
+                        range = Some(value.trim().to_string());
+                    }
+                }
+                let stream = reader.get_mut();
+                if attempt == 0 {
+                    assert_eq!(range, None);
+                    let header = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    stream.write_all(header.as_bytes()).expect("write header");
+                    stream.write_all(&body[..half]).expect("write body");
+                    continue;
+                }
+                assert_eq!(range, Some(format!("bytes={half}-")));
+                let header = if honor_range {
+                    format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {half}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len() - 1,
+                        body.len(),
+                        body.len() - half
+                    )
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    )
+                };
+                stream.write_all(header.as_bytes()).expect("write header");
+                let rest = if honor_range {
+                    &body[half..]
+                } else {
+                    &body[..]
+                };
+                stream.write_all(rest).expect("write body");
+            }
+        });
+        (base, handle)
+    }
+
+    #[test]
+    fn download_asset_resumes_interrupted_body() {
+        let body: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let options = Options {
+            network: NetworkConfig {
+                retries: Some(1),
+                ..NetworkConfig::default()
+            },
+            ..Options::default()
+        };
+        let client = github_client("mxcl", &options).expect("client");
+        for honor_range in [true, false] {
+            let (base, handle) = spawn_flaky_server(body.clone(), honor_range);
+            let temp = tempfile::tempdir().expect("temp dir");
+            let dest = temp.path().join("asset");
+            download_asset(&client, &format!("{base}/asset"), &dest, &options).expect("download");
+            assert_eq!(fs::read(&dest).expect("read"), body);
+            assert!(!temp.path().join("asset.part").exists());
+            handle.join().expect("server thread");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn rate_limited_release_fetch_explains_reset() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base = format!("http://{}", listener.local_addr().expect("addr"));
+        let reset = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("now")
+            .as_secs()
+            + 600;
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream);
+            loop {
+                let mut line = String::new();
+                let bytes = reader.read_line(&mut line).expect("read header");
+                if bytes == 0 || line == "\r\n" {
+                    break;
+                }
+            }
+            let body = br#"{"message":"API rate limit exceeded"}"#;
+            let header = format!(
+                "HTTP/1.1 403 Forbidden\r\nX-RateLimit-Remaining: 0\r\nX-RateLimit-Reset: {reset}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let stream = reader.get_mut();
+            stream.write_all(header.as_bytes()).expect("write header");
+            stream.write_all(body).expect("write body");
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &base);
+        let err = release_info("mxcl/tool").expect_err("rate limited");
+        handle.join().expect("server");
+        assert!(matches!(err, YoinkError::RateLimited(_)), "{err:?}");
+        let message = format!("{err:#}");
+        assert!(message.contains("rate limit exceeded"), "{message}");
+        assert!(message.contains("(in 10m)"), "{message}");
+        assert!(message.contains("YOINK_GITHUB_TOKEN"), "{message}");
+    }
+
+    #[test]
+    #[serial]
+    fn forbidden_without_rate_limit_headers_is_not_rate_limited() {
+        let server = TestServer::sequence(|_| {
+            let latest = "/repos/mxcl/tool/releases/latest".to_string();
+            vec![
+                (latest.clone(), 403, br#"{"message":"Forbidden"}"#.to_vec()),
+                (latest, 429, br#"{"message":"Slow down"}"#.to_vec()),
+            ]
+        });
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        for _ in 0..2 {
+            let err = release_info("mxcl/tool").expect_err("forbidden");
+            assert!(matches!(err, YoinkError::Network(_)), "{err:?}");
+        }
+
+        server.finish();
+    }
+
+    #[test]
+    fn download_asset_parallel_reassembles_ranges() {
+        let body: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let (base, handle) = spawn_range_server(body.clone(), RangeSupport::Ranges, 4);
+
+        let client = github_client("mxcl", &Options::default()).expect("client");
+        let temp = tempfile::tempdir().expect("temp dir");
+        let dest = temp.path().join("asset");
+        let url = format!("{base}/asset");
+        let ranged = download_asset_parallel(&client, &url, &dest, 3, &Options::default())
+            .expect("download");
+        assert!(ranged);
+        assert_eq!(fs::read(&dest).expect("read"), body);
+        assert!(!temp.path().join("asset.part").exists());
+
+        handle.join().expect("server thread");
+    }
+
+    #[test]
+    fn fetch_asset_falls_back_without_range_support() {
+        // Each server answers the HEAD probe, then whatever the parallel path asks before it
+        // gives up, then the single-stream download.
+        for (support, requests) in [
+            (RangeSupport::None, 2),
+            (RangeSupport::HeadRejected, 2),
+            (RangeSupport::Ignored, 4),
+        ] {
+            let body = b"hello".to_vec();
+            let (base, handle) = spawn_range_server(body.clone(), support, requests);
+
+            let client = github_client("mxcl", &Options::default()).expect("client");
+            let temp = tempfile::tempdir().expect("temp dir");
+            let dest = temp.path().join("asset");
+            let url = format!("{base}/asset");
+            let options = Options {
+                parallel_download: Some(2),
+                ..Options::default()
+            };
+            fetch_asset(&client, &url, &dest, &options).expect("download");
+            assert_eq!(fs::read(&dest).expect("read"), body, "{support:?}");
+            assert!(!temp.path().join("asset.part").exists());
+
+            handle.join().expect("server thread");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn github_client_uses_token_header() {
+        let _guard = EnvGuard::set("YOINK_GITHUB_TOKEN", "token123");
+        let _client = github_client("mxcl", &Options::default()).expect("client");
+    }
+
+    #[test]
+    #[serial]
+    fn owner_token_map_overrides_global_token() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let config = temp.path().join("config.toml");
+        fs::write(&config, "[owner-tokens]\nacme = \"acme-token\"\n").expect("write config");
+        let _config = EnvGuard::set("YOINK_CONFIG", &config);
+        let _token = EnvGuard::set("YOINK_GITHUB_TOKEN", "global-token");
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base = format!("http://{}", listener.local_addr().expect("addr"));
+        let body = format!(
+            "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
+        );
+        let handle = thread::spawn(move || {
+            let mut authorizations = Vec::new();
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().expect("accept");
+                let mut reader = BufReader::new(stream);
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("read header");
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("authorization") {
+                            authorizations.push(value.trim().to_string());
+                        }
+                    }
+                }
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let stream = reader.get_mut();
+                stream.write_all(header.as_bytes()).expect("write header");
+                stream.write_all(body.as_bytes()).expect("write body");
+            }
+            authorizations
+        });
+
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &base);
+        release_info("acme/tool").expect("acme release info");
+        release_info("mxcl/tool").expect("mxcl release info");
+        let authorizations = handle.join().expect("server thread");
+        assert_eq!(authorizations, ["token acme-token", "token global-token"]);
+    }
+
+    #[test]
+    fn expected_checksum_reads_bare_and_listed_hashes() {
+        let hash = "a".repeat(64);
+        let other = "b".repeat(64);
+        assert_eq!(
+            expected_checksum(&format!("{hash}\n"), "tool.tar.gz"),
+            Some(hash.clone())
+        );
+        let listing = format!("{other}  tool-mac.tar.gz\n{hash} *dist/tool.tar.gz\n");
+        assert_eq!(expected_checksum(&listing, "tool.tar.gz"), Some(hash));
+        assert_eq!(expected_checksum(&listing, "tool.zip"), None);
+        assert_eq!(expected_checksum("not a hash\n", "tool.tar.gz"), None);
+    }
+
+    #[test]
+    #[serial]
+    fn prepare_binary_verifies_sha256_asset() {
+        let serve = |checksum: String| {
+            TestServer::new(move |base| {
+                let mut responses = BTreeMap::new();
+                let body = format!(
+                    "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}},{{\"name\":\"tool.sha256\",\"browser_download_url\":\"{base}/download/tool.sha256\"}}]}}"
+                );
+                responses.insert(
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    body.into_bytes(),
+                );
+                responses.insert("/download/tool".to_string(), b"bin".to_vec());
+                responses.insert("/download/tool.sha256".to_string(), checksum.into_bytes());
+                responses
+            })
+        };
+        let good = format!("{:x}  tool\n", Sha256::digest(b"bin"));
+        let bad = format!("{:x}  tool\n", Sha256::digest(b"tampered"));
+
+        let server = serve(good);
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let prepared = prepare_binary("mxcl/tool", &Options::default()).expect("verified");
+        assert_eq!(fs::read(&prepared.path).expect("read"), b"bin");
+        server.finish();
+
+        let server = serve(bad);
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let err = prepare_binary("mxcl/tool", &Options::default())
+            .err()
+            .expect("mismatch should fail");
+        let message = format!("{err:#}");
+        assert!(message.contains(&format!("{:x}", Sha256::digest(b"tampered"))));
+        assert!(message.contains(&format!("{:x}", Sha256::digest(b"bin"))));
+        server.finish();
+
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}},{{\"name\":\"tool.sha256\",\"browser_download_url\":\"{base}/download/tool.sha256\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            no_verify: true,
+            ..Options::default()
+        };
+        prepare_binary("mxcl/tool", &options).expect("unverified");
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn configured_extract_path_overrides_heuristics() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool.tar.gz");
+            let body = format!(
+                "{{\"tag_name\":\"v2.0.0\",\"assets\":[{{\"name\":\"tool.tar.gz\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let tar = make_tar_gz_bytes(&[("tool", b"wrapper"), ("bin/linux/tool", b"real")]);
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool.tar.gz".to_string(), tar);
+            responses
+        });
+
+        let temp = tempfile::tempdir().expect("temp dir");
+        let config = temp.path().join("config.toml");
+        fs::write(&config, "[extract]\n\"mxcl/tool\" = \"bin/linux/tool\"\n")
+            .expect("write config");
+        let _config = EnvGuard::set("YOINK_CONFIG", &config);
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let prepared = prepare_binary("mxcl/tool", &Options::default()).expect("prepare binary");
+        assert!(prepared.path.ends_with("bin/linux/tool"));
+        assert_eq!(fs::read(&prepared.path).expect("read binary"), b"real");
+
+        server.finish();
+    }
+
+    #[test]
+    fn find_binaries_errors_when_binary_path_missing() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        fs::write(root.join("tool"), b"bin").expect("write tool");
+        let options = Options {
+            binary_path: Some("bin/tool".to_string()),
+            ..Options::default()
+        };
+        assert!(find_binaries(root, "tool", &options).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn network_config_merges_config_env_and_flags() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let config = temp.path().join("config.toml");
+        fs::write(&config, "[network]\ntimeout = 7\nretries = 2\n").expect("write config");
+        let _config = EnvGuard::set("YOINK_CONFIG", &config);
+
+        let network = network_config(&Options::default()).expect("network config");
+        assert_eq!(network.timeout(), Duration::from_secs(7));
+        assert_eq!(network.retries, Some(2));
+        assert_eq!(network.retry_404, None);
+
+        {
+            let _retries = EnvGuard::set("YOINK_RETRIES", "1");
+            let network = network_config(&Options::default()).expect("network config");
+            assert_eq!(network.retries, Some(1));
+            let flags = Options {
+                network: NetworkConfig {
+                    retries: Some(3),
+                    ..NetworkConfig::default()
+                },
+                ..Options::default()
+            };
+            let network = network_config(&flags).expect("network config");
+            assert_eq!(network.retries, Some(3));
+            assert_eq!(network.timeout, Some(Duration::from_secs(7)));
+        }
+        {
+            let _timeout = EnvGuard::set("YOINK_TIMEOUT", "250ms");
+            let network = network_config(&Options::default()).expect("network config");
+            assert_eq!(network.timeout(), Duration::from_millis(250));
+        }
+        let network: NetworkConfig = toml::from_str("timeout = \"500ms\"").expect("parse network");
+        assert_eq!(network.timeout(), Duration::from_millis(500));
+
+        let server = TestServer::sequence(|base| {
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
+            );
+            let latest = "/repos/mxcl/tool/releases/latest".to_string();
+            vec![
+                (latest.clone(), 503, b"{}".to_vec()),
+                (latest.clone(), 502, b"{}".to_vec()),
+                (latest, 200, body.into_bytes()),
+            ]
+        });
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let info = release_info("mxcl/tool").expect("retried release info");
+        assert_eq!(info.tag, "v1.0.0");
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn release_info_uses_override_base() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let info = release_info("mxcl/tool").expect("release info");
+        assert_eq!(info.owner, "mxcl");
+        assert_eq!(info.name, "tool");
+        assert_eq!(info.tag, "v1.0.0");
+        assert_eq!(info.asset_name, "tool");
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn release_info_resolves_gitlab_releases() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let release = |tag: &str| {
+                format!(
+                    "{{\"tag_name\":\"{tag}\",\"assets\":{{\"links\":[{{\"name\":\"tool\",\"url\":\"{base}/link/tool\",\"direct_asset_url\":\"{base}/direct/tool\"}}]}}}}"
+                )
+            };
+            responses.insert(
+                "/projects/mxcl%2Ftool/releases/permalink/latest".to_string(),
+                release("v2.0.0").into_bytes(),
+            );
+            responses.insert(
+                "/projects/mxcl%2Ftool/releases/v1.0.0".to_string(),
+                release("v1.0.0").into_bytes(),
+            );
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITLAB_API_BASE", &server.base);
+        let info = release_info("gitlab:mxcl/tool").expect("latest release");
+        assert_eq!((info.owner.as_str(), info.name.as_str()), ("mxcl", "tool"));
+        assert_eq!(info.tag, "v2.0.0");
+        assert_eq!(info.asset_url, format!("{}/direct/tool", server.base));
+
+        let options = Options {
+            host: Some("gitlab.example.com".to_string()),
+            ..Options::default()
+        };
+        let info = release_info_with("mxcl/tool@v1.0.0", &options).expect("tagged release");
+        assert_eq!(info.tag, "v1.0.0");
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn gitlab_honors_pre_and_semver_latest_and_encodes_tags() {
+        let server = TestServer::sequence(|base| {
+            let release = |tag: &str, released_at: &str, upcoming: bool| {
+                format!(
+                    "{{\"tag_name\":\"{tag}\",\"released_at\":\"{released_at}\",\"upcoming_release\":{upcoming},\"assets\":{{\"links\":[{{\"name\":\"tool\",\"url\":\"{base}/download/tool\"}}]}}}}"
+                )
+            };
+            let releases = format!(
+                "[{},{},{}]",
+                release("v2.0.0-rc.1", "2024-03-01T00:00:00Z", true),
+                release("v1.9.0", "2024-02-01T00:00:00Z", false),
+                release("v1.10.0", "2024-01-01T00:00:00Z", false)
+            );
+            let list = "/projects/mxcl%2Ftool/releases?per_page=100".to_string();
+            vec![
+                (list.clone(), 200, releases.clone().into_bytes()),
+                (list, 200, releases.into_bytes()),
+                (
+                    "/projects/mxcl%2Ftool/releases/tool%2Fv1.0.0".to_string(),
+                    200,
+                    release("tool/v1.0.0", "2023-01-01T00:00:00Z", false).into_bytes(),
+                ),
+            ]
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITLAB_API_BASE", &server.base);
+        let pre = Options {
+            pre: true,
+            ..Options::default()
+        };
+        let info = release_info_with("gitlab:mxcl/tool", &pre).expect("newest release");
+        assert_eq!(info.tag, "v2.0.0-rc.1");
+        let semver = Options {
+            semver_latest: true,
+            ..Options::default()
+        };
+        let info = release_info_with("gitlab:mxcl/tool", &semver).expect("highest release");
+        assert_eq!(info.tag, "v1.10.0");
+        let info = release_info("gitlab:mxcl/tool@tool/v1.0.0").expect("tagged release");
+        assert_eq!(info.tag, "tool/v1.0.0");
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn gitlab_host_installs_upgrade_from_the_same_host() {
+        let server = TestServer::sequence(|base| {
+            let release = |tag: &str| {
+                format!(
+                    "{{\"tag_name\":\"{tag}\",\"assets\":{{\"links\":[{{\"name\":\"tool\",\"url\":\"{base}/download/{tag}\"}}]}}}}"
+                )
+                .into_bytes()
+            };
+            let latest = "/projects/mxcl%2Ftool/releases/permalink/latest".to_string();
+            vec![
+                (latest.clone(), 200, release("v1.0.0")),
+                ("/download/v1.0.0".to_string(), 200, b"v1".to_vec()),
+                (latest, 200, release("v2.0.0")),
+                ("/download/v2.0.0".to_string(), 200, b"v2".to_vec()),
+            ]
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _gitlab_guard = EnvGuard::set("YOINK_GITLAB_API_BASE", &server.base);
+        let _github_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", "http://127.0.0.1:9");
+
+        let options = Options {
+            host: Some("gitlab.example.com".to_string()),
+            ..Options::default()
+        };
+        let report = install_with_report("mxcl/tool", &options).expect("install");
+        assert_eq!(report.repo, "gitlab:mxcl/tool");
+        let state = load_state().expect("load state");
+        assert_eq!(
+            state.installs["gitlab:mxcl/tool"].host.as_deref(),
+            Some("gitlab.example.com")
+        );
+
+        let upgrades = upgrade_all().expect("upgrade").upgraded;
+        assert_eq!(upgrades[0].version, "2.0.0");
+        assert_eq!(fs::read(bin.path().join("tool")).expect("read bin"), b"v2");
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn prepare_binary_downloads_and_extracts() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool.tar.gz");
+            let body = format!(
+                "{{\"tag_name\":\"v2.0.0\",\"assets\":[{{\"name\":\"tool.tar.gz\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let tar = make_tar_gz_bytes(&[("tool", b"bin")]);
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool.tar.gz".to_string(), tar);
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let prepared = prepare_binary("mxcl/tool", &Options::default()).expect("prepare binary");
+        assert!(prepared.path.exists());
+        assert_eq!(prepared.asset_name, "tool.tar.gz");
+        assert!(prepared.extra_paths.is_empty());
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn cached_downloads_skip_the_network_until_refreshed() {
+        let server = TestServer::sequence(|base| {
+            let release = format!(
+                "{{\"tag_name\":\"v2.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
+            );
+            let latest = "/repos/mxcl/tool/releases/latest".to_string();
+            vec![
+                (latest.clone(), 200, release.clone().into_bytes()),
+                ("/download/tool".to_string(), 200, b"first".to_vec()),
+                (latest.clone(), 200, release.clone().into_bytes()),
+                (latest, 200, release.into_bytes()),
+                ("/download/tool".to_string(), 200, b"second".to_vec()),
+            ]
+        });
+
+        let data = tempfile::tempdir().expect("temp dir");
+        let _data_guard = EnvGuard::set("XDG_DATA_HOME", data.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            cache: true,
+            ..Options::default()
+        };
+
+        let prepared = prepare_binary("mxcl/tool", &options).expect("download");
+        assert_eq!(fs::read(&prepared.path).expect("read"), b"first");
+        let prepared = prepare_binary("mxcl/tool", &options).expect("cached");
+        assert_eq!(fs::read(&prepared.path).expect("read"), b"first");
+        let refreshed = Options {
+            refresh: true,
+            ..options
+        };
+        let prepared = prepare_binary("mxcl/tool", &refreshed).expect("refresh");
+        assert_eq!(fs::read(&prepared.path).expect("read"), b"second");
+        server.finish();
+
+        let cached = data
+            .path()
+            .join("yoink/cache/github.com/mxcl/tool/v2.0.0/tool");
+        assert_eq!(fs::read(&cached).expect("read cache"), b"second");
+        assert_eq!(prune_download_cache(0).expect("prune"), 6);
+        assert!(!cached.exists());
+    }
+
+    #[test]
+    #[serial]
+    fn cached_downloads_install_offline_from_cached_release_metadata() {
+        let server = TestServer::sequence(|base| {
+            let release = format!(
+                "{{\"tag_name\":\"v2.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}},{{\"name\":\"tool.sha256\",\"browser_download_url\":\"{base}/download/tool.sha256\"}}]}}"
+            );
+            let checksum = format!("{:x}  tool\n", Sha256::digest(b"bin"));
+            vec![
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    200,
+                    release.clone().into_bytes(),
+                ),
+                ("/download/tool".to_string(), 200, b"bin".to_vec()),
+                (
+                    "/download/tool.sha256".to_string(),
+                    200,
+                    checksum.into_bytes(),
+                ),
+                (
+                    "/repos/mxcl/tool/releases/tags/v2.0.0".to_string(),
+                    200,
+                    release.into_bytes(),
+                ),
+            ]
+        });
+
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _data = EnvGuard::set("XDG_DATA_HOME", temp.path().join("data"));
+        let _cache = EnvGuard::set("XDG_CACHE_HOME", temp.path().join("cache"));
+        let options = Options {
+            cache: true,
+            ..Options::default()
+        };
+        {
+            let _api = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+            prepare_binary("mxcl/tool", &options).expect("download");
+            prepare_binary("mxcl/tool@v2.0.0", &options).expect("cached asset");
+        }
+        server.finish();
+
+        let _unreachable = EnvGuard::set("YOINK_GITHUB_API_BASE", "http://127.0.0.1:9");
+        for repo in ["mxcl/tool", "mxcl/tool@v2.0.0"] {
+            let prepared = prepare_binary(repo, &options).expect("offline");
+            assert_eq!(prepared.tag, "v2.0.0");
+            assert_eq!(fs::read(&prepared.path).expect("read"), b"bin");
+        }
+        let cached = temp
+            .path()
+            .join("data/yoink/cache/github.com/mxcl/tool/v2.0.0");
+        assert!(cached.join("tool").is_file());
+        assert!(cached.join("tool.checksums").is_file());
+        // Another forge's copy of the same repo isn't served from github.com's entries.
+        let gitlab = Options {
+            host: Some("gitlab.example.invalid".to_string()),
+            ..options
+        };
+        let _gitlab = EnvGuard::set("YOINK_GITLAB_API_BASE", "http://127.0.0.1:9");
+        assert!(prepare_binary("mxcl/tool@v2.0.0", &gitlab).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn prepare_binary_uses_temp_dir_override() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let body = format!(
+                "{{\"tag_name\":\"v2.0.0\",\"assets\":[{{\"name\":\"tool.tar.gz\",\"browser_download_url\":\"{base}/download/tool.tar.gz\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert(
+                "/download/tool.tar.gz".to_string(),
+                make_tar_gz_bytes(&[("tool", b"bin")]),
+            );
+            responses
+        });
+
+        let temp = tempfile::tempdir().expect("temp dir");
+        let base = temp.path().join("scratch");
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            temp_dir: Some(base.clone()),
+            ..Options::default()
+        };
+        let prepared = prepare_binary("mxcl/tool", &options).expect("prepare binary");
+        assert!(prepared._download_dir.path().starts_with(&base));
+        assert!(
+            prepared.path.starts_with(&base),
+            "{}",
+            prepared.path.display()
+        );
+        drop(prepared);
+        assert_eq!(fs::read_dir(&base).expect("read scratch").count(), 0);
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn checksum_required_refuses_unverifiable_assets() {
+        let server = TestServer::new(|base| {
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
+            );
+            BTreeMap::from([(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            )])
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            checksum_required: true,
+            ..Options::default()
+        };
+        match install_with("mxcl/tool", &options) {
+            Ok(path) => panic!("installed {} without a checksum", path.display()),
+            Err(err) => assert!(err.to_string().contains("no checksum"), "{err}"),
+        }
+        assert!(!bin.path().join("tool").exists());
+
+        // Installs never compare against a lockfile, so naming one doesn't lift the requirement.
+        let lockfile = home.path().join("yoink.lock");
+        fs::write(&lockfile, "").expect("write lockfile");
+        let options = Options {
+            lockfile: Some(lockfile),
+            ..options
+        };
+        assert!(install_with("mxcl/tool", &options).is_err());
+        assert!(!bin.path().join("tool").exists());
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn checksum_required_accepts_lockfile_digest_on_run() {
+        let script = b"#!/bin/sh\ntouch \"$1\"\n";
+        let server = TestServer::new(|base| {
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
+            );
+            BTreeMap::from([
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    body.into_bytes(),
+                ),
+                ("/download/tool".to_string(), script.to_vec()),
+            ])
+        });
+
+        let temp = tempfile::tempdir().expect("temp dir");
+        let lockfile = temp.path().join("yoink.lock");
+        let digest = format!("{:x}", Sha256::digest(script));
+        fs::write(
+            &lockfile,
+            format!("[[lock]]\nrepo = \"mxcl/tool\"\nsha256 = \"{digest}\"\n"),
+        )
+        .expect("write lockfile");
+        let marker = temp.path().join("ran");
+        let options = Options {
+            checksum_required: true,
+            lockfile: Some(lockfile),
+            ..Options::default()
+        };
+
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with("mxcl/tool", &[marker.display().to_string()], &options)
+            .expect("run verified by lockfile");
+        assert_eq!(code, 0);
+        assert!(marker.exists());
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn prepare_binary_downloads_gzip() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool.gz");
+            let body = format!(
+                "{{\"tag_name\":\"v2.1.0\",\"assets\":[{{\"name\":\"tool.gz\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let gzip = make_gzip_bytes(b"bin");
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool.gz".to_string(), gzip);
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let prepared = prepare_binary("mxcl/tool", &Options::default()).expect("prepare binary");
+        assert!(prepared.path.exists());
+        assert_eq!(prepared.asset_name, "tool.gz");
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn fetch_primary_reads_binary_bytes() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool.tar.gz");
+            let body = format!(
+                "{{\"tag_name\":\"v1.2.0\",\"assets\":[{{\"name\":\"tool.tar.gz\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert(
+                "/download/tool.tar.gz".to_string(),
+                make_tar_gz_bytes(&[("tool-1.2.0/tool", b"payload")]),
+            );
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let mut fetched = fetch_primary("mxcl/tool").expect("fetch primary");
+        assert_eq!(fetched.repo, "mxcl/tool");
+        assert_eq!(fetched.tag, "v1.2.0");
+        assert_eq!(fetched.size, 7);
+        let mut bytes = Vec::new();
+        fetched.read_to_end(&mut bytes).expect("read");
+        assert_eq!(bytes, b"payload");
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn build_fails_clearly_without_a_build_file() {
+        let server = TestServer::sequence(|_base| {
+            vec![
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    200,
+                    br#"{"tag_name":"v1.0.0","assets":[]}"#.to_vec(),
+                ),
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    200,
+                    br#"{"tag_name":"v1.0.0","assets":[]}"#.to_vec(),
+                ),
+                (
+                    "/repos/mxcl/tool/contents/Cargo.toml?ref=v1.0.0".to_string(),
+                    404,
+                    b"{}".to_vec(),
+                ),
+                (
+                    "/repos/mxcl/tool/contents/go.mod?ref=v1.0.0".to_string(),
+                    404,
+                    b"{}".to_vec(),
+                ),
+            ]
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            build: true,
+            ..Options::default()
+        };
+        let err = match prepare_binary("mxcl/tool", &options) {
+            Ok(_) => panic!("built without a build file"),
+            Err(err) => err,
+        };
+        assert!(err.to_string().contains("no Cargo.toml or go.mod"), "{err}");
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn build_refuses_when_checksums_are_required() {
+        let options = Options {
+            build: true,
+            checksum_required: true,
+            ..Options::default()
+        };
+        let client = github_client("mxcl", &options).expect("client");
+        let err = match build_from_source(&GitHub, &client, "mxcl", "tool", None, &options) {
+            Ok(_) => panic!("built without a checksum"),
+            Err(err) => err,
+        };
+        assert!(err.to_string().contains("--checksum-required"), "{err}");
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn build_installs_cargo_binary_when_release_has_no_assets() {
+        let release = br#"{"tag_name":"v1.0.0","assets":[]}"#;
+        let server = TestServer::sequence(|_base| {
+            vec![
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    200,
+                    release.to_vec(),
+                ),
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    200,
+                    release.to_vec(),
+                ),
+                (
+                    "/repos/mxcl/tool/contents/Cargo.toml?ref=v1.0.0".to_string(),
+                    200,
+                    b"{}".to_vec(),
+                ),
+            ]
+        });
+
+        let tools = tempfile::tempdir().expect("tools dir");
+        let log = tools.path().join("log");
+        let _path_guard = setup_fake_build_tools(tools.path(), &log);
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let _git_guard = EnvGuard::set("YOINK_GITHUB_GIT_BASE", "file:///git");
+        let dest = tempfile::tempdir().expect("dest dir");
+        let options = Options {
+            build: true,
+            ..Options::default()
+        };
+        let summary = download_to_dir_with("mxcl/tool", dest.path(), &options).expect("build");
+        assert_eq!(summary.tag, "v1.0.0");
+        assert_eq!(summary.asset_name, "Cargo.toml");
+        assert_eq!(summary.primary_path, dest.path().join(binary_name("tool")));
+        assert!(summary.primary_path.is_file());
+        let log = fs::read_to_string(&log).expect("read log");
+        assert!(
+            log.contains("git clone --quiet --depth 1 --branch v1.0.0 file:///git/mxcl/tool.git"),
+            "{log}"
+        );
+        assert!(log.contains("cargo install --path"), "{log}");
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn download_to_dir_installs_extras() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool.zip");
+            let body = format!(
+                "{{\"tag_name\":\"v3.0.0\",\"assets\":[{{\"name\":\"tool.zip\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let zip = make_zip_bytes(&[("tool", b"bin"), ("helper", b"bin")]);
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool.zip".to_string(), zip);
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let dest = tempfile::tempdir().expect("temp dir");
+        let summary = download_to_dir("mxcl/tool", dest.path()).expect("download");
+        assert_eq!(summary.tag, "v3.0.0");
+        assert!(summary.primary_path.exists());
+        assert_eq!(summary.paths.len(), 2);
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn download_to_dir_skips_duplicate_extras() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool.zip");
+            let body = format!(
+                "{{\"tag_name\":\"v3.1.0\",\"assets\":[{{\"name\":\"tool.zip\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let zip = make_zip_bytes(&[("bin/tool", b"bin"), ("alt/tool", b"bin")]);
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool.zip".to_string(), zip);
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let dest = tempfile::tempdir().expect("temp dir");
+        let summary = download_to_dir("mxcl/tool", dest.path()).expect("download");
+        assert_eq!(summary.paths.len(), 1);
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn download_all_arches_fetches_each_arch_into_subdirs() {
+        let os = os_tokens()[0];
+        let x86 = format!("tool-{os}-x86_64.tar.gz");
+        let arm = format!("tool-{os}-aarch64.tar.gz");
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"{x86}\",\"browser_download_url\":\"{base}/download/x86\"}},{{\"name\":\"{arm}\",\"browser_download_url\":\"{base}/download/arm\"}},{{\"name\":\"tool-plan9-aarch64.tar.gz\",\"browser_download_url\":\"{base}/download/plan9\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert(
+                "/download/x86".to_string(),
+                make_tar_gz_bytes(&[("tool", b"x86")]),
+            );
+            responses.insert(
+                "/download/arm".to_string(),
+                make_tar_gz_bytes(&[("tool", b"arm")]),
+            );
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let dest = tempfile::tempdir().expect("temp dir");
+        let summaries =
+            download_all_arches("mxcl/tool", dest.path(), &Options::default()).expect("download");
+        assert_eq!(summaries.len(), 2);
+        let x86_bin = dest.path().join("x86_64").join("tool");
+        let arm_bin = dest.path().join("aarch64").join("tool");
+        assert_eq!(fs::read(x86_bin).expect("read x86"), b"x86");
+        assert_eq!(fs::read(arm_bin).expect("read arm"), b"arm");
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn download_all_arches_honors_only_os() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool-linux-x86_64\",\"browser_download_url\":\"{base}/download/linux\"}},{{\"name\":\"tool-darwin-x86_64\",\"browser_download_url\":\"{base}/download/darwin\"}},{{\"name\":\"tool-windows-x86_64.exe\",\"browser_download_url\":\"{base}/download/x86\"}},{{\"name\":\"tool-windows-aarch64.exe\",\"browser_download_url\":\"{base}/download/arm\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/x86".to_string(), b"x86".to_vec());
+            responses.insert("/download/arm".to_string(), b"arm".to_vec());
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let dest = tempfile::tempdir().expect("temp dir");
+        let options = Options {
+            only_os: Some("windows".to_string()),
+            ..Options::default()
+        };
+        let summaries = download_all_arches("mxcl/tool", dest.path(), &options).expect("download");
+        let names: Vec<&str> = summaries
+            .iter()
+            .map(|summary| summary.asset_name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["tool-windows-aarch64.exe", "tool-windows-x86_64.exe"]
+        );
+        assert_eq!(
+            fs::read(&summaries[0].primary_path).expect("read arm"),
+            b"arm"
+        );
+        assert_eq!(
+            fs::read(&summaries[1].primary_path).expect("read x86"),
+            b"x86"
+        );
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn download_all_arches_keeps_subdirs_under_templates_without_arch() {
+        let os = os_tokens()[0];
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool-{os}-x86_64\",\"browser_download_url\":\"{base}/download/x86\"}},{{\"name\":\"tool-{os}-aarch64\",\"browser_download_url\":\"{base}/download/arm\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/x86".to_string(), b"x86".to_vec());
+            responses.insert("/download/arm".to_string(), b"arm".to_vec());
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let dest = tempfile::tempdir().expect("temp dir");
+        let template = dest.path().join("{version}");
+        let summaries =
+            download_all_arches("mxcl/tool", &template, &Options::default()).expect("download");
+        assert_eq!(summaries.len(), 2);
+        let version_dir = dest.path().join("1.0.0");
+        assert_eq!(
+            fs::read(version_dir.join("x86_64").join(format!("tool-{os}-x86_64"))).expect("x86"),
+            b"x86"
+        );
+        assert_eq!(
+            fs::read(
+                version_dir
+                    .join("aarch64")
+                    .join(format!("tool-{os}-aarch64"))
+            )
+            .expect("arm"),
+            b"arm"
+        );
+
+        server.finish();
+    }
+
+    #[test]
+    fn pick_asset_honors_arch_override() {
+        let os = os_tokens()[0];
+        let assets = vec![
+            Asset {
+                name: format!("tool-{os}-x86_64.tar.gz"),
+                browser_download_url: "http://example.com/x86".to_string(),
+            },
+            Asset {
+                name: format!("tool-{os}-arm64.tar.gz"),
+                browser_download_url: "http://example.com/arm".to_string(),
+            },
+        ];
+        let options = Options {
+            arch: Some("arm64".to_string()),
+            ..Options::default()
+        };
+        let picked = pick_asset(&assets, "tool", None, &options).expect("pick asset");
+        assert_eq!(picked.browser_download_url, "http://example.com/arm");
+        assert_eq!(asset_arch("tool-linux-arm64.zip"), Some("aarch64"));
+        assert_eq!(asset_arch("tool-linux-armv7.zip"), Some("arm"));
+        assert_eq!(asset_arch("tool.zip"), None);
+        assert_eq!(asset_arch("charm-linux.zip"), None);
+        assert_eq!(asset_arch("tool-x86-64-linux.zip"), Some("x86_64"));
+        assert_eq!(asset_os("winterm-linux-amd64.zip"), Some("linux"));
+        assert_eq!(asset_os("tool-x86_64-apple-darwin.zip"), Some("macos"));
+        assert_eq!(asset_os("tool-x86_64-pc-windows-gnu.zip"), Some("windows"));
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(target_os = "linux")]
+    fn pick_asset_prefers_musl_on_musl_systems() {
+        let asset = |name: &str| Asset {
+            name: name.to_string(),
+            browser_download_url: format!("http://example.com/{name}"),
+        };
+        let arch = arch_tokens()[0];
+        let assets = vec![
+            asset(&format!("tool-{arch}-unknown-linux-gnu.tar.gz")),
+            asset(&format!("tool-{arch}-unknown-linux-musl.tar.gz")),
+        ];
+        let pick = |libc: &str| {
+            let _libc = EnvGuard::set("YOINK_LIBC", libc);
+            let picked = pick_asset(&assets, "tool", None, &Options::default()).expect("pick");
+            picked.name
+        };
+
+        assert_eq!(
+            pick("musl"),
+            format!("tool-{arch}-unknown-linux-musl.tar.gz")
+        );
+        assert_eq!(pick("gnu"), format!("tool-{arch}-unknown-linux-gnu.tar.gz"));
+
+        // These musl builds name no OS, so the glibc ones match as well on everything but libc.
+        let _libc = EnvGuard::set("YOINK_LIBC", "musl");
+        let options = Options {
+            arch: Some("x86_64".to_string()),
+            ..Options::default()
+        };
+        for (musl, gnu) in [
+            (
+                "tool-musl-x86_64.tar.gz",
+                "tool-x86_64-unknown-linux-gnu.tar.gz",
+            ),
+            (
+                "tool-musl-static-x86_64.tar.gz",
+                "tool-x86_64-linux-gnu.tar.gz",
+            ),
+        ] {
+            let assets = vec![asset(gnu), asset(musl)];
+            let picked = pick_asset(&assets, "tool", None, &options).expect("pick");
+            assert_eq!(picked.name, musl);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn pick_asset_applies_asset_preferences() {
+        let _libc = EnvGuard::set("YOINK_LIBC", "gnu");
+        let os = os_tokens()[0];
+        let arch = arch_tokens()[0];
+        let asset = |suffix: &str| Asset {
+            name: format!("tool-{os}-{arch}-{suffix}"),
+            browser_download_url: format!("http://example.com/{suffix}"),
+        };
+        let assets = vec![asset("gnu.zip"), asset("gnu.tar.gz"), asset("musl.tar.gz")];
+        let pick = |prefer: &[&str], avoid: &[&str]| {
+            let options = Options {
+                asset_prefer: prefer.iter().map(|token| token.to_string()).collect(),
+                asset_avoid: avoid.iter().map(|token| token.to_string()).collect(),
+                ..Options::default()
+            };
+            let picked = pick_asset(&assets, "tool", None, &options).expect("pick asset");
+            picked.browser_download_url
+        };
+
+        assert_eq!(pick(&[], &[]), "http://example.com/gnu.zip");
+        assert_eq!(pick(&["tar.gz"], &[]), "http://example.com/gnu.tar.gz");
+        assert_eq!(pick(&["MUSL"], &[]), "http://example.com/musl.tar.gz");
+        assert_eq!(pick(&[], &["gnu"]), "http://example.com/musl.tar.gz");
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(target_os = "linux")]
+    fn asset_preferences_load_from_xdg_config() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let config_dir = temp.path().join("yoink");
+        fs::create_dir_all(&config_dir).expect("mkdir config");
+        fs::write(
+            config_dir.join("config.toml"),
+            "[asset]\nprefer = [\"static\", \"musl\"]\navoid = [\"debug\"]\n",
+        )
+        .expect("write config");
+        let _guard = EnvGuard::set("XDG_CONFIG_HOME", temp.path());
+
+        let options = with_asset_preferences(&Options::default())
+            .expect("load config")
+            .expect("preferences configured");
+        assert_eq!(options.asset_prefer, ["static", "musl"]);
+        assert_eq!(options.asset_avoid, ["debug"]);
+
+        let explicit = Options {
+            asset_avoid: vec!["gnu".to_string()],
+            ..Options::default()
+        };
+        assert!(with_asset_preferences(&explicit).expect("load").is_none());
+    }
+
+    #[test]
+    fn pick_asset_asset_glob_bypasses_scoring() {
+        let asset = |name: &str| Asset {
+            name: name.to_string(),
+            browser_download_url: format!("http://example.com/{name}"),
+        };
+        let assets = vec![
+            asset("tool-1.0-linux-x64.tar.gz"),
+            asset("tool-1.0-linux-x64-musl.tar.gz"),
+            asset("tool-1.0-darwin-arm64.tar.gz"),
+            asset("tool-1.0-linux-x64.tar.gz.sha256"),
+        ];
+        let with_glob = |pattern: &str| Options {
+            asset: Some(pattern.to_string()),
+            only_os: Some("windows".to_string()),
+            ..Options::default()
+        };
+
+        let picked = pick_asset(&assets, "tool", None, &with_glob("tool-*-linux-x64.tar.gz"))
+            .expect("one match");
+        assert_eq!(picked.name, "tool-1.0-linux-x64.tar.gz");
+        let picked = pick_asset(&assets, "tool", None, &with_glob("*darwin*")).expect("one match");
+        assert_eq!(picked.name, "tool-1.0-darwin-arm64.tar.gz");
+
+        match pick_asset(
+            &assets,
+            "tool",
+            None,
+            &with_glob("tool-*-linux-x64*.tar.gz"),
+        ) {
+            Ok(_) => panic!("several assets should match"),
+            Err(err) => assert!(err.to_string().contains("matches several assets"), "{err}"),
+        }
+        match pick_asset(&assets, "tool", None, &with_glob("*.zip")) {
+            Ok(_) => panic!("no asset should match"),
+            Err(err) => assert!(err.to_string().contains("no assets match"), "{err}"),
+        }
+
+        assert!(glob_matches("t?ol-*", "tool-x"));
+        assert!(!glob_matches("tool", "tool-x"));
+        assert!(glob_matches("*a*b*", "xxaybzb"));
+    }
+
+    #[test]
+    fn pick_asset_tag_tokens_match_generic_names() {
+        let assets = vec![Asset {
+            name: "tool.tar.gz".to_string(),
+            browser_download_url: "http://example.com/tool.tar.gz".to_string(),
+        }];
+        let options = Options {
+            only_os: Some("linux".to_string()),
+            ..Options::default()
+        };
+        let tag = Some("v1.0.0-linux-x64");
+        assert!(pick_asset(&assets, "tool", tag, &options).is_err());
+
+        let options = Options {
+            tag_tokens: true,
+            ..options
+        };
+        let picked = pick_asset(&assets, "tool", tag, &options).expect("pick asset");
+        assert_eq!(picked.name, "tool.tar.gz");
+        assert!(pick_asset(&assets, "tool", Some("v1.0.0-windows-x64"), &options).is_err());
+    }
+
+    #[test]
+    fn pick_asset_only_os_and_arch_filter_candidates() {
+        let asset = |name: &str| Asset {
+            name: name.to_string(),
+            browser_download_url: format!("http://example.com/{name}"),
+        };
+        let mac_only = vec![
+            asset("tool-darwin-arm64.tar.gz"),
+            asset("tool-darwin-x86_64.tar.gz"),
+        ];
+        let options = Options {
+            only_os: Some("linux".to_string()),
+            ..Options::default()
+        };
+        let message = match pick_asset(&mac_only, "tool", None, &options) {
+            Ok(picked) => panic!("picked {} despite --only-os linux", picked.name),
+            Err(err) => err.to_string(),
+        };
+        assert!(message.contains("no assets for linux"), "{message}");
+        assert!(message.contains("tool-darwin-arm64.tar.gz"), "{message}");
+
+        let assets = vec![
+            asset("tool-darwin-arm64.tar.gz"),
+            asset("tool-windows-gnu-x86_64.zip"),
+            asset("tool-linux-x86_64.tar.gz"),
+            asset("tool-linux-arm64.tar.gz"),
+        ];
+        let options = Options {
+            only_os: Some("linux".to_string()),
+            only_arch: Some("arm64".to_string()),
+            ..Options::default()
+        };
+        let picked = pick_asset(&assets, "tool", None, &options).expect("pick asset");
+        assert_eq!(picked.name, "tool-linux-arm64.tar.gz");
+
+        let options = Options {
+            only_os: Some("windows".to_string()),
+            ..Options::default()
+        };
+        let picked = pick_asset(&assets, "tool", None, &options).expect("pick asset");
+        assert_eq!(picked.name, "tool-windows-gnu-x86_64.zip");
+    }
+
+    #[test]
+    fn pick_asset_ignore_arch_prefers_generic_asset() {
+        let generic = Asset {
+            name: "tool.jar".to_string(),
+            browser_download_url: "http://example.com/jar".to_string(),
+        };
+        let options = Options {
+            ignore_arch: true,
+            ..Options::default()
+        };
+        let picked =
+            pick_asset(std::slice::from_ref(&generic), "tool", None, &options).expect("pick asset");
+        assert_eq!(picked.browser_download_url, "http://example.com/jar");
+
+        let os = os_tokens()[0];
+        let arch = arch_tokens()[0];
+        let assets = vec![
+            Asset {
+                name: format!("tool-{os}-{arch}.tar.gz"),
+                browser_download_url: "http://example.com/native".to_string(),
+            },
+            generic,
+        ];
+        let picked = pick_asset(&assets, "tool", None, &Options::default()).expect("pick asset");
+        assert_eq!(picked.browser_download_url, "http://example.com/native");
+        let picked = pick_asset(&assets, "tool", None, &options).expect("pick asset");
+        assert_eq!(picked.browser_download_url, "http://example.com/jar");
+    }
+
+    #[test]
+    #[serial]
+    fn install_with_report_records_state() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v4.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        let report = install_with_report("mxcl/tool", &Options::default()).expect("install");
+        assert!(report.path.exists());
+        assert_eq!(report.version, "v4.0.0");
+        assert!(!report.used_sudo);
+        let json = serde_json::to_value(&report).expect("serialize report");
+        assert_eq!(json["used_sudo"], false);
+
+        let installs = list_installs().expect("list installs");
+        assert_eq!(installs.len(), 1);
+
+        server.finish();
     }
 
     #[test]
-    fn token_helpers_include_expected_tokens() {
-        let os = os_tokens();
-        match env::consts::OS {
-            "macos" => assert!(os.contains(&"macos")),
-            "linux" => assert!(os.contains(&"linux")),
-            "windows" => assert!(os.contains(&"windows")),
-            other => assert!(os.contains(&other)),
-        }
+    #[serial]
+    #[cfg(unix)]
+    fn install_with_report_notes_the_sudo_fallback() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v4.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
 
-        let arch = arch_tokens();
-        match env::consts::ARCH {
-            "x86_64" => assert!(arch.contains(&"x86_64")),
-            "aarch64" => assert!(arch.contains(&"aarch64")),
-            "arm" => assert!(arch.contains(&"arm")),
-            other => assert!(arch.contains(&other)),
-        }
+        let (_sudo_dir, _path_guard) = setup_fake_sudo();
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        FORCE_SUDO.store(true, atomic::Ordering::SeqCst);
+        let report = install_with_report("mxcl/tool", &Options::default());
+        FORCE_SUDO.store(false, atomic::Ordering::SeqCst);
+        let report = report.expect("install");
+        assert_eq!(fs::read(&report.path).expect("read"), b"bin");
+        assert!(report.used_sudo);
+        let json = serde_json::to_value(&report).expect("serialize report");
+        assert_eq!(json["used_sudo"], true);
+
+        server.finish();
     }
 
     #[test]
-    fn asset_helpers_prefer_best_match() {
-        let os = os_tokens();
-        let arch = arch_tokens();
-        let best_name = format!("tool-{}-{}.tar.gz", os[0], arch[0]);
-        let assets = vec![
-            Asset {
-                name: "tool.sig".to_string(),
-                browser_download_url: "http://example.com/tool.sig".to_string(),
-            },
-            Asset {
-                name: format!("tool-{}", os[0]),
-                browser_download_url: "http://example.com/tool-os".to_string(),
-            },
-            Asset {
-                name: best_name.clone(),
-                browser_download_url: "http://example.com/tool-best".to_string(),
-            },
+    #[serial]
+    fn install_places_man_pages_by_section() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool.tar.gz");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool.tar.gz\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let tar = make_tar_gz_bytes(&[
+                ("tool", b"bin"),
+                ("share/man/man1/tool.1", b".TH TOOL 1"),
+                ("man/tool.conf.5", b".TH TOOL.CONF 5"),
+                ("doc/notes.5", b"not a man page"),
+            ]);
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool.tar.gz".to_string(), tar);
+            responses
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        let man = home.path().join("man");
+        let options = Options {
+            man_dir: Some(man.clone()),
+            ..Options::default()
+        };
+        install_with_report("mxcl/tool", &options).expect("install");
+        let pages = [
+            man.join("man1").join("tool.1"),
+            man.join("man5").join("tool.conf.5"),
         ];
-        let picked = pick_asset(&assets, "tool").expect("pick asset");
-        assert_eq!(picked.name, best_name);
-        assert!(is_ignored_asset("foo.sha256"));
-        assert!(is_archive_name("foo.tar.gz"));
-        assert!(is_gzip_name("foo.gz"));
-        assert!(!is_gzip_name("foo.tar.gz"));
+        assert_eq!(fs::read(&pages[0]).expect("man1"), b".TH TOOL 1");
+        assert!(pages[1].is_file());
+        assert!(!man.join("man5").join("notes.5").exists());
+        assert!(!bin.path().join("tool.1").exists());
+
+        let state = load_state().expect("state");
+        let mut recorded = state.installs["mxcl/tool"].man_pages.clone();
+        recorded.sort();
+        assert_eq!(recorded, pages);
+
+        uninstall("mxcl/tool").expect("uninstall");
+        assert!(pages.iter().all(|page| !page.exists()));
+
+        assert_eq!(man_section(Path::new("man3/tool.3p.gz")), Some('3'));
+        assert_eq!(man_section(Path::new("share/man/tool.1")), Some('1'));
+        assert_eq!(man_section(Path::new("man5/tool.1")), None);
+        assert_eq!(man_section(Path::new("tool.1")), None);
+        assert_eq!(man_section(Path::new("man/tool-1.2.1")), None);
+        assert_eq!(man_section(Path::new("man/tool")), None);
+
+        server.finish();
     }
 
     #[test]
-    fn pick_asset_errors_on_empty_assets() {
-        assert!(pick_asset(&[], "tool").is_err());
+    #[serial]
+    fn install_skips_man_pages_without_man_dir() {
+        let server = TestServer::new(|base| {
+            let url = format!("{base}/download/tool.tar.gz");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool.tar.gz\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let tar = make_tar_gz_bytes(&[("tool", b"bin"), ("man/man1/tool.1", b".TH TOOL 1")]);
+            BTreeMap::from([
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    body.into_bytes(),
+                ),
+                ("/download/tool.tar.gz".to_string(), tar),
+            ])
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        install_with_report("mxcl/tool", &Options::default()).expect("install");
+        assert!(!home.path().join("man").exists());
+        assert!(!home
+            .path()
+            .join(".local")
+            .join("share")
+            .join("man")
+            .exists());
+        let state = load_state().expect("state");
+        assert!(state.installs["mxcl/tool"].man_pages.is_empty());
+
+        server.finish();
     }
 
     #[test]
-    fn pick_asset_falls_back_to_ignored_assets() {
-        let assets = vec![
-            Asset {
-                name: "tool.sha256".to_string(),
-                browser_download_url: "http://example.com/tool.sha256".to_string(),
-            },
-            Asset {
-                name: "tool.sig".to_string(),
-                browser_download_url: "http://example.com/tool.sig".to_string(),
-            },
-        ];
-        let picked = pick_asset(&assets, "tool").expect("pick asset");
-        assert!(picked.name.ends_with(".sha256") || picked.name.ends_with(".sig"));
+    #[serial]
+    fn ensure_is_noop_when_version_satisfied() {
+        let home = tempfile::tempdir().expect("temp dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", "http://127.0.0.1:9");
+        let bin = home.path().join("tool");
+        fs::write(&bin, b"bin").expect("write bin");
+        record_install(
+            "mxcl/tool",
+            "v1.2.3",
+            std::slice::from_ref(&bin),
+            true,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+
+        let report = ensure("mxcl/tool@1.2.3", &Options::default()).expect("ensure");
+        assert_eq!(report.action, EnsureAction::Unchanged);
+        assert_eq!(report.install.path, bin);
+        // A prefix pin is satisfied by any release it prefixes.
+        let report = ensure("mxcl/tool@1.2", &Options::default()).expect("prefix ensure");
+        assert_eq!(report.action, EnsureAction::Unchanged);
+        let report = install_with_report("mxcl/tool@v1.2", &Options::default()).expect("install");
+        assert_eq!(report.version, "v1.2.3");
+
+        match ensure("mxcl/tool", &Options::default()) {
+            Ok(_) => panic!("ensure without a version should fail"),
+            Err(err) => assert!(err.to_string().contains("needs a version"), "{err}"),
+        }
     }
 
     #[test]
-    fn pick_asset_prefers_repo_stem() {
-        let os = os_tokens();
-        let arch = arch_tokens();
-        let name = format!("bun-{}-{}.zip", os[0], arch[0]);
-        let profile = format!("bun-profile-{}-{}.zip", os[0], arch[0]);
-        let assets = vec![
-            Asset {
-                name: profile.clone(),
-                browser_download_url: "http://example.com/bun-profile".to_string(),
-            },
-            Asset {
-                name: name.clone(),
-                browser_download_url: "http://example.com/bun".to_string(),
-            },
-        ];
-        let picked = pick_asset(&assets, "bun").expect("pick asset");
-        assert_eq!(picked.name, name);
+    #[serial]
+    fn ensure_installs_then_replaces_mismatched_version() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            for tag in ["v1.0.0", "v1.2.3"] {
+                let body = format!(
+                    "{{\"tag_name\":\"{tag}\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/{tag}/tool\"}}]}}"
+                );
+                responses.insert(
+                    format!("/repos/mxcl/tool/releases/tags/{tag}"),
+                    body.into_bytes(),
+                );
+                responses.insert(format!("/download/{tag}/tool"), tag.as_bytes().to_vec());
+            }
+            responses
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        let report = ensure("mxcl/tool@v1.0.0", &Options::default()).expect("first ensure");
+        assert_eq!(report.action, EnsureAction::Installed);
+        assert_eq!(fs::read(&report.install.path).expect("read bin"), b"v1.0.0");
+
+        let report = ensure("mxcl/tool@v1.2.3", &Options::default()).expect("second ensure");
+        assert_eq!(
+            report.action,
+            EnsureAction::Replaced {
+                from: "v1.0.0".to_string()
+            }
+        );
+        assert_eq!(fs::read(&report.install.path).expect("read bin"), b"v1.2.3");
+        let installs = list_installs().expect("list installs");
+        assert_eq!(installs[0].version, "1.2.3");
+
+        server.finish();
     }
 
     #[test]
-    fn asset_score_counts_exe() {
-        assert_eq!(asset_score("tool.exe", &[], &[]), 1);
+    #[serial]
+    fn concurrent_installs_into_one_dir_do_not_corrupt() {
+        let bodies = [vec![b'a'; 256 * 1024], vec![b'b'; 256 * 1024]];
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            for (repo, body) in ["a", "b"].into_iter().zip(&bodies) {
+                let release = format!(
+                    "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/{repo}/tool\"}}]}}"
+                );
+                responses.insert(
+                    format!("/repos/mxcl/{repo}/releases/latest"),
+                    release.into_bytes(),
+                );
+                responses.insert(format!("/download/{repo}/tool"), body.clone());
+            }
+            responses
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        let handles: Vec<_> = ["mxcl/a", "mxcl/b"]
+            .into_iter()
+            .map(|repo| thread::spawn(move || install(repo)))
+            .collect();
+        for handle in handles {
+            handle.join().expect("install thread").expect("install");
+        }
+        server.finish();
+
+        let installed = fs::read(bin.path().join("tool")).expect("read tool");
+        assert!(bodies.contains(&installed));
+        let state = load_state().expect("load state");
+        assert_eq!(
+            state.installs.keys().collect::<Vec<_>>(),
+            ["mxcl/a", "mxcl/b"]
+        );
+        assert_eq!(fs::read_dir(bin.path()).expect("read bin dir").count(), 1);
     }
 
     #[test]
-    fn find_binaries_prefers_shortest_exact() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let root = temp.path();
-        fs::create_dir_all(root.join("bin")).expect("mkdir");
-        fs::create_dir_all(root.join("docs")).expect("mkdir docs");
-        fs::write(root.join("tool"), b"bin").expect("write tool");
-        fs::write(root.join("bin").join("tool"), b"bin").expect("write tool bin");
-        fs::write(root.join("bin").join("helper"), b"bin").expect("write helper");
-        fs::write(root.join("docs").join("readme.md"), b"doc").expect("write doc");
+    #[serial]
+    fn install_waits_for_the_install_dir_lock() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let release = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                release.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
 
-        let (primary, extras) = find_binaries(root, "tool").expect("find binaries");
-        assert_eq!(primary, root.join("tool"));
-        assert!(extras.contains(&root.join("bin").join("tool")));
-        assert!(extras.contains(&root.join("bin").join("helper")));
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        let held = lock_install_dir(bin.path()).expect("hold lock");
+        let handle = thread::spawn(|| install("mxcl/tool"));
+        // Once the server has sent everything, only the lock stands between it and the dir.
+        server.finish();
+        thread::sleep(Duration::from_millis(200));
+        assert!(!handle.is_finished());
+        assert!(!bin.path().join("tool").exists());
+
+        drop(held);
+        handle.join().expect("install thread").expect("install");
+        assert_eq!(
+            fs::read(bin.path().join("tool")).expect("read tool"),
+            b"bin"
+        );
+        assert!(home.path().join("yoink/locks").is_dir());
     }
 
     #[test]
-    fn find_binaries_prefers_bin_when_no_exact() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let root = temp.path();
-        fs::create_dir_all(root.join("bin")).expect("mkdir");
-        fs::create_dir_all(root.join("alt")).expect("mkdir");
-        fs::write(root.join("bin").join("run"), b"bin").expect("write run");
-        fs::write(root.join("alt").join("tool"), b"bin").expect("write tool");
+    #[serial]
+    fn rollback_reinstalls_previous_version() {
+        let server = TestServer::new(|base| {
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/v1.0.0/tool\"}}]}}"
+            );
+            BTreeMap::from([
+                (
+                    "/repos/mxcl/tool/releases/tags/v1.0.0".to_string(),
+                    body.into_bytes(),
+                ),
+                ("/download/v1.0.0/tool".to_string(), b"v1.0.0".to_vec()),
+            ])
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        let tool = bin.path().join("tool");
+        fs::write(&tool, b"v2.0.0").expect("write bin");
+        for tag in ["v1.0.0", "v2.0.0"] {
+            record_install(
+                "mxcl/tool",
+                tag,
+                std::slice::from_ref(&tool),
+                false,
+                &InstallMeta::default(),
+            )
+            .expect("record install");
+        }
+        let entry = load_state()
+            .expect("load state")
+            .installs
+            .remove("mxcl/tool")
+            .expect("entry");
+        assert_eq!(entry.history, ["v1.0.0"]);
+
+        let report = rollback("mxcl/tool", &Options::default()).expect("rollback");
+        assert_eq!(report.version, "v1.0.0");
+        assert_eq!(fs::read(&tool).expect("read bin"), b"v1.0.0");
+        let entry = load_state()
+            .expect("load state")
+            .installs
+            .remove("mxcl/tool")
+            .expect("entry");
+        assert!(entry.pinned);
+        assert!(entry.history.is_empty());
+
+        match rollback("mxcl/tool", &Options::default()) {
+            Ok(_) => panic!("rollback without history should fail"),
+            Err(err) => assert!(err.to_string().contains("no earlier version"), "{err}"),
+        }
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn store_install_links_versions_and_uninstall_prunes_them() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            for tag in ["v1.0.0", "v2.0.0"] {
+                let body = format!(
+                    "{{\"tag_name\":\"{tag}\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/{tag}/tool\"}}]}}"
+                );
+                responses.insert(
+                    format!("/repos/mxcl/tool/releases/tags/{tag}"),
+                    body.into_bytes(),
+                );
+                responses.insert(format!("/download/{tag}/tool"), tag.as_bytes().to_vec());
+            }
+            responses
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            store: true,
+            ..Options::default()
+        };
 
-        let (primary, _extras) = find_binaries(root, "yoink").expect("find binaries");
-        assert_eq!(primary, root.join("bin").join("run"));
-    }
+        let first = install_with_report("mxcl/tool@v1.0.0", &options).expect("install v1");
+        let second = install_with_report("mxcl/tool@v2.0.0", &options).expect("install v2");
+        assert_eq!(first.path, second.path);
+        let store = home
+            .path()
+            .join("yoink")
+            .join("store")
+            .join("mxcl")
+            .join("tool");
+        assert!(second.path.is_symlink());
+        assert_eq!(
+            fs::read_link(&second.path).expect("read link"),
+            store.join("v2.0.0").join("tool")
+        );
+        assert_eq!(fs::read(&second.path).expect("read bin"), b"v2.0.0");
+        assert_eq!(
+            fs::read(store.join("v1.0.0").join("tool")).expect("old version kept"),
+            b"v1.0.0"
+        );
+        let state = load_state().expect("load state");
+        assert_eq!(
+            state.installs["mxcl/tool"].store_path.as_deref(),
+            Some(store.join("v2.0.0").as_path())
+        );
 
-    #[test]
-    fn find_binaries_prefers_shortest_probable() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let root = temp.path();
-        fs::create_dir_all(root.join("a")).expect("mkdir");
-        fs::create_dir_all(root.join("longer").join("path")).expect("mkdir");
-        fs::write(root.join("a").join("run"), b"bin").expect("write run");
-        fs::write(root.join("longer").join("path").join("tool"), b"bin").expect("write tool");
+        uninstall("mxcl/tool").expect("uninstall");
+        assert!(fs::symlink_metadata(&second.path).is_err());
+        assert!(!store.exists());
 
-        let (primary, _extras) = find_binaries(root, "yoink").expect("find binaries");
-        assert_eq!(primary, root.join("a").join("run"));
+        server.finish();
     }
 
     #[test]
-    fn find_binaries_single_candidate() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let root = temp.path();
-        fs::write(root.join("only"), b"bin").expect("write file");
-        let (primary, extras) = find_binaries(root, "yoink").expect("find binaries");
-        assert_eq!(primary, root.join("only"));
-        assert!(extras.is_empty());
-    }
+    #[serial]
+    #[cfg(unix)]
+    fn rollback_relinks_store_install_without_downloading() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            for tag in ["v1.0.0", "v2.0.0"] {
+                let body = format!(
+                    "{{\"tag_name\":\"{tag}\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/{tag}/tool\"}}]}}"
+                );
+                responses.insert(
+                    format!("/repos/mxcl/tool/releases/tags/{tag}"),
+                    body.into_bytes(),
+                );
+                responses.insert(format!("/download/{tag}/tool"), tag.as_bytes().to_vec());
+            }
+            responses
+        });
 
-    #[test]
-    fn find_binaries_falls_back_to_single_candidate() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let root = temp.path();
-        fs::write(root.join("notes.txt"), b"doc").expect("write file");
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            store: true,
+            ..Options::default()
+        };
+        install_with_report("mxcl/tool@v1.0.0", &options).expect("install v1");
+        install_with_report("mxcl/tool@v2.0.0", &options).expect("install v2");
+        server.finish();
 
-        let (primary, _extras) = find_binaries(root, "yoink").expect("find binaries");
-        assert_eq!(primary, root.join("notes.txt"));
+        // Every release was served once already; a download now would find nothing.
+        let report = rollback("mxcl/tool", &Options::default()).expect("rollback");
+        assert_eq!(report.version, "v1.0.0");
+        let store = home
+            .path()
+            .join("yoink")
+            .join("store")
+            .join("mxcl")
+            .join("tool");
+        assert_eq!(
+            fs::read_link(&report.path).expect("read link"),
+            store.join("v1.0.0").join("tool")
+        );
+        let state = load_state().expect("load state");
+        let entry = &state.installs["mxcl/tool"];
+        assert_eq!(entry.version, "v1.0.0");
+        assert_eq!(
+            entry.store_path.as_deref(),
+            Some(store.join("v1.0.0").as_path())
+        );
     }
 
     #[test]
-    fn find_binaries_errors_without_candidates() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let root = temp.path();
-        assert!(find_binaries(root, "yoink").is_err());
-    }
+    #[serial]
+    fn install_with_report_skips_duplicate_extras() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool.zip");
+            let body = format!(
+                "{{\"tag_name\":\"v4.1.0\",\"assets\":[{{\"name\":\"tool.zip\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let zip = make_zip_bytes(&[("bin/tool", b"bin"), ("alt/tool", b"bin")]);
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool.zip".to_string(), zip);
+            responses
+        });
 
-    #[test]
-    fn probable_binary_filters_docs_and_extensions() {
-        assert!(!is_probable_binary_candidate(Path::new("README.md")));
-        assert!(!is_probable_binary_candidate(Path::new("docs/tool")));
-        assert!(!is_probable_binary_candidate(Path::new("share/tool")));
-        assert!(is_probable_binary_candidate(Path::new("bin/tool")));
-    }
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
 
-    #[test]
-    fn probable_binary_handles_missing_filename() {
-        assert!(!is_probable_binary_candidate(Path::new("")));
-    }
+        let report = install_with_report("mxcl/tool", &Options::default()).expect("install");
+        assert!(report.path.exists());
 
-    #[test]
-    fn probable_binary_filters_extensions() {
-        assert!(!is_probable_binary_candidate(Path::new("notes.json")));
+        server.finish();
     }
 
     #[test]
-    fn path_has_component_handles_case() {
-        let path = Path::new("Foo").join("Bin").join("tool");
-        let path = path.as_path();
-        assert!(path_has_component(path, "bin"));
-        assert!(!path_has_component(path, "share"));
-    }
+    #[serial]
+    fn install_with_report_applies_rename_map() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/uv.zip");
+            let body = format!(
+                "{{\"tag_name\":\"v0.5.0\",\"assets\":[{{\"name\":\"uv.zip\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let zip = make_zip_bytes(&[("uv", b"uv"), ("uvx", b"uvx")]);
+            responses.insert(
+                "/repos/astral-sh/uv/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/uv.zip".to_string(), zip);
+            responses
+        });
 
-    #[test]
-    fn install_binary_copies_and_sets_mode() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let src = temp.path().join("src");
-        let dest = temp.path().join("dest");
-        fs::write(&src, b"hello").expect("write");
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
 
-        install_binary(&src, &dest).expect("install binary");
-        let contents = fs::read(&dest).expect("read dest");
-        assert_eq!(contents, b"hello");
+        let options = Options {
+            rename_map: parse_rename_map("uv=uv-pinned, uvx=uvx-pinned").expect("rename map"),
+            ..Options::default()
+        };
+        let report = install_with_report("astral-sh/uv", &options).expect("install");
+        assert_eq!(report.path, bin.path().join("uv-pinned"));
+        assert!(bin.path().join("uvx-pinned").exists());
+        assert!(!bin.path().join("uv").exists());
 
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mode = fs::metadata(&dest).expect("stat").permissions().mode();
-            assert!(mode & 0o111 != 0);
-        }
-    }
+        let state = load_state().expect("load state");
+        let entry = &state.installs["astral-sh/uv"];
+        assert_eq!(entry.bin, bin.path().join("uv-pinned"));
+        assert_eq!(entry.bins, [bin.path().join("uvx-pinned")]);
 
-    #[test]
-    fn install_payload_handles_normal_copy() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let src = temp.path().join("src");
-        let dest = temp.path().join("dest");
-        fs::write(&src, b"hello").expect("write");
-        install_payload(&src, &dest).expect("install payload");
-        assert!(dest.exists());
+        server.finish();
     }
 
     #[test]
-    fn install_payload_errors_on_missing_dest_parent() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let src = temp.path().join("src");
-        let dest = temp.path().join("missing").join("dest");
-        fs::write(&src, b"hello").expect("write");
-        assert!(install_payload(&src, &dest).is_err());
+    fn parse_rename_map_rejects_bad_pairs() {
+        let map = parse_rename_map("a=b").expect("rename map");
+        assert_eq!(map["a"], "b");
+        assert!(parse_rename_map("a").is_err());
+        assert!(parse_rename_map("a=").is_err());
+        assert!(parse_rename_map("a=../b").is_err());
+        assert_eq!(
+            renamed(
+                OsStr::new("a.exe"),
+                &Options {
+                    rename_map: map,
+                    ..Options::default()
+                }
+            ),
+            OsString::from("b.exe")
+        );
     }
 
     #[test]
     #[serial]
-    #[cfg(unix)]
-    fn install_payload_retries_with_sudo_on_permission_denied() {
-        let (_sudo_dir, _path_guard) = setup_fake_sudo();
+    fn emit_manifest_records_downloads() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v2.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
+
         let temp = tempfile::tempdir().expect("temp dir");
-        let src = temp.path().join("src");
-        let dest = temp.path().join("dest");
-        fs::write(&src, b"hello").expect("write");
-        fs::write(&dest, b"old").expect("write dest");
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let summary = download_to_dir("mxcl/tool", temp.path()).expect("download");
+        server.finish();
 
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&dest).expect("stat").permissions();
-            perms.set_mode(0o444);
-            fs::set_permissions(&dest, perms).expect("chmod");
-        }
+        let json_path = temp.path().join("manifest.json");
+        emit_manifest(&json_path, std::slice::from_ref(&summary)).expect("emit json");
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&json_path).expect("read json"))
+                .expect("parse json");
+        let tool = &json["tool"][0];
+        assert_eq!(tool["repo"], "mxcl/tool");
+        assert_eq!(tool["version"], "v2.0.0");
+        assert_eq!(
+            tool["bin"][0]["path"],
+            temp.path().join("tool").display().to_string()
+        );
+        assert_eq!(
+            tool["bin"][0]["sha256"],
+            sha256_file(&summary.primary_path).unwrap()
+        );
 
-        install_payload(&src, &dest).expect("install payload");
-        assert_eq!(fs::read(&dest).expect("read"), b"hello");
+        let toml_path = temp.path().join("manifest.toml");
+        emit_manifest(&toml_path, std::slice::from_ref(&summary)).expect("emit toml");
+        assert_eq!(
+            read_repo_file(&toml_path).expect("read back"),
+            ["mxcl/tool"]
+        );
+
+        let sha512_path = temp.path().join("manifest-sha512.json");
+        let summary = DownloadSummary {
+            digest_algo: "sha512".parse().expect("parse algo"),
+            ..summary
+        };
+        emit_manifest(&sha512_path, &[summary]).expect("emit sha512 json");
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&sha512_path).expect("read json"))
+                .expect("parse json");
+        let bin = &json["tool"][0]["bin"][0];
+        let digest = bin["sha512"].as_str().expect("sha512 digest");
+        assert_eq!(digest.len(), 128);
+        assert!(digest.chars().all(|ch| ch.is_ascii_hexdigit()));
+        assert!(bin.get("sha256").is_none());
     }
 
     #[test]
     #[serial]
-    #[cfg(unix)]
-    fn install_with_sudo_uses_fake_sudo() {
-        let (_sudo_dir, _path_guard) = setup_fake_sudo();
-        let temp = tempfile::tempdir().expect("temp dir");
-        let src = temp.path().join("src");
-        let dest = temp.path().join("dest");
-        fs::write(&src, b"hello").expect("write");
+    fn semver_latest_picks_highest_release() {
+        let server = TestServer::new(|_base| {
+            let mut responses = BTreeMap::new();
+            let body = r#"[{"tag_name":"v1.0.0","assets":[]},{"tag_name":"v2.0.0-rc.1","prerelease":true,"assets":[]},{"tag_name":"v1.1.0","assets":[{"name":"tool","browser_download_url":"http://example.invalid/tool"}]},{"tag_name":"nightly","assets":[]}]"#;
+            responses.insert(
+                "/repos/mxcl/tool/releases?per_page=100".to_string(),
+                body.as_bytes().to_vec(),
+            );
+            responses
+        });
 
-        install_with_sudo(&src, &dest).expect("install with sudo");
-        assert_eq!(fs::read(&dest).expect("read"), b"hello");
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            semver_latest: true,
+            ..Options::default()
+        };
+        let info = release_info_with("mxcl/tool", &options).expect("release info");
+        assert_eq!(info.tag, "v1.1.0");
+
+        server.finish();
     }
 
     #[test]
     #[serial]
-    #[cfg(unix)]
-    fn ensure_install_dir_uses_sudo_on_permission_denied() {
-        let (_sudo_dir, _path_guard) = setup_fake_sudo();
-        let temp = tempfile::tempdir().expect("temp dir");
-        let protected = temp.path().join("protected");
-        fs::create_dir_all(&protected).expect("mkdir");
-
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&protected).expect("stat").permissions();
-            perms.set_mode(0o500);
-            fs::set_permissions(&protected, perms).expect("chmod");
-        }
+    fn pre_picks_newest_release_including_prereleases() {
+        let server = TestServer::new(|_base| {
+            let mut responses = BTreeMap::new();
+            let asset = r#"[{"name":"tool","browser_download_url":"http://example.invalid/tool"}]"#;
+            let body = format!(
+                r#"[{{"tag_name":"v3.0.0-draft","draft":true,"assets":{asset}}},{{"tag_name":"v2.0.0-rc.1","prerelease":true,"published_at":"2024-05-02T10:00:00Z","assets":{asset}}},{{"tag_name":"v1.0.0","published_at":"2024-05-01T10:00:00Z","assets":{asset}}}]"#
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases?per_page=100".to_string(),
+                body.into_bytes(),
+            );
+            responses
+        });
 
-        let install_dir = protected.join("bin");
-        ensure_install_dir(&install_dir).expect("ensure install dir");
-        assert!(install_dir.exists());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            pre: true,
+            ..Options::default()
+        };
+        let info = release_info_with("mxcl/tool", &options).expect("release info");
+        assert_eq!(info.tag, "v2.0.0-rc.1");
 
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&protected).expect("stat").permissions();
-            perms.set_mode(0o700);
-            fs::set_permissions(&protected, perms).expect("chmod");
-        }
+        server.finish();
     }
 
     #[test]
-    fn is_permission_denied_detects_nested_error() {
-        let err = anyhow::Error::new(io::Error::new(io::ErrorKind::PermissionDenied, "nope"));
-        assert!(is_permission_denied(&err));
+    fn place_binary_applies_dest_exists_policy() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let payload = temp.path().join("payload");
+        fs::write(&payload, b"new").expect("write payload");
+        let dest = temp.path().join("tool");
+        let reset = || fs::write(&dest, b"old").expect("write dest");
 
-        let err = anyhow::Error::new(io::Error::new(io::ErrorKind::Other, "nope"));
-        assert!(!is_permission_denied(&err));
-    }
+        reset();
+        let err = place_binary(&payload, &dest, DestExists::Error, true).expect_err("error policy");
+        assert!(err.to_string().contains("already exists"));
+        assert_eq!(fs::read(&dest).unwrap(), b"old");
 
-    #[test]
-    #[cfg(unix)]
-    fn exit_status_code_reads_code() {
-        use std::os::unix::process::ExitStatusExt;
-        let status = ExitStatus::from_raw(9);
-        assert_eq!(exit_status_code(status), 137);
+        place_binary(&payload, &dest, DestExists::Skip, true).expect("skip");
+        assert_eq!(fs::read(&dest).unwrap(), b"old");
 
-        let status = Command::new("sh")
-            .arg("-c")
-            .arg("exit 42")
-            .status()
-            .expect("run");
-        assert_eq!(exit_status_code(status), 42);
-    }
+        place_binary(&payload, &dest, DestExists::Overwrite, true).expect("overwrite");
+        assert_eq!(fs::read(&dest).unwrap(), b"new");
 
-    #[test]
-    #[serial]
-    fn default_install_dir_prefers_env_vars() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let _guard = EnvGuard::set("YOINKDIR", temp.path());
-        let dir = default_install_dir().expect("default install dir");
-        assert_eq!(dir, temp.path());
-    }
+        reset();
+        place_binary(&payload, &dest, DestExists::Backup, true).expect("backup");
+        assert_eq!(fs::read(&dest).unwrap(), b"new");
+        assert_eq!(fs::read(temp.path().join("tool.bak")).unwrap(), b"old");
 
-    #[test]
-    #[serial]
-    fn default_install_dir_uses_bin_env_when_set() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let _guard = EnvGuard::set("YOINK_BIN_DIR", temp.path());
-        env::remove_var("YOINKDIR");
-        let dir = default_install_dir().expect("default install dir");
-        assert_eq!(dir, temp.path());
+        reset();
+        place_binary(&payload, &dest, DestExists::Backup, true).expect("second backup");
+        let backups = fs::read_dir(temp.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| name.starts_with("tool.") && name.ends_with(".bak"))
+            .count();
+        assert_eq!(backups, 2);
+
+        let fresh = temp.path().join("fresh");
+        place_binary(&payload, &fresh, DestExists::Error, true).expect("missing dest");
+        assert_eq!(fs::read(&fresh).unwrap(), b"new");
     }
 
     #[test]
     #[serial]
-    fn ensure_install_dir_creates_path() {
+    fn public_errors_are_classified() {
+        let server = TestServer::sequence(|base| {
+            let release = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool-windows.tar.gz\",\"browser_download_url\":\"{base}/download/tool-windows.tar.gz\"}}]}}"
+            );
+            let latest = "/repos/mxcl/tool/releases/latest".to_string();
+            vec![
+                (latest.clone(), 404, b"{}".to_vec()),
+                (latest.clone(), 200, release.clone().into_bytes()),
+                (latest, 200, release.into_bytes()),
+                (
+                    "/download/tool-windows.tar.gz".to_string(),
+                    200,
+                    b"not a tarball".to_vec(),
+                ),
+            ]
+        });
+
         let temp = tempfile::tempdir().expect("temp dir");
-        let install_dir = temp.path().join("bin");
-        ensure_install_dir(&install_dir).expect("ensure install dir");
-        assert!(install_dir.exists());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let linux_only = Options {
+            only_os: Some("linux".to_string()),
+            ..Options::default()
+        };
+
+        match release_info("mxcl/tool") {
+            Err(YoinkError::NotFound(_)) => {}
+            other => panic!("expected NotFound, got {:?}", other.map(|info| info.tag)),
+        }
+        match release_info_with("mxcl/tool", &linux_only) {
+            Err(YoinkError::NoAsset(err)) => {
+                assert!(err.to_string().contains("no assets for linux"), "{err}")
+            }
+            other => panic!("expected NoAsset, got {:?}", other.map(|info| info.tag)),
+        }
+        match download_to_dir("mxcl/tool", temp.path()) {
+            Err(YoinkError::Extract(_)) => {}
+            other => panic!(
+                "expected Extract, got {:?}",
+                other.map(|summary| summary.tag)
+            ),
+        }
+        server.finish();
     }
 
     #[test]
     #[serial]
-    fn state_loads_empty_when_missing() {
+    fn rename_conflict_policy_keeps_both_downloads() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            for owner in ["alice", "bob"] {
+                let release = format!(
+                    "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/{owner}/tool\"}}]}}"
+                );
+                responses.insert(
+                    format!("/repos/{owner}/tool/releases/latest"),
+                    release.into_bytes(),
+                );
+                responses.insert(format!("/download/{owner}/tool"), owner.as_bytes().to_vec());
+            }
+            responses
+        });
+
         let temp = tempfile::tempdir().expect("temp dir");
-        let _home = EnvGuard::set("HOME", temp.path());
-        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            dest_exists: "rename".parse().expect("parse policy"),
+            ..Options::default()
+        };
+        let first = download_to_dir_with("alice/tool", temp.path(), &options).expect("first");
+        let second = download_to_dir_with("bob/tool", temp.path(), &options).expect("second");
+        server.finish();
 
-        let state = load_state().expect("load state");
-        assert!(state.installs.is_empty());
+        assert_eq!(first.primary_path, temp.path().join("tool"));
+        assert_eq!(second.primary_path, temp.path().join("tool-bob"));
+        assert_eq!(fs::read(&first.primary_path).expect("read"), b"alice");
+        assert_eq!(fs::read(&second.primary_path).expect("read"), b"bob");
     }
 
     #[test]
     #[serial]
-    fn record_and_remove_install_updates_state() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let _home = EnvGuard::set("HOME", temp.path());
-        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
-
-        let bin = temp.path().join("bin").join("yoink");
-        let extra = temp.path().join("bin").join("helper");
-        fs::create_dir_all(bin.parent().expect("bin parent")).expect("mkdir");
-        fs::write(&bin, b"bin").expect("write bin");
-        fs::write(&extra, b"bin").expect("write extra");
-
-        record_install("mxcl/yoink", "v1.2.3", &[bin.clone(), extra.clone()])
-            .expect("record install");
+    fn rename_conflict_policy_replaces_own_files_only() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            for (owner, tag) in [
+                ("alice", "v1.0.0"),
+                ("alice", "v2.0.0"),
+                ("carol", "v1.0.0"),
+            ] {
+                let release = format!(
+                    "{{\"tag_name\":\"{tag}\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/{owner}/{tag}/tool\"}}]}}"
+                );
+                responses.insert(
+                    format!("/repos/{owner}/tool/releases/tags/{tag}"),
+                    release.into_bytes(),
+                );
+                responses.insert(
+                    format!("/download/{owner}/{tag}/tool"),
+                    format!("{owner} {tag}").into_bytes(),
+                );
+            }
+            responses
+        });
 
-        let installs = list_installs().expect("list installs");
-        assert_eq!(installs.len(), 1);
-        assert_eq!(installs[0].version, "1.2.3");
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            dest_exists: "rename".parse().expect("parse policy"),
+            ..Options::default()
+        };
+        download_to_dir_with("alice/tool@v1.0.0", temp.path(), &options).expect("v1");
+        let upgraded =
+            download_to_dir_with("alice/tool@v2.0.0", temp.path(), &options).expect("v2");
+        assert_eq!(upgraded.primary_path, temp.path().join("tool"));
+        assert_eq!(
+            fs::read(&upgraded.primary_path).expect("read"),
+            b"alice v2.0.0"
+        );
+        assert!(!temp.path().join("tool-alice").exists());
+
+        let squatter = temp.path().join("tool-carol");
+        fs::write(&squatter, b"someone else").expect("write squatter");
+        let err = download_to_dir_with("carol/tool@v1.0.0", temp.path(), &options)
+            .expect_err("both names taken");
+        assert!(err.to_string().contains("other repos"), "{err}");
+        assert_eq!(fs::read(&squatter).expect("read"), b"someone else");
+        assert_eq!(
+            fs::read(temp.path().join("tool")).expect("read"),
+            b"alice v2.0.0"
+        );
 
-        remove_install("mxcl/yoink").expect("remove install");
-        assert!(!bin.exists());
-        assert!(!extra.exists());
+        server.finish();
     }
 
     #[test]
     #[serial]
-    fn remove_install_errors_when_missing() {
+    fn dry_run_resolves_without_downloading() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool-linux.tar.gz");
+            let body = format!(
+                "{{\"tag_name\":\"v3.0.0\",\"assets\":[{{\"name\":\"tool-linux.tar.gz\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses
+        });
+
         let temp = tempfile::tempdir().expect("temp dir");
-        let _home = EnvGuard::set("HOME", temp.path());
-        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
-        assert!(remove_install("mxcl/yoink").is_err());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let plan = dry_run("mxcl/tool", temp.path(), &Options::default()).expect("dry run");
+        assert_eq!(plan.info.tag, "v3.0.0");
+        assert_eq!(plan.info.asset_name, "tool-linux.tar.gz");
+        assert_eq!(plan.destination, temp.path().join(binary_name("tool")));
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+
+        server.finish();
     }
 
     #[test]
     #[serial]
-    fn remove_install_handles_directories() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let _home = EnvGuard::set("HOME", temp.path());
-        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+    fn dry_run_names_the_file_the_real_run_writes() {
+        let server = TestServer::sequence(|base| {
+            let release = |asset: &str| {
+                format!(
+                    "{{\"tag_name\":\"v3.0.0\",\"assets\":[{{\"name\":\"{asset}\",\"browser_download_url\":\"{base}/download/{asset}\"}}]}}"
+                )
+                .into_bytes()
+            };
+            let latest = "/repos/mxcl/tool/releases/latest".to_string();
+            vec![
+                (latest.clone(), 200, release("tool-linux.tar.gz")),
+                (latest, 200, release("TOOL.GZ")),
+            ]
+        });
 
-        let bin_dir = temp.path().join("bin_dir");
-        fs::create_dir_all(&bin_dir).expect("mkdir");
-        record_install("mxcl/yoink", "v1.0.0", &[bin_dir.clone()]).expect("record install");
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            bin: Some("helper".to_string()),
+            ..Options::default()
+        };
+        let plan = dry_run("mxcl/tool", temp.path(), &options).expect("dry run");
+        assert_eq!(plan.destination, temp.path().join(binary_name("helper")));
+        let plan = dry_run("mxcl/tool", temp.path(), &Options::default()).expect("dry run");
+        assert_eq!(plan.destination, temp.path().join("TOOL"));
 
-        assert!(remove_install("mxcl/yoink").is_ok());
-        assert!(!bin_dir.exists());
+        server.finish();
     }
 
     #[test]
-    fn read_state_defaults_on_empty_file() {
-        let mut file = tempfile::tempfile().expect("temp file");
-        let state = read_state_locked(&mut file).expect("read state");
-        assert!(state.installs.is_empty());
-    }
+    #[serial]
+    fn download_since_tag_fetches_each_newer_release() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let release = |tag: &str| {
+                format!(
+                    "{{\"tag_name\":\"{tag}\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/{tag}/tool\"}}]}}"
+                )
+            };
+            let body = format!(
+                "[{},{},{}]",
+                release("v1.2.0"),
+                release("v1.0.0"),
+                release("v1.1.0")
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases?per_page=100".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/v1.1.0/tool".to_string(), b"one-one".to_vec());
+            responses.insert("/download/v1.2.0/tool".to_string(), b"one-two".to_vec());
+            responses
+        });
 
-    #[test]
-    fn extract_zip_archive() {
         let temp = tempfile::tempdir().expect("temp dir");
-        let archive = temp.path().join("tool.zip");
-        write_zip(&archive, &[("tool", b"bin"), ("README.md", b"doc")]);
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let summaries = download_since_tag("mxcl/tool", "v1.0.0", temp.path(), &Options::default())
+            .expect("download since tag");
+        let tags: Vec<&str> = summaries
+            .iter()
+            .map(|summary| summary.tag.as_str())
+            .collect();
+        assert_eq!(tags, ["v1.1.0", "v1.2.0"]);
+        assert_eq!(
+            fs::read(temp.path().join("v1.1.0").join("tool")).expect("read v1.1.0"),
+            b"one-one"
+        );
+        assert_eq!(
+            fs::read(temp.path().join("v1.2.0").join("tool")).expect("read v1.2.0"),
+            b"one-two"
+        );
+        assert!(!temp.path().join("v1.0.0").exists());
 
-        let extracted = extract_archive(&archive, "tool").expect("extract zip");
-        assert!(extracted.primary.ends_with("tool"));
-        assert!(extracted.primary.exists());
+        server.finish();
     }
 
     #[test]
-    fn extract_zip_directory_entries() {
+    #[serial]
+    fn batch_downloads_apply_configured_asset_preferences() {
+        let os = os_tokens()[0];
+        let server = TestServer::sequence(|base| {
+            let asset = |name: &str| {
+                format!(
+                    "{{\"name\":\"{name}\",\"browser_download_url\":\"{base}/download/{name}\"}}"
+                )
+            };
+            let assets = ["x86_64", "x86_64-static", "aarch64", "aarch64-static"]
+                .map(|suffix| asset(&format!("tool-{os}-{suffix}")))
+                .join(",");
+            let release = |tag: &str| format!("{{\"tag_name\":\"{tag}\",\"assets\":[{assets}]}}");
+            let download = |arch: &str| {
+                (
+                    format!("/download/tool-{os}-{arch}-static"),
+                    200,
+                    arch.as_bytes().to_vec(),
+                )
+            };
+            vec![
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    200,
+                    release("v1.1.0").into_bytes(),
+                ),
+                download("aarch64"),
+                download("x86_64"),
+                (
+                    "/repos/mxcl/tool/releases?per_page=100".to_string(),
+                    200,
+                    format!("[{},{}]", release("v1.1.0"), release("v1.0.0")).into_bytes(),
+                ),
+                download("x86_64"),
+            ]
+        });
+
         let temp = tempfile::tempdir().expect("temp dir");
-        let archive = temp.path().join("tool.zip");
-        let file = fs::File::create(&archive).expect("create zip");
-        let mut zip = zip::ZipWriter::new(file);
-        let options = zip::write::FileOptions::default();
-        zip.add_directory("bin/", options).expect("add dir");
-        zip.start_file("bin/tool", options).expect("start file");
-        zip.write_all(b"bin").expect("write file");
-        zip.finish().expect("finish zip");
+        let config = temp.path().join("config.toml");
+        fs::write(&config, "[asset]\nprefer = [\"static\"]\n").expect("write config");
+        let _config_guard = EnvGuard::set("YOINK_CONFIG", &config);
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
 
-        let extracted = extract_archive(&archive, "tool").expect("extract zip");
-        assert!(extracted.primary.ends_with("tool"));
+        let arches = temp.path().join("arches");
+        let summaries =
+            download_all_arches("mxcl/tool", &arches, &Options::default()).expect("all arches");
+        let assets: Vec<&str> = summaries.iter().map(|s| s.asset_name.as_str()).collect();
+        assert_eq!(
+            assets,
+            [
+                format!("tool-{os}-aarch64-static"),
+                format!("tool-{os}-x86_64-static")
+            ]
+        );
+        let options = Options {
+            arch: Some("x86_64".to_string()),
+            ..Options::default()
+        };
+        let since = temp.path().join("since");
+        let summaries =
+            download_since_tag("mxcl/tool", "v1.0.0", &since, &options).expect("since tag");
+        assert_eq!(summaries[0].asset_name, format!("tool-{os}-x86_64-static"));
+
+        server.finish();
     }
 
     #[test]
-    fn extract_tar_gz_archive() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let archive = temp.path().join("tool.tar.gz");
-        write_tar_gz(&archive, &[("tool", b"bin")]);
+    #[serial]
+    fn path_warning_suggests_export_for_shell() {
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = home.path().join("bin");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", &bin);
+        let _shell_guard = EnvGuard::set("SHELL", "/bin/zsh");
 
-        let extracted = extract_archive(&archive, "tool").expect("extract tar.gz");
-        assert!(extracted.primary.ends_with("tool"));
-    }
+        let on_path = env::join_paths([Path::new("/usr/bin"), bin.as_path()]).expect("join");
+        assert_eq!(path_warning(&bin, &on_path), None);
 
-    #[test]
-    fn extract_tar_xz_archive() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let archive = temp.path().join("tool.tar.xz");
-        write_tar_xz(&archive, &[("tool", b"bin")]);
+        let warning = path_warning(&bin, OsStr::new("/usr/bin")).expect("warning");
+        assert!(warning.contains("is not on your PATH"), "{warning}");
+        assert!(
+            warning.contains("export PATH=\"$HOME/bin:$PATH\""),
+            "{warning}"
+        );
 
-        let extracted = extract_archive(&archive, "tool").expect("extract tar.xz");
-        assert!(extracted.primary.ends_with("tool"));
+        let _shell_guard = EnvGuard::set("SHELL", "/bin/tcsh");
+        let warning = path_warning(&bin, OsStr::new("/usr/bin")).expect("warning");
+        assert!(warning.contains("export PATH="), "{warning}");
     }
 
     #[test]
-    fn extract_tar_bz2_archive() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let archive = temp.path().join("tool.tar.bz2");
-        write_tar_bz2(&archive, &[("tool", b"bin")]);
-
-        let extracted = extract_archive(&archive, "tool").expect("extract tar.bz2");
-        assert!(extracted.primary.ends_with("tool"));
+    #[serial]
+    fn path_warning_once_honors_opt_out_and_warns_once() {
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = home.path().join("bin");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let path_var = OsStr::new("/usr/bin");
+        let options = Options::default();
+        PATH_WARNED.store(false, atomic::Ordering::SeqCst);
+
+        let opt_out = EnvGuard::set("YOINK_NO_PATH_CHECK", "1");
+        assert_eq!(path_warning_once(&bin, path_var, &options), None);
+        drop(opt_out);
+
+        let warning = path_warning_once(&bin, path_var, &options).expect("first warning");
+        assert!(warning.contains("is not on your PATH"), "{warning}");
+        assert_eq!(path_warning_once(&bin, path_var, &options), None);
+        assert_eq!(
+            path_warning_once(&home.path().join("other"), path_var, &options),
+            None
+        );
     }
 
     #[test]
-    fn extract_archive_rejects_unknown_format() {
+    #[serial]
+    fn dest_dir_template_expands_platform_variables() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool-linux-arm64");
+            let body = format!(
+                "{{\"tag_name\":\"v1.4.0\",\"assets\":[{{\"name\":\"tool-linux-arm64\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool-linux-arm64".to_string(), b"bin".to_vec());
+            responses
+        });
+
         let temp = tempfile::tempdir().expect("temp dir");
-        let archive = temp.path().join("tool.rar");
-        fs::write(&archive, b"bad").expect("write");
-        assert!(extract_archive(&archive, "tool").is_err());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let template = temp.path().join("dist/{os}/{arch}/{version}");
+        let options = Options {
+            only_os: Some("linux".to_string()),
+            arch: Some("arm64".to_string()),
+            ..Options::default()
+        };
+        let summary = download_to_dir_with("mxcl/tool", &template, &options).expect("download");
+        let expected = temp
+            .path()
+            .join("dist/linux/aarch64/1.4.0/tool-linux-arm64");
+        assert_eq!(summary.primary_path, expected);
+        assert!(expected.exists());
+
+        server.finish();
     }
 
     #[test]
-    fn extract_gzip_non_archive() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let archive = temp.path().join("payload.gz");
-        write_gzip(&archive, b"bin");
+    #[serial]
+    fn partial_tag_resolves_to_newest_matching_release() {
+        let server = TestServer::sequence(|base| {
+            let releases = ["v1.20.0", "v1.2.5", "v1.2.0", "v1.1.9"]
+                .map(|tag| {
+                    format!(
+                        "{{\"tag_name\":\"{tag}\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/{tag}\"}}]}}"
+                    )
+                })
+                .join(",");
+            // A full first page means there may be more, so the matches on page two count.
+            let older = (0..100)
+                .map(|patch| format!("{{\"tag_name\":\"v0.9.{patch}\",\"assets\":[]}}"))
+                .collect::<Vec<_>>()
+                .join(",");
+            let list = "/repos/mxcl/tool/releases?per_page=100".to_string();
+            vec![
+                (
+                    "/repos/mxcl/tool/releases/tags/1.2".to_string(),
+                    404,
+                    b"{}".to_vec(),
+                ),
+                (list.clone(), 502, b"{}".to_vec()),
+                (list.clone(), 200, format!("[{older}]").into_bytes()),
+                (
+                    format!("{list}&page=2"),
+                    200,
+                    format!("[{releases}]").into_bytes(),
+                ),
+            ]
+        });
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            network: NetworkConfig {
+                retries: Some(1),
+                ..NetworkConfig::default()
+            },
+            ..Options::default()
+        };
 
-        let extracted = extract_gzip(&archive, "tool").expect("extract gzip");
-        assert!(extracted.primary.exists());
+        let info = release_info_with("mxcl/tool@1.2", &options).expect("release info");
+        assert_eq!(info.tag, "v1.2.5");
+        assert!(tag_has_prefix("1.2.0", "v1.2"));
+        assert!(!tag_has_prefix("v1.20.0", "1.2"));
+
+        server.finish();
     }
 
     #[test]
-    fn extract_gzip_matches_repo_name() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let archive = temp.path().join("tool.gz");
-        write_gzip(&archive, b"bin");
+    #[serial]
+    fn wait_for_release_polls_until_the_release_appears() {
+        let server = TestServer::sequence(|base| {
+            let latest = "/repos/mxcl/tool/releases/latest".to_string();
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
+            );
+            vec![
+                (latest.clone(), 404, b"{}".to_vec()),
+                (latest.clone(), 404, b"{}".to_vec()),
+                (latest, 200, body.into_bytes()),
+            ]
+        });
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            wait_for_release: Some(Duration::from_secs(2)),
+            ..Options::default()
+        };
 
-        let extracted = extract_gzip(&archive, "tool").expect("extract gzip");
-        assert!(extracted.primary.ends_with("tool"));
+        let started = std::time::Instant::now();
+        let info = release_info_with("mxcl/tool", &options).expect("release appears");
+        assert_eq!(info.tag, "v1.0.0");
+        assert!(started.elapsed() >= Duration::from_millis(400));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        server.finish();
+
+        let server = TestServer::sequence(|_base| {
+            let latest = "/repos/mxcl/tool/releases/latest".to_string();
+            vec![(latest, 404, b"{}".to_vec())]
+        });
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let options = Options {
+            wait_for_release: Some(Duration::ZERO),
+            ..Options::default()
+        };
+        let err = release_info_with("mxcl/tool", &options).expect_err("never appears");
+        assert!(err.to_string().contains("did not appear"), "{err}");
+        server.finish();
     }
 
     #[test]
-    fn download_asset_from_local_server() {
+    #[serial]
+    fn upgrade_all_installs_every_repo() {
         let server = TestServer::new(|base| {
             let mut responses = BTreeMap::new();
-            responses.insert("/asset".to_string(), b"hello".to_vec());
-            let _ = base;
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v9.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
             responses
         });
 
-        let client = github_client().expect("client");
-        let temp = tempfile::tempdir().expect("temp dir");
-        let dest = temp.path().join("asset");
-        let url = format!("{}/asset", server.base);
-        download_asset(&client, &url, &dest).expect("download asset");
-        assert_eq!(fs::read(&dest).expect("read"), b"hello");
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        record_install(
+            "mxcl/tool",
+            "v1.0.0",
+            &[bin.path().join("tool")],
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+        let upgrades = upgrade_all().expect("upgrade").upgraded;
+        assert_eq!(upgrades.len(), 1);
+        assert_eq!(upgrades[0].version, "9.0.0");
 
         server.finish();
     }
 
     #[test]
     #[serial]
-    fn github_client_uses_token_header() {
-        let _guard = EnvGuard::set("YOINK_GITHUB_TOKEN", "token123");
-        let _client = github_client().expect("client");
+    fn upgrade_all_reinstalls_into_the_original_dir() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v9.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let original = tempfile::tempdir().expect("original dir");
+        let default = tempfile::tempdir().expect("default dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", default.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        record_install(
+            "mxcl/tool",
+            "v1.0.0",
+            &[original.path().join("tool")],
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+        let state = load_state().expect("load state");
+        assert_eq!(
+            state.installs["mxcl/tool"].install_dir.as_deref(),
+            Some(original.path())
+        );
+
+        let upgrades = upgrade_all().expect("upgrade").upgraded;
+        assert_eq!(upgrades[0].path, original.path().join("tool"));
+        assert!(original.path().join("tool").exists());
+        assert!(!default.path().join("tool").exists());
+
+        server.finish();
     }
 
     #[test]
     #[serial]
-    fn release_info_uses_override_base() {
+    fn pinned_install_is_idempotent_and_skipped_by_upgrade() {
         let server = TestServer::new(|base| {
             let mut responses = BTreeMap::new();
             let url = format!("{base}/download/tool");
@@ -1642,260 +11118,410 @@ mod tests {
                 "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
             );
             responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
+                "/repos/mxcl/tool/releases/tags/v1.0.0".to_string(),
                 body.into_bytes(),
             );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
             responses
         });
 
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let info = release_info("mxcl/tool").expect("release info");
-        assert_eq!(info.owner, "mxcl");
-        assert_eq!(info.name, "tool");
-        assert_eq!(info.tag, "v1.0.0");
-        assert_eq!(info.asset_name, "tool");
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
 
+        let report = install_with_report("mxcl/tool@v1.0.0", &Options::default()).expect("install");
+        assert_eq!(report.version, "v1.0.0");
         server.finish();
+
+        let again =
+            install_with_report("mxcl/tool@v1.0.0", &Options::default()).expect("reinstall");
+        assert_eq!(again.path, report.path);
+        let state = load_state().expect("load state");
+        assert!(state.installs["mxcl/tool"].pinned);
+        assert!(upgrade_all().expect("upgrade").upgraded.is_empty());
     }
 
     #[test]
     #[serial]
-    fn prepare_binary_downloads_and_extracts() {
+    fn upgrade_all_reports_failures_after_upgrading_the_rest() {
         let server = TestServer::new(|base| {
             let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool.tar.gz");
-            let body = format!(
-                "{{\"tag_name\":\"v2.0.0\",\"assets\":[{{\"name\":\"tool.tar.gz\",\"browser_download_url\":\"{url}\"}}]}}"
-            );
-            let tar = make_tar_gz_bytes(&[("tool", b"bin")]);
+            for name in ["alpha", "gamma"] {
+                let url = format!("{base}/download/{name}");
+                let body = format!(
+                    "{{\"tag_name\":\"v9.0.0\",\"assets\":[{{\"name\":\"{name}\",\"browser_download_url\":\"{url}\"}}]}}"
+                );
+                responses.insert(
+                    format!("/repos/mxcl/{name}/releases/latest"),
+                    body.into_bytes(),
+                );
+                responses.insert(format!("/download/{name}"), b"bin".to_vec());
+            }
             responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                body.into_bytes(),
+                "/repos/mxcl/beta/releases/latest".to_string(),
+                b"not json".to_vec(),
             );
-            responses.insert("/download/tool.tar.gz".to_string(), tar);
             responses
         });
 
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let prepared = prepare_binary("mxcl/tool").expect("prepare binary");
-        assert!(prepared.path.exists());
-        assert_eq!(prepared.asset_name, "tool.tar.gz");
-        assert!(prepared.extra_paths.is_empty());
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        for name in ["alpha", "beta", "gamma"] {
+            record_install(
+                &format!("mxcl/{name}"),
+                "v1.0.0",
+                &[bin.path().join(name)],
+                false,
+                &InstallMeta::default(),
+            )
+            .expect("record install");
+        }
+        let report = upgrade_all().expect("upgrade");
+        let upgraded: Vec<&str> = report.upgraded.iter().map(|up| up.repo.as_str()).collect();
+        assert_eq!(upgraded, ["mxcl/alpha", "mxcl/gamma"]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "mxcl/beta");
+
+        let state = load_state().expect("load state");
+        assert_eq!(state.installs["mxcl/alpha"].version, "v9.0.0");
+        assert_eq!(state.installs["mxcl/beta"].version, "v1.0.0");
+        assert_eq!(state.installs["mxcl/gamma"].version, "v9.0.0");
 
         server.finish();
     }
 
     #[test]
     #[serial]
-    fn prepare_binary_downloads_gzip() {
-        let server = TestServer::new(|base| {
+    fn uninstall_removes_install() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+
+        record_install(
+            "mxcl/yoink",
+            "v1.0.0",
+            &[temp.path().join("yoink")],
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+        uninstall("mxcl/yoink").expect("uninstall");
+        let state = load_state().expect("load state");
+        assert!(state.installs.is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn gitlab_installs_are_looked_up_by_prefixed_key() {
+        let server = TestServer::new(|_base| {
             let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool.gz");
-            let body = format!(
-                "{{\"tag_name\":\"v2.1.0\",\"assets\":[{{\"name\":\"tool.gz\",\"browser_download_url\":\"{url}\"}}]}}"
-            );
-            let gzip = make_gzip_bytes(b"bin");
             responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                body.into_bytes(),
+                "/projects/mxcl%2Ftool/releases?per_page=100".to_string(),
+                br#"[{"tag_name":"v1.1.0","description":"newer"},{"tag_name":"v1.0.0"}]"#.to_vec(),
             );
-            responses.insert("/download/tool.gz".to_string(), gzip);
             responses
         });
 
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let prepared = prepare_binary("mxcl/tool").expect("prepare binary");
-        assert!(prepared.path.exists());
-        assert_eq!(prepared.asset_name, "tool.gz");
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let _api_guard = EnvGuard::set("YOINK_GITLAB_API_BASE", &server.base);
+        let bin = temp.path().join("tool");
+        fs::write(&bin, b"bin").expect("write bin");
+        record_install(
+            "gitlab:mxcl/tool",
+            "v1.0.0",
+            std::slice::from_ref(&bin),
+            true,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+
+        assert_eq!(
+            installed_bins("gitlab:mxcl/tool").expect("bins").primary,
+            bin
+        );
+        assert!(installed_bins("mxcl/tool").is_err());
+        let report = ensure("gitlab:mxcl/tool@v1.0.0", &Options::default()).expect("ensure");
+        assert_eq!(report.action, EnsureAction::Unchanged);
+        let notes = release_notes_since_installed("gitlab:mxcl/tool").expect("notes");
+        let tags: Vec<&str> = notes.iter().map(|note| note.tag.as_str()).collect();
+        assert_eq!(tags, ["v1.1.0"]);
+
+        uninstall("gitlab:mxcl/tool").expect("uninstall");
+        assert!(load_state().expect("load state").installs.is_empty());
 
         server.finish();
     }
 
     #[test]
     #[serial]
-    fn download_to_dir_installs_extras() {
-        let server = TestServer::new(|base| {
+    fn release_notes_since_installed_lists_newer_releases() {
+        let server = TestServer::new(|_base| {
             let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool.zip");
-            let body = format!(
-                "{{\"tag_name\":\"v3.0.0\",\"assets\":[{{\"name\":\"tool.zip\",\"browser_download_url\":\"{url}\"}}]}}"
-            );
-            let zip = make_zip_bytes(&[("tool", b"bin"), ("helper", b"bin")]);
+            let body = r#"[{"tag_name":"v1.2.0","body":"third","assets":[]},{"tag_name":"v1.1.0","body":"second","assets":[]},{"tag_name":"v1.0.0","body":"first","assets":[]}]"#;
             responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                body.into_bytes(),
+                "/repos/mxcl/tool/releases?per_page=100".to_string(),
+                body.as_bytes().to_vec(),
             );
-            responses.insert("/download/tool.zip".to_string(), zip);
             responses
         });
 
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let dest = tempfile::tempdir().expect("temp dir");
-        let summary = download_to_dir("mxcl/tool", dest.path()).expect("download");
-        assert_eq!(summary.tag, "v3.0.0");
-        assert!(summary.primary_path.exists());
-        assert_eq!(summary.paths.len(), 2);
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        record_install(
+            "mxcl/tool",
+            "v1.0.0",
+            &[temp.path().join("tool")],
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+        let notes = release_notes_since_installed("mxcl/tool").expect("release notes");
+        let tags: Vec<&str> = notes.iter().map(|notes| notes.tag.as_str()).collect();
+        assert_eq!(tags, ["v1.2.0", "v1.1.0"]);
+        assert_eq!(notes[1].body, "second");
 
         server.finish();
     }
 
     #[test]
     #[serial]
-    fn download_to_dir_skips_duplicate_extras() {
-        let server = TestServer::new(|base| {
-            let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool.zip");
-            let body = format!(
-                "{{\"tag_name\":\"v3.1.0\",\"assets\":[{{\"name\":\"tool.zip\",\"browser_download_url\":\"{url}\"}}]}}"
-            );
-            let zip = make_zip_bytes(&[("bin/tool", b"bin"), ("alt/tool", b"bin")]);
-            responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                body.into_bytes(),
-            );
-            responses.insert("/download/tool.zip".to_string(), zip);
-            responses
+    fn release_notes_since_installed_reads_every_gitlab_page() {
+        let server = TestServer::sequence(|_base| {
+            let older = (0..100)
+                .map(|minor| format!("{{\"tag_name\":\"v0.{minor}.0\"}}"))
+                .collect::<Vec<_>>()
+                .join(",");
+            let newer = r#"[{"tag_name":"v1.0.0-rc.1"},{"tag_name":"v1.0.0-rc.2","description":"rc"},{"tag_name":"v1.0.0"}]"#;
+            let list = "/projects/mxcl%2Ftool/releases?per_page=100".to_string();
+            vec![
+                (list.clone(), 200, format!("[{older}]").into_bytes()),
+                (format!("{list}&page=2"), 200, newer.as_bytes().to_vec()),
+            ]
         });
 
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let dest = tempfile::tempdir().expect("temp dir");
-        let summary = download_to_dir("mxcl/tool", dest.path()).expect("download");
-        assert_eq!(summary.paths.len(), 1);
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
+        let _api_guard = EnvGuard::set("YOINK_GITLAB_API_BASE", &server.base);
+
+        record_install(
+            "gitlab:mxcl/tool",
+            "v1.0.0-rc.1",
+            &[temp.path().join("tool")],
+            false,
+            &InstallMeta::default(),
+        )
+        .expect("record install");
+        let notes = release_notes_since_installed("gitlab:mxcl/tool").expect("release notes");
+        let tags: Vec<&str> = notes.iter().map(|notes| notes.tag.as_str()).collect();
+        assert_eq!(tags, ["v1.0.0", "v1.0.0-rc.2"]);
+        assert_eq!(notes[1].body, "rc");
 
         server.finish();
     }
 
+    #[test]
+    fn parse_duration_accepts_units() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse_duration("2d").unwrap(), Duration::from_secs(172_800));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("5w").is_err());
+    }
+
     #[test]
     #[serial]
-    fn install_with_version_records_state() {
+    fn release_info_reuses_cache_younger_than_max_age() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let _home = EnvGuard::set("HOME", temp.path());
+        let _cache = EnvGuard::set("XDG_CACHE_HOME", temp.path());
+        let options = Options {
+            info_max_age: Some(Duration::from_secs(600)),
+            ..Options::default()
+        };
+        let cached = |tag: &str| ReleaseInfo {
+            owner: "mxcl".to_string(),
+            name: "tool".to_string(),
+            tag: tag.to_string(),
+            asset_name: "tool".to_string(),
+            asset_url: "https://example.invalid/tool".to_string(),
+            checksum_url: None,
+            signature_urls: Vec::new(),
+        };
+
+        store_release_info("mxcl/tool", &cached("v1.0.0")).expect("store cache");
+        let _unreachable = EnvGuard::set("YOINK_GITHUB_API_BASE", "http://127.0.0.1:9");
+        let info = release_info_with("mxcl/tool", &options).expect("cached info");
+        assert_eq!(info.tag, "v1.0.0");
+        // A differently steered pick isn't served from that cache entry.
+        let steered = [
+            Options {
+                only_os: Some("linux".to_string()),
+                ..options.clone()
+            },
+            Options {
+                only_arch: Some("aarch64".to_string()),
+                ..options.clone()
+            },
+            Options {
+                tag_tokens: true,
+                ..options.clone()
+            },
+            Options {
+                asset_prefer: vec!["musl".to_string()],
+                ..options.clone()
+            },
+            Options {
+                asset_avoid: vec!["gnu".to_string()],
+                ..options.clone()
+            },
+        ];
+        for options in &steered {
+            assert!(release_info_with("mxcl/tool", options).is_err());
+        }
+        drop(_unreachable);
+
+        let mut cache = read_info_cache().expect("read cache");
+        cache
+            .entries
+            .get_mut("mxcl/tool")
+            .expect("entry")
+            .fetched_at = unix_now() - 3600;
+        fs::write(
+            info_cache_path().expect("cache path"),
+            serde_json::to_string(&cache).expect("encode cache"),
+        )
+        .expect("write stale cache");
+
         let server = TestServer::new(|base| {
             let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool");
             let body = format!(
-                "{{\"tag_name\":\"v4.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+                "{{\"tag_name\":\"v2.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
             );
             responses.insert(
                 "/repos/mxcl/tool/releases/latest".to_string(),
                 body.into_bytes(),
             );
-            responses.insert("/download/tool".to_string(), b"bin".to_vec());
             responses
         });
-
-        let home = tempfile::tempdir().expect("temp dir");
-        let bin = tempfile::tempdir().expect("bin dir");
-        let _home_guard = EnvGuard::set("HOME", home.path());
-        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
-        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
         let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-
-        let (path, version) = install_with_version("mxcl/tool").expect("install");
-        assert!(path.exists());
-        assert_eq!(version, "v4.0.0");
-
-        let installs = list_installs().expect("list installs");
-        assert_eq!(installs.len(), 1);
+        let info = release_info_with("mxcl/tool", &options).expect("refreshed info");
+        assert_eq!(info.tag, "v2.0.0");
+        let cache = read_info_cache().expect("read cache");
+        assert_eq!(cache.entries["mxcl/tool"].info.tag, "v2.0.0");
 
         server.finish();
     }
 
-    #[test]
     #[serial]
-    fn install_with_version_skips_duplicate_extras() {
+    #[cfg(unix)]
+    fn run_executes_downloaded_binary() {
         let server = TestServer::new(|base| {
             let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool.zip");
+            let url = format!("{base}/download/tool");
             let body = format!(
-                "{{\"tag_name\":\"v4.1.0\",\"assets\":[{{\"name\":\"tool.zip\",\"browser_download_url\":\"{url}\"}}]}}"
+                "{{\"tag_name\":\"v5.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
             );
-            let zip = make_zip_bytes(&[("bin/tool", b"bin"), ("alt/tool", b"bin")]);
+            let script = b"#!/bin/sh\nexit 7\n";
             responses.insert(
                 "/repos/mxcl/tool/releases/latest".to_string(),
                 body.into_bytes(),
             );
-            responses.insert("/download/tool.zip".to_string(), zip);
+            responses.insert("/download/tool".to_string(), script.to_vec());
             responses
         });
 
-        let home = tempfile::tempdir().expect("temp dir");
-        let bin = tempfile::tempdir().expect("bin dir");
-        let _home_guard = EnvGuard::set("HOME", home.path());
-        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
-        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
         let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-
-        let (path, _version) = install_with_version("mxcl/tool").expect("install");
-        assert!(path.exists());
+        let code = run("mxcl/tool", &[]).expect("run");
+        assert_eq!(code, 7);
 
         server.finish();
     }
 
     #[test]
     #[serial]
-    fn upgrade_all_installs_every_repo() {
+    #[cfg(unix)]
+    fn run_captured_returns_output_and_exit_code() {
         let server = TestServer::new(|base| {
             let mut responses = BTreeMap::new();
             let url = format!("{base}/download/tool");
             let body = format!(
-                "{{\"tag_name\":\"v9.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+                "{{\"tag_name\":\"v5.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
             );
+            let script = b"#!/bin/sh\necho \"out $1\"\necho err >&2\nexit 3\n";
             responses.insert(
                 "/repos/mxcl/tool/releases/latest".to_string(),
                 body.into_bytes(),
             );
-            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses.insert("/download/tool".to_string(), script.to_vec());
             responses
         });
 
-        let home = tempfile::tempdir().expect("temp dir");
-        let bin = tempfile::tempdir().expect("bin dir");
-        let _home_guard = EnvGuard::set("HOME", home.path());
-        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
-        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
         let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-
-        record_install("mxcl/tool", "v1.0.0", &[bin.path().join("tool")]).expect("record install");
-        let upgrades = upgrade_all().expect("upgrade");
-        assert_eq!(upgrades.len(), 1);
-        assert_eq!(upgrades[0].version, "9.0.0");
+        let (code, stdout, stderr) =
+            run_captured("mxcl/tool", &["hello".to_string()]).expect("run captured");
+        assert_eq!(code, 3);
+        assert_eq!(stdout, b"out hello\n");
+        assert_eq!(stderr, b"err\n");
 
         server.finish();
     }
 
     #[test]
     #[serial]
-    fn uninstall_removes_install() {
-        let temp = tempfile::tempdir().expect("temp dir");
-        let _home = EnvGuard::set("HOME", temp.path());
-        let _xdg = EnvGuard::set("XDG_DATA_HOME", temp.path());
-
-        record_install("mxcl/yoink", "v1.0.0", &[temp.path().join("yoink")])
-            .expect("record install");
-        uninstall("mxcl/yoink").expect("uninstall");
-        let state = load_state().expect("load state");
-        assert!(state.installs.is_empty());
-    }
-    #[serial]
     #[cfg(unix)]
-    fn run_executes_downloaded_binary() {
+    fn run_refuses_binary_that_mismatches_lockfile() {
         let server = TestServer::new(|base| {
             let mut responses = BTreeMap::new();
             let url = format!("{base}/download/tool");
             let body = format!(
                 "{{\"tag_name\":\"v5.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
             );
-            let script = b"#!/bin/sh\nexit 7\n";
             responses.insert(
                 "/repos/mxcl/tool/releases/latest".to_string(),
                 body.into_bytes(),
             );
-            responses.insert("/download/tool".to_string(), script.to_vec());
+            responses.insert(
+                "/download/tool".to_string(),
+                b"#!/bin/sh\ntouch \"$1\"\n".to_vec(),
+            );
             responses
         });
 
+        let temp = tempfile::tempdir().expect("temp dir");
+        let lockfile = temp.path().join("yoink.lock");
+        let expected = format!("{:x}", Sha256::digest(b"#!/bin/sh\nexit 0\n"));
+        fs::write(
+            &lockfile,
+            format!("[[lock]]\nrepo = \"mxcl/tool\"\nsha256 = \"{expected}\"\n"),
+        )
+        .expect("write lockfile");
+        let marker = temp.path().join("ran");
+        let options = Options {
+            lockfile: Some(lockfile),
+            ..Options::default()
+        };
+
         let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let code = run("mxcl/tool", &[]).expect("run");
-        assert_eq!(code, 7);
+        let err = run_with("mxcl/tool", &[marker.display().to_string()], &options)
+            .expect_err("mismatch should refuse");
+        assert!(err.to_string().contains("does not match"));
+        assert!(!marker.exists());
 
         server.finish();
     }
@@ -1923,6 +11549,44 @@ mod tests {
         }
     }
 
+    /// Puts `git` and `cargo` stand-ins in `dir` ahead of `PATH`. Each appends its command
+    /// line to `log`; `git clone` makes an empty checkout and `cargo install` a `tool` binary.
+    #[cfg(unix)]
+    fn setup_fake_build_tools(dir: &Path, log: &Path) -> EnvGuard {
+        let git = format!(
+            r#"#!/bin/sh
+echo "git $*" >> "{log}"
+for arg in "$@"; do
+  last="$arg"
+done
+mkdir -p "$last"
+"#,
+            log = log.display()
+        );
+        let cargo = format!(
+            r#"#!/bin/sh
+echo "cargo $*" >> "{log}"
+while [ $# -gt 0 ]; do
+  if [ "$1" = "--root" ]; then
+    root="$2"
+  fi
+  shift
+done
+mkdir -p "$root/bin"
+printf '#!/bin/sh\n' > "$root/bin/tool"
+chmod +x "$root/bin/tool"
+"#,
+            log = log.display()
+        );
+        for (name, script) in [("git", git), ("cargo", cargo)] {
+            let path = dir.join(name);
+            fs::write(&path, script).expect("write fake tool");
+            set_executable(&path).expect("chmod fake tool");
+        }
+        let existing = env::var("PATH").unwrap_or_default();
+        EnvGuard::set("PATH", format!("{}:{existing}", dir.display()))
+    }
+
     #[cfg(unix)]
     fn setup_fake_sudo() -> (tempfile::TempDir, EnvGuard) {
         use std::os::unix::fs::PermissionsExt;
@@ -1936,6 +11600,11 @@ shift
 
 case "$cmd" in
   mv)
+    last=""
+    for arg in "$@"; do
+      last="$arg"
+    done
+    chmod u+w "$(dirname "$last")" 2>/dev/null || true
     exec /bin/mv "$@"
     ;;
   mkdir)
@@ -1994,6 +11663,43 @@ esac
             }
         }
 
+        /// Serves `(path, status, body)` responses strictly in order, one per connection.
+        fn sequence<F>(make_responses: F) -> Self
+        where
+            F: FnOnce(&str) -> Vec<(String, u16, Vec<u8>)>,
+        {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+            let addr = listener.local_addr().expect("addr");
+            let base = format!("http://{addr}");
+            let responses = make_responses(&base);
+            let handle = thread::spawn(move || {
+                for (path, status, body) in responses {
+                    let (mut stream, _) = listener.accept().expect("accept");
+                    let mut reader = BufReader::new(&mut stream);
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).expect("read request");
+                    assert_eq!(request_line.split_whitespace().nth(1), Some(path.as_str()));
+                    loop {
+                        let mut line = String::new();
+                        let bytes = reader.read_line(&mut line).expect("read header");
+                        if bytes == 0 || line == "\r\n" {
+                            break;
+                        }
+                    }
+                    let header = format!(
+                        "HTTP/1.1 {status} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    stream.write_all(header.as_bytes()).expect("write header");
+                    stream.write_all(&body).expect("write body");
+                }
+            });
+            Self {
+                base,
+                handle: Some(handle),
+            }
+        }
+
         fn finish(mut self) {
             if let Some(handle) = self.handle.take() {
                 handle.join().expect("server thread");
@@ -2029,6 +11735,88 @@ esac
         reader.get_mut().write_all(body).expect("write body");
     }
 
+    /// Serves `body` at any path, answering HEAD probes and `Range` requests like a CDN.
+    /// How `spawn_range_server` treats HEAD probes and Range requests.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum RangeSupport {
+        Ranges,
+        None,
+        /// HEAD gets a 405, as some CDNs answer.
+        HeadRejected,
+        /// Range support is advertised but every GET gets the whole body.
+        Ignored,
+    }
+
+    fn spawn_range_server(
+        body: Vec<u8>,
+        support: RangeSupport,
+        requests: usize,
+    ) -> (String, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base = format!("http://{}", listener.local_addr().expect("addr"));
+        let handle = thread::spawn(move || {
+            for _ in 0..requests {
+                let (stream, _) = listener.accept().expect("accept");
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).expect("read request");
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    let bytes = reader.read_line(&mut line).expect("read header");
+                    if bytes == 0 || line == "\r\n" {
+                        break;
+                    }
+                    let lower = line.to_lowercase();
+                    if let Some(value) = lower.strip_prefix("range: bytes=") {
+                        let (start, end) = value.trim().split_once('-').expect("range");
+                        let start: usize = start.parse().expect("start");
+                        let end: usize = end.parse().expect("end");
+                        range = Some((start, end));
+                    }
+                }
+                let ranges_header = match support {
+                    RangeSupport::Ranges | RangeSupport::Ignored => "Accept-Ranges: bytes\r\n",
+                    RangeSupport::None | RangeSupport::HeadRejected => "",
+                };
+                if support == RangeSupport::Ignored {
+                    range = None;
+                }
+                let stream = reader.get_mut();
+                if request_line.starts_with("HEAD") && support == RangeSupport::HeadRejected {
+                    stream
+                        .write_all(
+                            b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        )
+                        .expect("write header");
+                } else if request_line.starts_with("HEAD") {
+                    let header = format!(
+                        "HTTP/1.1 200 OK\r\n{ranges_header}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    stream.write_all(header.as_bytes()).expect("write header");
+                } else if let Some((start, end)) = range {
+                    let slice = &body[start..=end];
+                    let header = format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {start}-{end}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len(),
+                        slice.len()
+                    );
+                    stream.write_all(header.as_bytes()).expect("write header");
+                    stream.write_all(slice).expect("write body");
+                } else {
+                    let header = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    stream.write_all(header.as_bytes()).expect("write header");
+                    stream.write_all(&body).expect("write body");
+                }
+            }
+        });
+        (base, handle)
+    }
+
     fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
         let file = fs::File::create(path).expect("create zip");
         let mut zip = zip::ZipWriter::new(file);
@@ -2040,6 +11828,52 @@ esac
         zip.finish().expect("finish zip");
     }
 
+    fn write_cab(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut builder = cab::CabinetBuilder::new();
+        let folder = builder.add_folder(cab::CompressionType::MsZip);
+        for &(name, _) in entries {
+            folder.add_file(name);
+        }
+        let file = fs::File::create(path).expect("create cab");
+        let mut writer = builder.build(file).expect("build cab");
+        let mut index = 0;
+        while let Some(mut entry) = writer.next_file().expect("next cab file") {
+            entry.write_all(entries[index].1).expect("write cab file");
+            index += 1;
+        }
+        writer.finish().expect("finish cab");
+    }
+
+    #[test]
+    fn extract_zip_decodes_legacy_entry_names() {
+        // Written with ASCII stand-ins, then patched to raw bytes without the UTF-8 flag:
+        // `caf\x82` is CP437 for `café`, and `r\xc3\xa9sum\xc3\xa9` is unflagged UTF-8.
+        let mut bytes = make_zip_bytes(&[("cafZ/helper", b"bin"), ("rXXsumXX/tool", b"bin")]);
+        let patch = |bytes: &mut Vec<u8>, from: &[u8], to: &[u8]| {
+            let mut patched = 0;
+            while let Some(at) = bytes.windows(from.len()).position(|window| window == from) {
+                bytes[at..at + to.len()].copy_from_slice(to);
+                patched += 1;
+            }
+            assert_eq!(patched, 2, "local header and central directory");
+        };
+        patch(&mut bytes, b"cafZ", b"caf\x82");
+        patch(&mut bytes, b"rXXsumXX", "résumé".as_bytes());
+
+        let temp = tempfile::tempdir().expect("temp dir");
+        let archive = temp.path().join("tool.zip");
+        fs::write(&archive, bytes).expect("write zip");
+        let root = temp.path().join("out");
+        fs::create_dir(&root).expect("mkdir out");
+        extract_zip(&archive, &root).expect("extract zip");
+        assert!(root.join("café").join("helper").is_file());
+
+        let (primary, extras) =
+            find_binaries(&root, "tool", &Options::default()).expect("find binaries");
+        assert_eq!(primary, root.join("résumé").join("tool"));
+        assert_eq!(extras, vec![root.join("café").join("helper")]);
+    }
+
     fn make_zip_bytes(entries: &[(&str, &[u8])]) -> Vec<u8> {
         let mut buffer = Cursor::new(Vec::new());
         {
@@ -2078,6 +11912,14 @@ esac
         });
     }
 
+    fn write_tar_zst(path: &Path, entries: &[(&str, &[u8])]) {
+        let file = fs::File::create(path).expect("create tar.zst");
+        let encoder = zstd::Encoder::new(file, 0).expect("zstd encoder");
+        write_tar_entries(encoder, entries, |encoder| {
+            encoder.finish().expect("finish")
+        });
+    }
+
     fn make_tar_gz_bytes(entries: &[(&str, &[u8])]) -> Vec<u8> {
         let buffer = Cursor::new(Vec::new());
         let encoder = flate2::write::GzEncoder::new(buffer, flate2::Compression::default());
@@ -2133,6 +11975,10 @@ esac
         type Inner = fs::File;
     }
 
+    impl IntoInner for zstd::Encoder<'_, fs::File> {
+        type Inner = fs::File;
+    }
+
     fn write_gzip(path: &Path, contents: &[u8]) {
         let file = fs::File::create(path).expect("create gzip");
         let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
@@ -1,23 +1,106 @@
+use schemars::JsonSchema;
 use serde::Serialize;
 use std::env;
-use std::path::PathBuf;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-#[derive(Serialize)]
+/// Set by `--quiet-errors`: print errors as one line instead of the full report.
+static QUIET_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Shows an error as its full debug report, or under `--quiet-errors` as its message chain
+/// joined by `: `.
+struct ErrorLine<'a, E>(&'a E);
+
+impl<E: fmt::Display + fmt::Debug> fmt::Display for ErrorLine<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if QUIET_ERRORS.load(Ordering::Relaxed) {
+            write!(f, "{:#}", self.0)
+        } else {
+            write!(f, "{:?}", self.0)
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
 struct DownloadJson {
     repo: String,
     tag: String,
     url: String,
     executables: Vec<String>,
+    /// Every written binary with its archive origin; only with `--json-verbose`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    binaries: Option<Vec<BinaryJson>>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct BinaryJson {
+    path: String,
+    source: String,
+    size: u64,
+    primary: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
+struct BinsJson {
+    repo: String,
+    tag: String,
+    asset: String,
+    /// Archive-relative paths, primary first.
+    binaries: Vec<ListedBinJson>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ListedBinJson {
+    path: String,
+    primary: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
 struct InfoJson {
     repo: String,
     tag: String,
     url: String,
 }
 
+#[derive(Serialize, JsonSchema)]
+struct ListJson {
+    repo: String,
+    version: String,
+    installed_at: Option<u64>,
+    bin: String,
+    bins: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct WhichJson {
+    repo: String,
+    primary: String,
+    bins: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct InstallJson {
+    repo: String,
+    version: String,
+    path: String,
+    /// Whether writing the binaries needed the sudo fallback.
+    used_sudo: bool,
+    /// How to put the install dir on `PATH` when it isn't.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path_warning: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct NotesJson {
+    tag: String,
+    body: String,
+}
+
 fn main() -> ExitCode {
     run_with_args(env::args().skip(1))
 }
@@ -26,7 +109,12 @@ fn run_with_args<I>(args: I) -> ExitCode
 where
     I: IntoIterator<Item = String>,
 {
-    let args: Vec<String> = args.into_iter().collect();
+    let mut args: Vec<String> = args.into_iter().collect();
+    // Ahead of a subcommand; among the main flags the loop below takes it like any other.
+    if args.first().is_some_and(|arg| arg == "--quiet-errors") {
+        args.remove(0);
+        QUIET_ERRORS.store(true, Ordering::Relaxed);
+    }
     if args.is_empty() {
         print_usage();
         return ExitCode::from(2);
@@ -40,10 +128,147 @@ where
             print_version();
             return ExitCode::SUCCESS;
         }
+        if args[0] == "--json-schema" {
+            return print_json(&json_schemas());
+        }
+        if args[0] == "--self-test" {
+            return match yoink::self_test() {
+                Ok(tested) => {
+                    println!("self-test passed: {tested}");
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("yoink: self-test failed: {}", ErrorLine(&err));
+                    ExitCode::from(1)
+                }
+            };
+        }
+    }
+
+    if args[0] == "--path-setup" {
+        let shell = match args.as_slice() {
+            [_] => None,
+            [_, shell] => Some(shell.as_str()),
+            _ => {
+                eprintln!("yoink: --path-setup takes at most one shell");
+                return ExitCode::from(2);
+            }
+        };
+        return match yoink::path_setup_line(shell) {
+            Ok(line) => {
+                println!("{line}");
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("yoink: {err}");
+                ExitCode::from(2)
+            }
+        };
+    }
+
+    if args[0] == "list" {
+        return run_list(&args[1..]);
+    }
+
+    if args[0] == "migrate-dir" {
+        let [_, new_dir] = args.as_slice() else {
+            eprintln!("yoink: migrate-dir expects exactly one directory");
+            return ExitCode::from(2);
+        };
+        return match yoink::migrate_dir(Path::new(new_dir)) {
+            Ok(paths) => {
+                for path in paths {
+                    println!("{}", path.display());
+                }
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if args[0] == "which" || args[0] == "path" {
+        return run_which(&args[1..]);
+    }
+
+    if args[0] == "run" {
+        return run_run(&args[1..]);
+    }
+
+    if args[0] == "ensure" {
+        return run_ensure(&args[1..]);
+    }
+
+    if args[0] == "rollback" {
+        return run_rollback(&args[1..]);
+    }
+
+    if args[0] == "doctor" {
+        return run_doctor(&args[1..]);
+    }
+
+    if args[0] == "uninstall" {
+        return run_uninstall(&args[1..]);
+    }
+
+    if args[0] == "prune-cache" {
+        if args.len() > 1 {
+            eprintln!("yoink: prune-cache takes no arguments");
+            return ExitCode::from(2);
+        }
+        return match yoink::prune_download_cache(0) {
+            Ok(freed) => {
+                println!("freed {freed} bytes");
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if args[0] == "export" {
+        if args.len() > 1 {
+            eprintln!("yoink: export takes no arguments");
+            return ExitCode::from(2);
+        }
+        return match yoink::export_manifest() {
+            Ok(manifest) => {
+                print!("{manifest}");
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        };
     }
 
     let mut json_output = false;
+    let mut json_verbose = false;
     let mut info_only = false;
+    let mut notes_only = false;
+    let mut print_path = false;
+    let mut print_asset_name = false;
+    let mut list_bins = false;
+    let mut check = false;
+    let mut dry_run = false;
+    let mut keep_going = false;
+    let mut plain_paths = false;
+    let mut skip_if_present = false;
+    let mut min_version: Option<String> = None;
+    let mut repo_file: Option<PathBuf> = None;
+    let mut emit_manifest: Option<PathBuf> = None;
+    let mut since_tag: Option<String> = None;
+    let mut action: Option<yoink::DefaultAction> = None;
+    let mut options = yoink::Options {
+        progress: yoink::ProgressMode::auto(),
+        prompt_bin: io::stdin().is_terminal() && io::stderr().is_terminal(),
+        ..yoink::Options::default()
+    };
     let mut download_dir: Option<PathBuf> = None;
     let mut index = 0;
 
@@ -56,517 +281,2820 @@ where
             index += 1;
             break;
         }
+        if arg == "--quiet-errors" {
+            QUIET_ERRORS.store(true, Ordering::Relaxed);
+            index += 1;
+            continue;
+        }
         if arg == "-j" {
             json_output = true;
             index += 1;
             continue;
         }
+        if arg == "--json-verbose" {
+            json_output = true;
+            json_verbose = true;
+            index += 1;
+            continue;
+        }
         if arg == "-I" {
             info_only = true;
             index += 1;
             continue;
         }
-        if arg == "-C" {
-            let Some(dir) = args.get(index + 1) else {
-                eprintln!("yoink: expected directory after -C");
+        if arg == "--notes-only" {
+            notes_only = true;
+            index += 1;
+            continue;
+        }
+        if arg == "-n" || arg == "--dry-run" {
+            dry_run = true;
+            index += 1;
+            continue;
+        }
+        if arg == "-i" || arg == "--install" {
+            action = Some(yoink::DefaultAction::Install);
+            index += 1;
+            continue;
+        }
+        if arg == "--download" {
+            action = Some(yoink::DefaultAction::Download);
+            index += 1;
+            continue;
+        }
+        if arg == "--check" {
+            check = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--list-bins" {
+            list_bins = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--print-asset-name" {
+            print_asset_name = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--print-path" {
+            print_path = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--plain-paths" || arg == "--absolute" {
+            plain_paths = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--skip-if-present" {
+            skip_if_present = true;
+            index += 1;
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--min-version") {
+            let Ok(version) = value else {
                 return ExitCode::from(2);
             };
-            download_dir = Some(PathBuf::from(dir));
-            index += 2;
+            min_version = Some(version);
             continue;
         }
-        if arg.starts_with("-C") && arg.len() > 2 {
-            download_dir = Some(PathBuf::from(&arg[2..]));
-            index += 1;
+        if let Some(value) = long_value(&args, &mut index, "--since-tag") {
+            let Ok(tag) = value else {
+                return ExitCode::from(2);
+            };
+            since_tag = Some(tag);
             continue;
         }
-        if arg.len() > 2 {
-            let mut chars = arg.chars();
-            chars.next();
-            let mut handled = true;
-            while let Some(ch) = chars.next() {
-                match ch {
-                    'j' => json_output = true,
-                    'I' => info_only = true,
-                    'C' => {
-                        let rest: String = chars.collect();
-                        if rest.is_empty() {
-                            let Some(dir) = args.get(index + 1) else {
-                                eprintln!("yoink: expected directory after -C");
-                                return ExitCode::from(2);
-                            };
-                            download_dir = Some(PathBuf::from(dir));
-                            index += 1;
-                        } else {
-                            download_dir = Some(PathBuf::from(rest));
-                        }
-                        break;
-                    }
-                    _ => {
-                        handled = false;
-                        break;
-                    }
-                }
-            }
-            if handled {
-                index += 1;
-                continue;
-            }
+        if let Some(value) = long_value(&args, &mut index, "--emit-manifest") {
+            let Ok(path) = value else {
+                return ExitCode::from(2);
+            };
+            emit_manifest = Some(PathBuf::from(path));
+            continue;
         }
-
-        eprintln!("yoink: unrecognized option {arg}");
-        print_usage();
-        return ExitCode::from(2);
-    }
-
-    let Some(repo) = args.get(index) else {
-        eprintln!("yoink: expected owner/repo as the first argument");
-        print_usage();
-        return ExitCode::from(2);
-    };
-    let rest: Vec<String> = args.iter().skip(index + 1).cloned().collect();
-
-    if !yoink::is_repo_shape(repo) {
-        eprintln!("yoink: expected owner/repo as the first argument");
-        print_usage();
-        return ExitCode::from(2);
-    }
-
-    if !rest.is_empty() {
-        if info_only || json_output || download_dir.is_some() {
-            eprintln!("yoink: -C, -j, and -I require no additional args");
-            return ExitCode::from(2);
+        if arg == "--keep-going" {
+            keep_going = true;
+            index += 1;
+            continue;
         }
-        return match yoink::run(repo, &rest) {
-            Ok(code) => {
-                let code = u8::try_from(code).unwrap_or(1);
-                ExitCode::from(code)
-            }
-            Err(err) => {
-                eprintln!("yoink: {err:?}");
-                ExitCode::from(1)
+        if let Some(value) = long_value(&args, &mut index, "--repo-file") {
+            let Ok(path) = value else {
+                return ExitCode::from(2);
+            };
+            repo_file = Some(PathBuf::from(path));
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--parallel-download") {
+            let Ok(parts) = value else {
+                return ExitCode::from(2);
+            };
+            let Ok(parts) = parts.parse::<usize>() else {
+                eprintln!("yoink: --parallel-download expects a number, got {parts}");
+                return ExitCode::from(2);
+            };
+            if parts < 2 {
+                eprintln!("yoink: --parallel-download must be at least 2");
+                return ExitCode::from(2);
             }
-        };
-    }
-
-    let cwd = match env::current_dir() {
-        Ok(dir) => dir,
-        Err(err) => {
-            eprintln!("yoink: {err:?}");
-            return ExitCode::from(1);
+            options.parallel_download = Some(parts);
+            continue;
         }
-    };
-    let download_dir = download_dir
-        .map(|dir| {
-            if dir.is_absolute() {
-                dir
-            } else {
-                cwd.join(dir)
+        if let Some(value) = long_value(&args, &mut index, "--max-parallel-repos") {
+            let Ok(jobs) = value else {
+                return ExitCode::from(2);
+            };
+            let Ok(jobs) = jobs.parse::<usize>() else {
+                eprintln!("yoink: --max-parallel-repos expects a number, got {jobs}");
+                return ExitCode::from(2);
+            };
+            if jobs == 0 {
+                eprintln!("yoink: --max-parallel-repos must be at least 1");
+                return ExitCode::from(2);
             }
-        })
-        .unwrap_or_else(|| cwd.clone());
-    let use_relative = download_dir == cwd;
-
-    if info_only {
-        match yoink::release_info(repo) {
-            Ok(info) => {
-                let payload = InfoJson {
-                    repo: format!("{}/{}", info.owner, info.name),
-                    tag: info.tag,
-                    url: info.asset_url,
-                };
-                match serde_json::to_string_pretty(&payload) {
-                    Ok(json) => println!("{json}"),
-                    Err(err) => {
-                        eprintln!("yoink: {err:?}");
-                        return ExitCode::from(1);
-                    }
+            options.max_parallel_repos = Some(jobs);
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--wait-for-release") {
+            let Ok(value) = value else {
+                return ExitCode::from(2);
+            };
+            match yoink::parse_duration(&value) {
+                Ok(wait) => options.wait_for_release = Some(wait),
+                Err(err) => {
+                    eprintln!("yoink: {err}");
+                    return ExitCode::from(2);
                 }
-                ExitCode::SUCCESS
             }
-            Err(err) => {
-                eprintln!("yoink: {err:?}");
-                ExitCode::from(1)
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--if-newer-than") {
+            let Ok(value) = value else {
+                return ExitCode::from(2);
+            };
+            match yoink::parse_duration(&value) {
+                Ok(max_age) => options.info_max_age = Some(max_age),
+                Err(err) => {
+                    eprintln!("yoink: {err}");
+                    return ExitCode::from(2);
+                }
             }
+            continue;
         }
-    } else {
-        match yoink::download_to_dir(repo, &download_dir) {
-            Ok(summary) => {
-                if json_output {
-                    let mut executables = Vec::new();
-                    for path in &summary.paths {
-                        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
-                            continue;
-                        };
-                        if executables.iter().any(|existing| existing == name) {
-                            continue;
-                        }
-                        executables.push(name.to_string());
-                    }
-                    let payload = DownloadJson {
-                        repo: summary.repo,
-                        tag: summary.tag,
-                        url: summary.url,
-                        executables,
-                    };
-                    match serde_json::to_string_pretty(&payload) {
-                        Ok(json) => println!("{json}"),
-                        Err(err) => {
-                            eprintln!("yoink: {err:?}");
-                            return ExitCode::from(1);
-                        }
-                    }
-                } else if use_relative {
-                    for path in &summary.paths {
-                        if let Ok(rel) = path.strip_prefix(&cwd) {
-                            let display = std::path::PathBuf::from(".").join(rel);
-                            println!("{}", display.display());
+        if let Some(value) = long_value(&args, &mut index, "--release-json") {
+            let Ok(source) = value else {
+                return ExitCode::from(2);
+            };
+            options.release_json = Some(source);
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--host") {
+            let Ok(host) = value else {
+                return ExitCode::from(2);
+            };
+            options.host = Some(host);
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--owner-token-map") {
+            let Ok(path) = value else {
+                return ExitCode::from(2);
+            };
+            options.owner_token_map = Some(PathBuf::from(path));
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--retry-404") {
+            let Ok(count) = value else {
+                return ExitCode::from(2);
+            };
+            let Ok(count) = count.parse::<u32>() else {
+                eprintln!("yoink: --retry-404 expects a number, got {count}");
+                return ExitCode::from(2);
+            };
+            options.network.retry_404 = Some(count);
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--retries") {
+            let Ok(count) = value else {
+                return ExitCode::from(2);
+            };
+            let Ok(count) = count.parse::<u32>() else {
+                eprintln!("yoink: --retries expects a number, got {count}");
+                return ExitCode::from(2);
+            };
+            options.network.retries = Some(count);
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--timeout") {
+            let Ok(value) = value else {
+                return ExitCode::from(2);
+            };
+            match yoink::parse_duration(&value) {
+                Ok(timeout) => options.network.timeout = Some(timeout),
+                Err(err) => {
+                    eprintln!("yoink: {err}");
+                    return ExitCode::from(2);
+                }
+            }
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--rename-map") {
+            let Ok(value) = value else {
+                return ExitCode::from(2);
+            };
+            match yoink::parse_rename_map(&value) {
+                Ok(map) => options.rename_map = map,
+                Err(err) => {
+                    eprintln!("yoink: {err}");
+                    return ExitCode::from(2);
+                }
+            }
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--digest-algo") {
+            let Ok(value) = value else {
+                return ExitCode::from(2);
+            };
+            match value.parse() {
+                Ok(algo) => options.digest_algo = algo,
+                Err(err) => {
+                    eprintln!("yoink: {err}");
+                    return ExitCode::from(2);
+                }
+            }
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--dest-exists")
+            .or_else(|| long_value(&args, &mut index, "--on-conflict"))
+        {
+            let Ok(value) = value else {
+                return ExitCode::from(2);
+            };
+            match value.parse() {
+                Ok(policy) => options.dest_exists = policy,
+                Err(err) => {
+                    eprintln!("yoink: {err}");
+                    return ExitCode::from(2);
+                }
+            }
+            continue;
+        }
+        if arg == "--quiet" {
+            options.progress = yoink::ProgressMode::None;
+            index += 1;
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--progress") {
+            let Ok(value) = value else {
+                return ExitCode::from(2);
+            };
+            match value.parse() {
+                Ok(mode) => options.progress = mode,
+                Err(err) => {
+                    eprintln!("yoink: {err}");
+                    return ExitCode::from(2);
+                }
+            }
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--arch") {
+            let Ok(arch) = value else {
+                return ExitCode::from(2);
+            };
+            options.arch = Some(arch);
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--man-dir") {
+            let Ok(dir) = value else {
+                return ExitCode::from(2);
+            };
+            options.man_dir = Some(PathBuf::from(dir));
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--temp-dir") {
+            let Ok(dir) = value else {
+                return ExitCode::from(2);
+            };
+            options.temp_dir = Some(PathBuf::from(dir));
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--install-config") {
+            let Ok(dir) = value else {
+                return ExitCode::from(2);
+            };
+            options.install_config = Some(PathBuf::from(dir));
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--config-glob") {
+            let Ok(pattern) = value else {
+                return ExitCode::from(2);
+            };
+            options.config_glob = Some(pattern);
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--bin") {
+            let Ok(bin) = value else {
+                return ExitCode::from(2);
+            };
+            options.bin = Some(bin);
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--asset") {
+            let Ok(pattern) = value else {
+                return ExitCode::from(2);
+            };
+            options.asset = Some(pattern);
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--only-os") {
+            let Ok(os) = value else {
+                return ExitCode::from(2);
+            };
+            options.only_os = Some(os);
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--only-arch") {
+            let Ok(arch) = value else {
+                return ExitCode::from(2);
+            };
+            options.only_arch = Some(arch);
+            continue;
+        }
+        if arg == "--no-verify" {
+            options.no_verify = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--ignore-arch" {
+            options.ignore_arch = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--checksum-required" {
+            options.checksum_required = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--tag-tokens" {
+            options.tag_tokens = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--pre" {
+            options.pre = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--semver-latest" {
+            options.semver_latest = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--verify-exec-format" || arg == "--no-verify-exec-format" {
+            options.no_verify_exec_format = arg == "--no-verify-exec-format";
+            index += 1;
+            continue;
+        }
+        if arg == "--cache" {
+            options.cache = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--refresh" {
+            options.refresh = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--follow-symlinks" {
+            options.follow_symlinks = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--include-hidden" {
+            options.include_hidden = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--build" {
+            options.build = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--store" {
+            options.store = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--strict" {
+            options.strict = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--keep-temp" {
+            options.keep_temp = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--fail-on-multiple-candidates" {
+            options.fail_on_multiple_candidates = true;
+            index += 1;
+            continue;
+        }
+        if arg == "--isolated" {
+            options.isolated = true;
+            index += 1;
+            continue;
+        }
+        if arg == "-C" {
+            let Some(dir) = args.get(index + 1) else {
+                eprintln!("yoink: expected directory after -C");
+                return ExitCode::from(2);
+            };
+            download_dir = Some(PathBuf::from(dir));
+            index += 2;
+            continue;
+        }
+        if let Some(value) = long_value(&args, &mut index, "--dest-dir-template") {
+            let Ok(template) = value else {
+                return ExitCode::from(2);
+            };
+            download_dir = Some(PathBuf::from(template));
+            continue;
+        }
+        if arg.starts_with("-C") && arg.len() > 2 {
+            download_dir = Some(PathBuf::from(&arg[2..]));
+            index += 1;
+            continue;
+        }
+        if arg.len() > 2 {
+            let mut chars = arg.chars();
+            chars.next();
+            let mut handled = true;
+            while let Some(ch) = chars.next() {
+                match ch {
+                    'j' => json_output = true,
+                    'I' => info_only = true,
+                    'n' => dry_run = true,
+                    'i' => action = Some(yoink::DefaultAction::Install),
+                    'C' => {
+                        let rest: String = chars.collect();
+                        if rest.is_empty() {
+                            let Some(dir) = args.get(index + 1) else {
+                                eprintln!("yoink: expected directory after -C");
+                                return ExitCode::from(2);
+                            };
+                            download_dir = Some(PathBuf::from(dir));
+                            index += 1;
                         } else {
-                            println!("{}", path.display());
+                            download_dir = Some(PathBuf::from(rest));
                         }
+                        break;
                     }
-                } else {
-                    for path in &summary.paths {
-                        println!("{}", path.display());
+                    _ => {
+                        handled = false;
+                        break;
                     }
                 }
-                ExitCode::SUCCESS
             }
-            Err(err) => {
-                eprintln!("yoink: {err:?}");
-                ExitCode::from(1)
+            if handled {
+                index += 1;
+                continue;
             }
         }
+
+        eprintln!("yoink: unrecognized option {arg}");
+        print_usage();
+        return ExitCode::from(2);
     }
-}
 
-fn print_usage() {
-    eprintln!("usage:");
-    eprintln!("  yoink [-jI] [-C dir] <owner/repo> [args...]");
-    eprintln!("  yoink --version");
-}
+    if min_version.is_some() && !skip_if_present {
+        eprintln!("yoink: --min-version requires --skip-if-present");
+        return ExitCode::from(2);
+    }
+    if print_asset_name && (info_only || notes_only || print_path || dry_run || repo_file.is_some())
+    {
+        eprintln!("yoink: --print-asset-name only applies to resolving a single repo");
+        return ExitCode::from(2);
+    }
+    if check
+        && (info_only
+            || notes_only
+            || print_path
+            || dry_run
+            || print_asset_name
+            || list_bins
+            || repo_file.is_some())
+    {
+        eprintln!("yoink: --check only applies to a single repo");
+        return ExitCode::from(2);
+    }
+    if list_bins
+        && (info_only
+            || notes_only
+            || print_path
+            || dry_run
+            || print_asset_name
+            || repo_file.is_some())
+    {
+        eprintln!("yoink: --list-bins only applies to a single repo");
+        return ExitCode::from(2);
+    }
+    if dry_run && (info_only || notes_only || print_path || repo_file.is_some()) {
+        eprintln!("yoink: --dry-run only applies to downloading a single repo");
+        return ExitCode::from(2);
+    }
+    // The check stands in for an install, so anything that isn't one can't be skipped.
+    if skip_if_present
+        && (info_only
+            || notes_only
+            || print_path
+            || dry_run
+            || check
+            || list_bins
+            || print_asset_name
+            || since_tag.is_some()
+            || options.arch.as_deref() == Some("all")
+            || repo_file.is_some()
+            || download_dir.is_some()
+            || emit_manifest.is_some())
+    {
+        eprintln!("yoink: --skip-if-present only applies to installing a single repo");
+        return ExitCode::from(2);
+    }
 
-fn print_version() {
-    println!("yoink {}", env!("CARGO_PKG_VERSION"));
-}
+    if options.info_max_age.is_some() && !info_only {
+        eprintln!("yoink: --if-newer-than only applies to -I");
+        return ExitCode::from(2);
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serial_test::serial;
-    use std::collections::BTreeMap;
-    use std::ffi::OsString;
-    use std::io::{BufRead, BufReader, Cursor, Write};
-    use std::net::TcpListener;
-    use std::path::Path;
-    use std::thread;
+    if options.ignore_arch && (options.arch.is_some() || options.only_arch.is_some()) {
+        eprintln!("yoink: --ignore-arch cannot be combined with --arch or --only-arch");
+        return ExitCode::from(2);
+    }
 
-    #[test]
-    fn no_args_returns_usage() {
-        let code = run_with_args(Vec::<String>::new());
-        assert_eq!(code, ExitCode::from(2));
+    if since_tag.is_some()
+        && (info_only || notes_only || print_path || dry_run || repo_file.is_some())
+    {
+        eprintln!("yoink: --since-tag only applies to downloading a single repo");
+        return ExitCode::from(2);
     }
 
-    #[test]
-    fn help_flag_returns_success() {
-        let code = run_with_args(vec!["-h".to_string()]);
-        assert_eq!(code, ExitCode::SUCCESS);
+    let all_arches = options.arch.as_deref() == Some("all");
+    if all_arches && (info_only || notes_only || print_path || repo_file.is_some()) {
+        eprintln!("yoink: --arch all only applies to downloading a single repo");
+        return ExitCode::from(2);
     }
 
-    #[test]
-    fn version_flag_returns_success() {
-        let code = run_with_args(vec!["--version".to_string()]);
-        assert_eq!(code, ExitCode::SUCCESS);
+    if action == Some(yoink::DefaultAction::Install)
+        && (info_only
+            || notes_only
+            || print_path
+            || dry_run
+            || print_asset_name
+            || list_bins
+            || repo_file.is_some()
+            || since_tag.is_some()
+            || all_arches
+            || download_dir.is_some()
+            || emit_manifest.is_some())
+    {
+        eprintln!("yoink: -i installs a single repo; it cannot be combined with -C, --emit-manifest, or other modes");
+        return ExitCode::from(2);
     }
+    // Naming a destination means downloading, whatever the default action.
+    let wants_download = download_dir.is_some() || emit_manifest.is_some();
 
-    #[test]
-    fn missing_directory_after_c_errors() {
-        let code = run_with_args(vec!["-C".to_string()]);
+    if options.release_json.is_some() && (notes_only || repo_file.is_some()) {
+        eprintln!("yoink: --release-json describes a single release; it cannot be combined with --notes-only or --repo-file");
+        return ExitCode::from(2);
+    }
+
+    if let Some(repo_file) = repo_file {
+        if info_only || notes_only || print_path {
+            eprintln!(
+                "yoink: --repo-file cannot be combined with -I, --notes-only, or --print-path"
+            );
+            return ExitCode::from(2);
+        }
+        let mut repos = match yoink::read_repo_file(&repo_file) {
+            Ok(repos) => repos,
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                return ExitCode::from(1);
+            }
+        };
+        repos.extend(args.iter().skip(index).cloned());
+        if repos.is_empty() {
+            eprintln!("yoink: no repos listed in {}", repo_file.display());
+            return ExitCode::from(2);
+        }
+        if let Some(repo) = repos
+            .iter()
+            .find(|repo| !yoink::is_repo_shape_with(repo, &options))
+        {
+            eprintln!("yoink: expected owner/repo, got {repo}");
+            return ExitCode::from(2);
+        }
+        let (cwd, download_dir) = match resolve_download_dir(download_dir) {
+            Ok(dirs) => dirs,
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                return ExitCode::from(1);
+            }
+        };
+        let use_relative = !plain_paths && download_dir == cwd;
+        return download_batch(
+            &repos,
+            &download_dir,
+            &cwd,
+            use_relative,
+            &options,
+            json_output,
+            json_verbose,
+            keep_going,
+            emit_manifest.as_deref(),
+        );
+    }
+
+    let Some(repo) = args.get(index) else {
+        eprintln!("yoink: expected owner/repo as the first argument");
+        print_usage();
+        return ExitCode::from(2);
+    };
+    let rest: Vec<String> = args.iter().skip(index + 1).cloned().collect();
+
+    if !yoink::is_repo_shape_with(repo, &options) {
+        eprintln!("yoink: expected owner/repo as the first argument");
+        print_usage();
+        return ExitCode::from(2);
+    }
+
+    if !rest.is_empty() {
+        if info_only || json_output || notes_only || print_path || download_dir.is_some() {
+            eprintln!(
+                "yoink: -C, -j, -I, --notes-only, and --print-path require no additional args"
+            );
+            return ExitCode::from(2);
+        }
+        if all_arches {
+            eprintln!("yoink: --arch all only applies to downloading a single repo");
+            return ExitCode::from(2);
+        }
+        if skip_if_present {
+            eprintln!("yoink: --skip-if-present only applies to installing a single repo");
+            return ExitCode::from(2);
+        }
+        return match yoink::run_with(repo, &rest, &options) {
+            Ok(code) => {
+                let code = u8::try_from(code).unwrap_or(1);
+                ExitCode::from(code)
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if notes_only {
+        return match yoink::release_notes_since_installed(repo) {
+            Ok(notes) => {
+                if json_output {
+                    let payload: Vec<NotesJson> = notes
+                        .into_iter()
+                        .map(|notes| NotesJson {
+                            tag: notes.tag,
+                            body: notes.body,
+                        })
+                        .collect();
+                    return print_json(&payload);
+                } else {
+                    for (i, notes) in notes.iter().enumerate() {
+                        if i > 0 {
+                            println!();
+                        }
+                        println!("## {}", notes.tag);
+                        println!();
+                        println!("{}", notes.body.trim_end());
+                    }
+                }
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if print_path {
+        let path = match &download_dir {
+            Some(dir) => yoink::destination_path_with(repo, dir, &options),
+            None => yoink::install_path_with(repo, &options),
+        };
+        return match path {
+            Ok(path) => {
+                println!("{}", path.display());
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    let (cwd, download_dir) = match resolve_download_dir(download_dir) {
+        Ok(dirs) => dirs,
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            return ExitCode::from(1);
+        }
+    };
+    let use_relative = !plain_paths && download_dir == cwd;
+
+    if skip_if_present {
+        match resolve_action(action, wants_download) {
+            Ok(yoink::DefaultAction::Install) => {}
+            Ok(yoink::DefaultAction::Download) => {
+                eprintln!("yoink: --skip-if-present only applies to installing a single repo");
+                return ExitCode::from(2);
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                return ExitCode::from(1);
+            }
+        }
+        match yoink::present_on_path_with(repo, min_version.as_deref(), &options) {
+            Ok(Some(path)) => {
+                eprintln!(
+                    "yoink: {repo} is already present at {}; skipping",
+                    path.display()
+                );
+                println!("{}", path.display());
+                return ExitCode::SUCCESS;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                return ExitCode::from(1);
+            }
+        }
+    }
+
+    if check {
+        return match yoink::check_repo(repo, &options) {
+            Ok(yoink::RepoStatus::HasRelease) => {
+                println!("{repo}: has a latest release");
+                ExitCode::SUCCESS
+            }
+            Ok(yoink::RepoStatus::NoRelease) => {
+                println!("{repo}: exists but has no release");
+                ExitCode::from(3)
+            }
+            Ok(yoink::RepoStatus::NotFound) => {
+                println!("{repo}: not found");
+                ExitCode::from(4)
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if list_bins {
+        return match yoink::list_bins(repo, &options) {
+            Ok(bins) => {
+                if json_output {
+                    return print_json(&bins_json(bins));
+                }
+                println!("{} (primary)", bins.primary.display());
+                for extra in &bins.extras {
+                    println!("{}", extra.display());
+                }
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if print_asset_name {
+        return match yoink::release_info_with(repo, &options) {
+            Ok(info) => {
+                println!("{}", info.asset_name);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if dry_run {
+        let action = match resolve_action(action, wants_download) {
+            Ok(action) => action,
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                return ExitCode::from(1);
+            }
+        };
+        let plan = yoink::dry_run(repo, &download_dir, &options).and_then(|plan| {
+            if action == yoink::DefaultAction::Install {
+                let destination = yoink::install_path_with(repo, &options)?;
+                return Ok((plan, destination.display().to_string()));
+            }
+            let destination =
+                display_paths(std::slice::from_ref(&plan.destination), &cwd, use_relative);
+            Ok((plan, destination[0].clone()))
+        });
+        return match plan {
+            Ok((plan, destination)) => {
+                println!("tag: {}", plan.info.tag);
+                println!("asset: {}", plan.info.asset_name);
+                println!("destination: {destination}");
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if info_only {
+        match yoink::release_info_with(repo, &options) {
+            Ok(info) => {
+                let payload = InfoJson {
+                    repo: format!("{}/{}", info.owner, info.name),
+                    tag: info.tag,
+                    url: info.asset_url,
+                };
+                print_json(&payload)
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        }
+    } else {
+        if let Some(since) = &since_tag {
+            let result = yoink::download_since_tag(repo, since, &download_dir, &options);
+            return report_downloads(
+                result,
+                emit_manifest.as_deref(),
+                json_output,
+                json_verbose,
+                &cwd,
+                use_relative,
+            );
+        }
+        if all_arches {
+            options.arch = None;
+            let result = yoink::download_all_arches(repo, &download_dir, &options);
+            return report_downloads(
+                result,
+                emit_manifest.as_deref(),
+                json_output,
+                json_verbose,
+                &cwd,
+                use_relative,
+            );
+        }
+        let action = match resolve_action(action, wants_download) {
+            Ok(action) => action,
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                return ExitCode::from(1);
+            }
+        };
+        if action == yoink::DefaultAction::Install {
+            return match yoink::install_with_report(repo, &options) {
+                Ok(report) => {
+                    warn_install(&report);
+                    if json_output {
+                        return print_json(&install_json(report));
+                    }
+                    println!("{}", report.path.display());
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("yoink: {}", ErrorLine(&err));
+                    ExitCode::from(1)
+                }
+            };
+        }
+        match yoink::download_to_dir_with(repo, &download_dir, &options) {
+            Ok(summary) => {
+                warn_all(&summary.warnings);
+                if write_manifest(emit_manifest.as_deref(), std::slice::from_ref(&summary))
+                    != ExitCode::SUCCESS
+                {
+                    return ExitCode::from(1);
+                }
+                if json_output {
+                    return print_json(&download_json(summary, json_verbose));
+                }
+                print_paths(&summary.paths, &cwd, use_relative);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("yoink: {}", ErrorLine(&err));
+                ExitCode::from(1)
+            }
+        }
+    }
+}
+
+/// Matches `--name value` or `--name=value`, advancing `index` past the consumed arguments.
+fn long_value(args: &[String], index: &mut usize, name: &str) -> Option<Result<String, ()>> {
+    let arg = &args[*index];
+    if arg == name {
+        let Some(value) = args.get(*index + 1) else {
+            eprintln!("yoink: expected value after {name}");
+            return Some(Err(()));
+        };
+        *index += 2;
+        return Some(Ok(value.clone()));
+    }
+    let value = arg.strip_prefix(name)?.strip_prefix('=')?;
+    *index += 1;
+    Some(Ok(value.to_string()))
+}
+
+fn run_run(args: &[String]) -> ExitCode {
+    let mut options = yoink::Options {
+        progress: yoink::ProgressMode::auto(),
+        ..yoink::Options::default()
+    };
+    let mut index = 0;
+    while index < args.len() && args[index].starts_with('-') {
+        if let Some(value) = long_value(args, &mut index, "--digest-algo") {
+            let Ok(value) = value else {
+                return ExitCode::from(2);
+            };
+            match value.parse() {
+                Ok(algo) => options.digest_algo = algo,
+                Err(err) => {
+                    eprintln!("yoink: {err}");
+                    return ExitCode::from(2);
+                }
+            }
+            continue;
+        }
+        if let Some(value) = long_value(args, &mut index, "--lockfile") {
+            let Ok(path) = value else {
+                return ExitCode::from(2);
+            };
+            options.lockfile = Some(PathBuf::from(path));
+            continue;
+        }
+        if args[index] == "--checksum-required" {
+            options.checksum_required = true;
+            index += 1;
+            continue;
+        }
+        if let Some(value) = long_value(args, &mut index, "--arch") {
+            let Ok(arch) = value else {
+                return ExitCode::from(2);
+            };
+            options.arch = Some(arch);
+            continue;
+        }
+        if let Some(value) = long_value(args, &mut index, "--asset") {
+            let Ok(pattern) = value else {
+                return ExitCode::from(2);
+            };
+            options.asset = Some(pattern);
+            continue;
+        }
+        if let Some(value) = long_value(args, &mut index, "--bin") {
+            let Ok(name) = value else {
+                return ExitCode::from(2);
+            };
+            options.bin = Some(name);
+            continue;
+        }
+        eprintln!("yoink: unrecognized run option {}", args[index]);
+        return ExitCode::from(2);
+    }
+    let Some(repo) = args.get(index) else {
+        eprintln!("yoink: run expects owner/repo");
+        return ExitCode::from(2);
+    };
+    if !yoink::is_repo_shape_with(repo, &options) {
+        eprintln!("yoink: expected owner/repo, got {repo}");
+        return ExitCode::from(2);
+    }
+    let mut rest = &args[index + 1..];
+    if rest.first().map(String::as_str) == Some("--") {
+        rest = &rest[1..];
+    }
+    match yoink::run_with(repo, rest, &options) {
+        Ok(code) => ExitCode::from(u8::try_from(code).unwrap_or(1)),
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn run_which(args: &[String]) -> ExitCode {
+    let mut json_output = false;
+    let mut all = false;
+    let mut rest = args;
+    while let [flag, tail @ ..] = rest {
+        match flag.as_str() {
+            "-j" => json_output = true,
+            "-a" => all = true,
+            _ => break,
+        }
+        rest = tail;
+    }
+    let repo = match rest {
+        [repo] if !repo.starts_with('-') => repo,
+        _ => {
+            eprintln!("yoink: which expects [-j] [-a] <owner/repo>");
+            return ExitCode::from(2);
+        }
+    };
+    match yoink::installed_bins(repo) {
+        Ok(installed) => {
+            if json_output {
+                return print_json(&which_json(installed));
+            }
+            if !all {
+                println!("{}", installed.primary.display());
+                return ExitCode::SUCCESS;
+            }
+            for bin in &installed.bins {
+                println!("{}", bin.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn run_ensure(args: &[String]) -> ExitCode {
+    let [repo] = args else {
+        eprintln!("yoink: ensure expects exactly one <owner/repo@version>");
+        return ExitCode::from(2);
+    };
+    let options = yoink::Options {
+        progress: yoink::ProgressMode::auto(),
+        ..yoink::Options::default()
+    };
+    match yoink::ensure(repo, &options) {
+        Ok(report) => {
+            let install = report.install;
+            warn_install(&install);
+            let path = install.path.display();
+            match report.action {
+                yoink::EnsureAction::Unchanged => {
+                    println!(
+                        "{} {} already installed at {path}",
+                        install.repo, install.version
+                    )
+                }
+                yoink::EnsureAction::Installed => {
+                    println!("installed {} {} at {path}", install.repo, install.version)
+                }
+                yoink::EnsureAction::Replaced { from } => println!(
+                    "replaced {} {from} with {} at {path}",
+                    install.repo, install.version
+                ),
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn run_rollback(args: &[String]) -> ExitCode {
+    let [repo] = args else {
+        eprintln!("yoink: rollback expects exactly one <owner/repo>");
+        return ExitCode::from(2);
+    };
+    let options = yoink::Options {
+        progress: yoink::ProgressMode::auto(),
+        ..yoink::Options::default()
+    };
+    match yoink::rollback(repo, &options) {
+        Ok(report) => {
+            warn_install(&report);
+            println!(
+                "rolled back {} to {} at {}",
+                report.repo,
+                report.version,
+                report.path.display()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn run_doctor(args: &[String]) -> ExitCode {
+    let fix = match args {
+        [] => false,
+        [flag] if flag == "--fix" => true,
+        _ => {
+            eprintln!("yoink: doctor expects [--fix]");
+            return ExitCode::from(2);
+        }
+    };
+    let stale = match yoink::doctor(fix) {
+        Ok(stale) => stale,
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            return ExitCode::from(1);
+        }
+    };
+    for install in &stale {
+        for bin in &install.missing {
+            println!("{}: missing {}", install.repo, bin.display());
+        }
+        for bin in &install.not_executable {
+            println!("{}: not executable {}", install.repo, bin.display());
+        }
+        if install.pruned {
+            println!("{}: removed from install state", install.repo);
+        }
+    }
+    if stale.iter().all(|install| install.pruned) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
+
+fn run_uninstall(args: &[String]) -> ExitCode {
+    let (prune_store, target) = match args {
+        [flag, target] if flag == "--keep-store" => (false, target),
+        [target] => (true, target),
+        _ => {
+            eprintln!("yoink: uninstall expects [--keep-store] <owner/repo|bin>");
+            return ExitCode::from(2);
+        }
+    };
+    match yoink::uninstall_with(target, prune_store) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn bins_json(bins: yoink::ArchiveBins) -> BinsJson {
+    let listed = |path: &Path, primary| ListedBinJson {
+        path: path.display().to_string(),
+        primary,
+    };
+    BinsJson {
+        repo: bins.repo,
+        tag: bins.tag,
+        asset: bins.asset_name,
+        binaries: std::iter::once(listed(&bins.primary, true))
+            .chain(bins.extras.iter().map(|extra| listed(extra, false)))
+            .collect(),
+    }
+}
+
+fn install_json(report: yoink::InstallReport) -> InstallJson {
+    InstallJson {
+        repo: report.repo,
+        version: report.version,
+        path: report.path.display().to_string(),
+        used_sudo: report.used_sudo,
+        path_warning: report.path_warning,
+        warnings: report.warnings,
+    }
+}
+
+fn which_json(installed: yoink::InstalledBins) -> WhichJson {
+    WhichJson {
+        repo: installed.repo,
+        primary: installed.primary.display().to_string(),
+        bins: installed
+            .bins
+            .iter()
+            .map(|bin| bin.display().to_string())
+            .collect(),
+    }
+}
+
+fn run_list(args: &[String]) -> ExitCode {
+    let mut json_output = false;
+    let mut sort = yoink::ListSort::default();
+    let mut index = 0;
+    while index < args.len() {
+        if args[index] == "-j" {
+            json_output = true;
+            index += 1;
+            continue;
+        }
+        if let Some(value) = long_value(args, &mut index, "--sort") {
+            let Ok(value) = value else {
+                return ExitCode::from(2);
+            };
+            match value.parse() {
+                Ok(parsed) => sort = parsed,
+                Err(err) => {
+                    eprintln!("yoink: {err}");
+                    return ExitCode::from(2);
+                }
+            }
+            continue;
+        }
+        eprintln!("yoink: unrecognized list argument {}", args[index]);
+        return ExitCode::from(2);
+    }
+
+    let mut installs = match yoink::list_installs() {
+        Ok(installs) => installs,
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            return ExitCode::from(1);
+        }
+    };
+    yoink::sort_installs(&mut installs, sort);
+    if json_output {
+        let payload: Vec<ListJson> = installs
+            .into_iter()
+            .map(|install| ListJson {
+                repo: install.repo,
+                version: install.version,
+                installed_at: install.installed_at,
+                bin: install.bin.display().to_string(),
+                bins: install
+                    .bins
+                    .iter()
+                    .map(|bin| bin.display().to_string())
+                    .collect(),
+            })
+            .collect();
+        return print_json(&payload);
+    }
+    for install in installs {
+        println!("{} {}", install.repo, install.version);
+    }
+    ExitCode::SUCCESS
+}
+
+/// What a bare `yoink owner/repo` does: `-i`/`--download` when given, downloading when a
+/// destination is named, else the configured default action.
+fn resolve_action(
+    action: Option<yoink::DefaultAction>,
+    wants_download: bool,
+) -> Result<yoink::DefaultAction, yoink::YoinkError> {
+    match action {
+        Some(action) => Ok(action),
+        None if wants_download => Ok(yoink::DefaultAction::Download),
+        None => yoink::default_action(),
+    }
+}
+
+fn resolve_download_dir(download_dir: Option<PathBuf>) -> std::io::Result<(PathBuf, PathBuf)> {
+    let cwd = env::current_dir()?;
+    let download_dir = download_dir
+        .map(|dir| {
+            if dir.is_absolute() {
+                dir
+            } else {
+                cwd.join(dir)
+            }
+        })
+        .unwrap_or_else(|| cwd.clone());
+    Ok((cwd, download_dir))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn download_batch(
+    repos: &[String],
+    download_dir: &Path,
+    cwd: &Path,
+    use_relative: bool,
+    options: &yoink::Options,
+    json_output: bool,
+    json_verbose: bool,
+    keep_going: bool,
+    emit_manifest: Option<&Path>,
+) -> ExitCode {
+    let mut summaries = Vec::new();
+    let mut failed = Vec::new();
+    let mut downloaded = |summary: yoink::DownloadSummary| {
+        warn_all(&summary.warnings);
+        if !json_output {
+            print_paths(&summary.paths, cwd, use_relative);
+        }
+        summaries.push(summary);
+    };
+    if keep_going {
+        for (repo, result) in repos
+            .iter()
+            .zip(yoink::download_many(repos, download_dir, options))
+        {
+            match result {
+                Ok(summary) => downloaded(summary),
+                Err(err) => {
+                    eprintln!("yoink: {repo}: {}", ErrorLine(&err));
+                    failed.push(repo.as_str());
+                }
+            }
+        }
+    } else {
+        // Failing fast means later repos never start, so go one at a time.
+        for repo in repos {
+            match yoink::download_to_dir_with(repo, download_dir, options) {
+                Ok(summary) => downloaded(summary),
+                Err(err) => {
+                    eprintln!("yoink: {repo}: {}", ErrorLine(&err));
+                    return ExitCode::from(1);
+                }
+            }
+        }
+    }
+
+    if write_manifest(emit_manifest, &summaries) != ExitCode::SUCCESS {
+        return ExitCode::from(1);
+    }
+    if json_output {
+        let payloads: Vec<DownloadJson> = summaries
+            .into_iter()
+            .map(|summary| download_json(summary, json_verbose))
+            .collect();
+        if print_json(&payloads) != ExitCode::SUCCESS {
+            return ExitCode::from(1);
+        }
+    }
+    if keep_going {
+        eprintln!(
+            "yoink: {} succeeded, {} failed",
+            repos.len() - failed.len(),
+            failed.len()
+        );
+        for repo in &failed {
+            eprintln!("yoink: failed: {repo}");
+        }
+    }
+    if failed.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
+
+/// Prints a multi-download result as paths or JSON, writing `--emit-manifest` if given.
+fn report_downloads(
+    result: Result<Vec<yoink::DownloadSummary>, yoink::YoinkError>,
+    emit_manifest: Option<&Path>,
+    json_output: bool,
+    json_verbose: bool,
+    cwd: &Path,
+    use_relative: bool,
+) -> ExitCode {
+    let summaries = match result {
+        Ok(summaries) => summaries,
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            return ExitCode::from(1);
+        }
+    };
+    for summary in &summaries {
+        warn_all(&summary.warnings);
+    }
+    if write_manifest(emit_manifest, &summaries) != ExitCode::SUCCESS {
+        return ExitCode::from(1);
+    }
+    if json_output {
+        let payload: Vec<DownloadJson> = summaries
+            .into_iter()
+            .map(|summary| download_json(summary, json_verbose))
+            .collect();
+        return print_json(&payload);
+    }
+    for summary in &summaries {
+        print_paths(&summary.paths, cwd, use_relative);
+    }
+    ExitCode::SUCCESS
+}
+
+/// Writes `--emit-manifest` when given; reports and fails on a write error.
+fn write_manifest(path: Option<&Path>, summaries: &[yoink::DownloadSummary]) -> ExitCode {
+    let Some(path) = path else {
+        return ExitCode::SUCCESS;
+    };
+    match yoink::emit_manifest(path, summaries) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn download_json(summary: yoink::DownloadSummary, verbose: bool) -> DownloadJson {
+    let mut executables = Vec::new();
+    for path in &summary.paths {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if executables.iter().any(|existing| existing == name) {
+            continue;
+        }
+        executables.push(name.to_string());
+    }
+    let binaries = verbose.then(|| {
+        summary
+            .paths
+            .iter()
+            .zip(&summary.sources)
+            .map(|(path, source)| BinaryJson {
+                path: path.display().to_string(),
+                source: source.display().to_string(),
+                size: std::fs::metadata(path).map_or(0, |metadata| metadata.len()),
+                primary: *path == summary.primary_path,
+            })
+            .collect()
+    });
+    DownloadJson {
+        repo: summary.repo,
+        tag: summary.tag,
+        url: summary.url,
+        executables,
+        binaries,
+    }
+}
+
+fn print_json<T: Serialize>(payload: &T) -> ExitCode {
+    match serde_json::to_string_pretty(payload) {
+        Ok(json) => {
+            println!("{json}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("yoink: {}", ErrorLine(&err));
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn warn_install(report: &yoink::InstallReport) {
+    warn_all(&report.warnings);
+    if let Some(warning) = &report.path_warning {
+        eprintln!("yoink: {warning}");
+    }
+}
+
+fn warn_all(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("yoink: warning: {warning}");
+    }
+}
+
+fn print_paths(paths: &[PathBuf], cwd: &Path, use_relative: bool) {
+    for path in display_paths(paths, cwd, use_relative) {
+        println!("{path}");
+    }
+}
+
+fn display_paths(paths: &[PathBuf], cwd: &Path, use_relative: bool) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| match path.strip_prefix(cwd) {
+            Ok(rel) if use_relative => PathBuf::from(".").join(rel).display().to_string(),
+            _ => path.display().to_string(),
+        })
+        .collect()
+}
+
+/// JSON Schema documents for every `-j` output shape, keyed by the mode that emits them.
+fn json_schemas() -> serde_json::Value {
+    serde_json::json!({
+        "download": schemars::schema_for!(DownloadJson),
+        "install": schemars::schema_for!(InstallJson),
+        "info": schemars::schema_for!(InfoJson),
+        "list": schemars::schema_for!(Vec<ListJson>),
+        "which": schemars::schema_for!(WhichJson),
+        "list-bins": schemars::schema_for!(BinsJson),
+        "notes": schemars::schema_for!(Vec<NotesJson>),
+    })
+}
+
+fn print_usage() {
+    eprintln!("usage:");
+    eprintln!("  yoink [-jI] [--json-verbose] [-C dir] [--plain-paths] [--no-verify] [--checksum-required] [--no-verify-exec-format] [--strict] [--skip-if-present [--min-version v]] [--arch arch|all] [--ignore-arch] [--only-os os] [--only-arch arch] [--tag-tokens] [--asset glob] [--bin name] [--follow-symlinks] [--include-hidden] [--build] [--store] [--cache [--refresh]] [--install-config dir [--config-glob glob]] [--man-dir dir] [--parallel-download n] [--fail-on-multiple-candidates] [--semver-latest] [--pre] [--print-asset-name] [--list-bins] [--dest-exists|--on-conflict error|overwrite|skip|backup|rename] [--keep-temp] [--temp-dir dir] [--rename-map from=to,...] [--emit-manifest path] [--digest-algo sha256|sha512] [--release-json url|-] [--owner-token-map path] [--host github.com|gitlab.com|host] [--wait-for-release duration] [--timeout duration] [--retries n] [--retry-404 n] [--progress none|bar|dots] [--quiet] [--isolated] <[gitlab:]owner/repo[@tag]> [args...]");
+    eprintln!("  yoink -i|--install|--download [-j] <owner/repo[@tag]>");
+    eprintln!("  YOINK_DEFAULT_ACTION=download|install (or default-action in config.toml) sets what a bare yoink owner/repo does");
+    eprintln!("  yoink [-j] [-C dir] [--keep-going] [--max-parallel-repos n] [--emit-manifest path] --repo-file <path> [owner/repo...]");
+    eprintln!("  yoink -I [-j] [--if-newer-than duration] <owner/repo>");
+    eprintln!("  yoink --print-path [-C dir] [--bin name] <owner/repo>");
+    eprintln!(
+        "  yoink --check <owner/repo>   (exit 0: has a release, 3: no release, 4: not found)"
+    );
+    eprintln!("  yoink -n|--dry-run [-C dir] <owner/repo>");
+    eprintln!("  yoink [-j] [-C dir] --since-tag <tag> <owner/repo>");
+    eprintln!(
+        "  -C and --dest-dir-template expand {{os}}, {{arch}}, {{version}}, {{tag}}, and {{repo}}"
+    );
+    eprintln!("  yoink --notes-only [-j] <owner/repo>");
+    eprintln!(
+        "  yoink run [--lockfile path] [--checksum-required] [--digest-algo sha256|sha512] [--arch arch] [--asset glob] [--bin name] <owner/repo> [-- args...]"
+    );
+    eprintln!("  yoink which [-j] [-a] <owner/repo>");
+    eprintln!("  yoink ensure <owner/repo@version>");
+    eprintln!("  yoink rollback <owner/repo>");
+    eprintln!("  yoink uninstall [--keep-store] <owner/repo|bin>");
+    eprintln!("  yoink doctor [--fix]");
+    eprintln!("  yoink list [-j] [--sort name|version|installed-at]");
+    eprintln!("  yoink migrate-dir <dir>");
+    eprintln!("  yoink export");
+    eprintln!("  yoink prune-cache");
+    eprintln!("  yoink --path-setup [bash|zsh|fish]");
+    eprintln!("  yoink --self-test");
+    eprintln!("  yoink --version");
+    eprintln!("  --quiet-errors prints each error as a single line");
+    eprintln!("  --quiet hides download progress");
+}
+
+fn print_version() {
+    println!("yoink {}", env!("CARGO_PKG_VERSION"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::collections::BTreeMap;
+    use std::ffi::OsString;
+    use std::io::{BufRead, BufReader, Cursor, Write};
+    use std::net::TcpListener;
+    use std::path::Path;
+    use std::thread;
+
+    #[test]
+    fn no_args_returns_usage() {
+        let code = run_with_args(Vec::<String>::new());
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn help_flag_returns_success() {
+        let code = run_with_args(vec!["-h".to_string()]);
+        assert_eq!(code, ExitCode::SUCCESS);
+    }
+
+    #[test]
+    fn version_flag_returns_success() {
+        let code = run_with_args(vec!["--version".to_string()]);
+        assert_eq!(code, ExitCode::SUCCESS);
+    }
+
+    #[test]
+    fn missing_directory_after_c_errors() {
+        let code = run_with_args(vec!["-C".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn inline_c_sets_download_dir() {
+        let code = run_with_args(vec!["-Ctmp".to_string(), "bad".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn invalid_repo_shape_errors() {
+        let code = run_with_args(vec!["not-a-repo".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn missing_repo_after_flags_errors() {
+        let code = run_with_args(vec!["-j".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn unknown_option_errors() {
+        let code = run_with_args(vec!["-Z".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn combined_flags_unknown_option_errors() {
+        let code = run_with_args(vec!["-jX".to_string(), "bad".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn combined_flags_reject_extra_args() {
+        let code = run_with_args(vec![
+            "-jI".to_string(),
+            "mxcl/tool".to_string(),
+            "extra".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn combined_flags_missing_dir_errors() {
+        let code = run_with_args(vec!["-jC".to_string(), "mxcl/tool".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn combined_flags_inline_dir_sets_download_dir() {
+        let code = run_with_args(vec!["-jCtmp".to_string(), "bad".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn dash_dash_stops_option_parsing() {
+        let code = run_with_args(vec!["--".to_string(), "bad".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    #[serial]
+    fn info_only_uses_release_info() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec!["-I".to_string(), "mxcl/tool".to_string()]);
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn info_only_reports_errors() {
+        let server = TestServer::new(|_base| {
+            let mut responses = BTreeMap::new();
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                b"not-json".to_vec(),
+            );
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec!["-I".to_string(), "mxcl/tool".to_string()]);
+        assert_eq!(code, ExitCode::from(1));
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn json_download_writes_to_directory() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
+
+        let dest = tempfile::tempdir().expect("temp dir");
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec![
+            "-j".to_string(),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert!(dest.path().join("tool").exists());
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn json_verbose_lists_binary_sources() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool.zip\",\"browser_download_url\":\"{base}/download/tool.zip\"}}]}}"
+            );
+            let zip = make_zip_bytes(&[
+                ("tool-1.0/bin/tool", b"bin"),
+                ("tool-1.0/bin/helper", b"help"),
+            ]);
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool.zip".to_string(), zip);
+            responses
+        });
+
+        let dest = tempfile::tempdir().expect("temp dir");
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let summary = yoink::download_to_dir("mxcl/tool", dest.path()).expect("download");
+        server.finish();
+
+        let json = serde_json::to_value(download_json(summary, true)).expect("encode json");
+        let binaries = json["binaries"].as_array().expect("binaries");
+        assert_eq!(binaries.len(), 2);
+        assert_eq!(binaries[0]["source"], "tool-1.0/bin/tool");
+        assert_eq!(binaries[0]["primary"], true);
+        assert_eq!(binaries[0]["size"], 3);
+        assert_eq!(binaries[1]["source"], "tool-1.0/bin/helper");
+        assert_eq!(binaries[1]["primary"], false);
+        assert_eq!(binaries[1]["size"], 4);
+    }
+
+    #[test]
+    #[serial]
+    fn json_output_skips_duplicate_executables() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool.zip");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool.zip\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let zip = make_zip_bytes(&[("bin/tool", b"bin"), ("alt/tool", b"bin")]);
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool.zip".to_string(), zip);
+            responses
+        });
+
+        let dest = tempfile::tempdir().expect("temp dir");
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec![
+            "-j".to_string(),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn download_relative_paths_use_cwd() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
+
+        let cwd = tempfile::tempdir().expect("temp dir");
+        let _cwd_guard = DirGuard::set(cwd.path());
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec!["mxcl/tool".to_string()]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert!(cwd.path().join("tool").exists());
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn plain_paths_prints_absolute_paths_in_cwd() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
+
+        let cwd = tempfile::tempdir().expect("temp dir");
+        let _cwd_guard = DirGuard::set(cwd.path());
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec!["--plain-paths".to_string(), "mxcl/tool".to_string()]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert!(cwd.path().join("tool").exists());
+
+        let tool = cwd.path().join("tool");
+        assert_eq!(
+            display_paths(std::slice::from_ref(&tool), cwd.path(), false),
+            [tool.display().to_string()]
+        );
+        assert_eq!(
+            display_paths(&[tool], cwd.path(), true),
+            [PathBuf::from(".").join("tool").display().to_string()]
+        );
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn relative_c_dir_joins_cwd() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
+
+        let cwd = tempfile::tempdir().expect("temp dir");
+        let _cwd_guard = DirGuard::set(cwd.path());
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec![
+            "-C".to_string(),
+            "bin".to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert!(cwd.path().join("bin").join("tool").exists());
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn run_branch_executes_binary() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            let script = b"#!/bin/sh\nexit 3\n";
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), script.to_vec());
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec!["mxcl/tool".to_string(), "arg".to_string()]);
+        assert_eq!(code, ExitCode::from(3));
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn run_verb_forwards_args_and_exit_code() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}},{{\"name\":\"tool.txt\",\"browser_download_url\":\"{base}/download/tool.txt\"}}]}}"
+            );
+            let script = b"#!/bin/sh\n[ \"$1\" = --flag ] && [ \"$2\" = x ] && exit 7\nexit 1\n";
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses.insert("/download/tool".to_string(), script.to_vec());
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec![
+            "run".to_string(),
+            "--asset".to_string(),
+            "tool".to_string(),
+            "mxcl/tool".to_string(),
+            "--".to_string(),
+            "--flag".to_string(),
+            "x".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(7));
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn run_branch_reports_errors() {
+        let server = TestServer::new(|_base| {
+            let mut responses = BTreeMap::new();
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                b"not-json".to_vec(),
+            );
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec!["mxcl/tool".to_string(), "arg".to_string()]);
+        assert_eq!(code, ExitCode::from(1));
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn download_to_dir_reports_errors() {
+        let server = TestServer::new(|_base| {
+            let mut responses = BTreeMap::new();
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                b"not-json".to_vec(),
+            );
+            responses
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec!["mxcl/tool".to_string()]);
+        assert_eq!(code, ExitCode::from(1));
+
+        server.finish();
+    }
+
+    #[test]
+    fn notes_only_rejects_extra_args() {
+        let code = run_with_args(vec![
+            "--notes-only".to_string(),
+            "mxcl/tool".to_string(),
+            "extra".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    #[serial]
+    fn dry_run_resolves_without_downloading() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                body.into_bytes(),
+            );
+            responses
+        });
+
+        let dest = tempfile::tempdir().expect("temp dir");
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec![
+            "-n".to_string(),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert!(!dest.path().join("tool").exists());
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn store_flag_links_install_into_store() {
+        let server = TestServer::sequence(|base| {
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
+            );
+            vec![
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    200,
+                    body.into_bytes(),
+                ),
+                ("/download/tool".to_string(), 200, b"bin".to_vec()),
+            ]
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _path_guard = EnvGuard::set("YOINK_NO_PATH_CHECK", "1");
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+
+        let code = run_with_args(vec![
+            "-i".to_string(),
+            "--store".to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        let installed = bin.path().join("tool");
+        assert_eq!(
+            std::fs::read_link(&installed).expect("store symlink"),
+            home.path()
+                .join("yoink")
+                .join("store")
+                .join("mxcl")
+                .join("tool")
+                .join("v1.0.0")
+                .join("tool")
+        );
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn default_action_install_records_state() {
+        let server = TestServer::sequence(|base| {
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{base}/download/tool\"}}]}}"
+            );
+            vec![
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    200,
+                    body.into_bytes(),
+                ),
+                ("/download/tool".to_string(), 200, b"bin".to_vec()),
+            ]
+        });
+
+        let home = tempfile::tempdir().expect("temp dir");
+        let bin = tempfile::tempdir().expect("bin dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", bin.path());
+        let _path_guard = EnvGuard::set("YOINK_NO_PATH_CHECK", "1");
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let _action_guard = EnvGuard::set("YOINK_DEFAULT_ACTION", "install");
+
+        let code = run_with_args(vec!["mxcl/tool".to_string()]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        let installed = yoink::installed_bins("mxcl/tool").expect("install recorded");
+        assert_eq!(installed.primary, bin.path().join("tool"));
+        assert!(installed.primary.is_file());
+
+        let code = run_with_args(vec![
+            "-i".to_string(),
+            "-C".to_string(),
+            bin.path().display().to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(2));
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn list_bins_reports_every_probable_binary() {
+        let server = TestServer::sequence(|base| {
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"uv.zip\",\"browser_download_url\":\"{base}/download/uv.zip\"}}]}}"
+            );
+            let zip = make_zip_bytes(&[
+                ("uv-1.0/uv", b"bin"),
+                ("uv-1.0/uvx", b"bin"),
+                ("uv-1.0/README.md", b"docs"),
+            ]);
+            let latest = "/repos/astral-sh/uv/releases/latest".to_string();
+            let download = "/download/uv.zip".to_string();
+            vec![
+                (latest.clone(), 200, body.clone().into_bytes()),
+                (download.clone(), 200, zip.clone()),
+                (latest, 200, body.into_bytes()),
+                (download, 200, zip),
+            ]
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let dest = tempfile::tempdir().expect("temp dir");
+        let code = run_with_args(vec![
+            "--list-bins".to_string(),
+            "-j".to_string(),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+            "astral-sh/uv".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(
+            std::fs::read_dir(dest.path()).expect("read dest").count(),
+            0
+        );
+
+        let bins = yoink::list_bins("astral-sh/uv", &yoink::Options::default()).expect("list");
+        let json = serde_json::to_value(bins_json(bins)).expect("encode json");
+        assert_eq!(
+            json["binaries"],
+            serde_json::json!([
+                {"path": Path::new("uv-1.0").join("uv").display().to_string(), "primary": true},
+                {"path": Path::new("uv-1.0").join("uvx").display().to_string(), "primary": false},
+            ])
+        );
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn print_asset_name_honors_os_filter() {
+        let server = TestServer::sequence(|base| {
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool-darwin-amd64.tar.gz\",\"browser_download_url\":\"{base}/download/darwin\"}},{{\"name\":\"tool-linux-amd64.tar.gz\",\"browser_download_url\":\"{base}/download/linux\"}}]}}"
+            );
+            let latest = "/repos/mxcl/tool/releases/latest".to_string();
+            vec![
+                (latest.clone(), 200, body.clone().into_bytes()),
+                (latest, 200, body.into_bytes()),
+            ]
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let dest = tempfile::tempdir().expect("temp dir");
+        let code = run_with_args(vec![
+            "--print-asset-name".to_string(),
+            "--only-os".to_string(),
+            "linux".to_string(),
+            "--arch".to_string(),
+            "x86_64".to_string(),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(
+            std::fs::read_dir(dest.path()).expect("read dest").count(),
+            0
+        );
+
+        let options = yoink::Options {
+            only_os: Some("linux".to_string()),
+            arch: Some("x86_64".to_string()),
+            ..yoink::Options::default()
+        };
+        let info = yoink::release_info_with("mxcl/tool", &options).expect("release info");
+        assert_eq!(info.asset_name, "tool-linux-amd64.tar.gz");
+
+        server.finish();
+    }
+
+    #[test]
+    fn print_path_does_not_download() {
+        let dest = tempfile::tempdir().expect("temp dir");
+        let code = run_with_args(vec![
+            "--print-path".to_string(),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert!(!dest.path().join("tool").exists());
+    }
+
+    #[test]
+    #[serial]
+    fn check_maps_release_status_to_exit_codes() {
+        let server = TestServer::sequence(|_base| {
+            vec![
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    200,
+                    br#"{"tag_name":"v1.0.0","assets":[]}"#.to_vec(),
+                ),
+                (
+                    "/repos/mxcl/draft/releases/latest".to_string(),
+                    404,
+                    b"{}".to_vec(),
+                ),
+                ("/repos/mxcl/draft".to_string(), 200, b"{}".to_vec()),
+                (
+                    "/repos/mxcl/missing/releases/latest".to_string(),
+                    404,
+                    b"{}".to_vec(),
+                ),
+                ("/repos/mxcl/missing".to_string(), 404, b"{}".to_vec()),
+            ]
+        });
+
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let check = |repo: &str| run_with_args(vec!["--check".to_string(), repo.to_string()]);
+        assert_eq!(check("mxcl/tool"), ExitCode::SUCCESS);
+        assert_eq!(check("mxcl/draft"), ExitCode::from(3));
+        assert_eq!(check("mxcl/missing"), ExitCode::from(4));
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn release_json_url_replaces_github_api() {
+        let server = TestServer::new(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v3.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert("/gist/release.json".to_string(), body.into_bytes());
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses
+        });
+
+        let dest = tempfile::tempdir().expect("temp dir");
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", "http://127.0.0.1:9");
+        let code = run_with_args(vec![
+            "--release-json".to_string(),
+            format!("{}/gist/release.json", server.base),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(
+            std::fs::read(dest.path().join("tool")).expect("read tool"),
+            b"bin"
+        );
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn retry_404_waits_for_asset_to_propagate() {
+        let server = TestServer::sequence(|base| {
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            vec![
+                (
+                    "/repos/mxcl/tool/releases/latest".to_string(),
+                    200,
+                    body.into_bytes(),
+                ),
+                ("/download/tool".to_string(), 404, b"{}".to_vec()),
+                ("/download/tool".to_string(), 404, b"{}".to_vec()),
+                ("/download/tool".to_string(), 200, b"bin".to_vec()),
+            ]
+        });
+
+        let dest = tempfile::tempdir().expect("temp dir");
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec![
+            "--retry-404".to_string(),
+            "3".to_string(),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(
+            std::fs::read(dest.path().join("tool")).expect("read tool"),
+            b"bin"
+        );
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn keep_going_continues_past_failures() {
+        let server = TestServer::with_status(|base| {
+            let mut responses = BTreeMap::new();
+            let url = format!("{base}/download/tool");
+            let body = format!(
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+            );
+            responses.insert(
+                "/repos/mxcl/missing/releases/latest".to_string(),
+                (404, b"{}".to_vec()),
+            );
+            responses.insert(
+                "/repos/mxcl/tool/releases/latest".to_string(),
+                (200, body.into_bytes()),
+            );
+            responses.insert("/download/tool".to_string(), (200, b"bin".to_vec()));
+            responses
+        });
+
+        let dest = tempfile::tempdir().expect("temp dir");
+        let list = dest.path().join("repos.txt");
+        std::fs::write(&list, "mxcl/missing\nmxcl/tool\n").expect("write repo file");
+        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let code = run_with_args(vec![
+            "--keep-going".to_string(),
+            "--repo-file".to_string(),
+            list.display().to_string(),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(1));
+        assert!(dest.path().join("tool").exists());
+
+        server.finish();
+    }
+
+    #[test]
+    #[serial]
+    fn repo_file_fails_fast_without_keep_going() {
+        // Even with room for parallel repos, nothing after the failure may start.
+        for jobs in ["1", "4"] {
+            let server = TestServer::with_status(|_base| {
+                let mut responses = BTreeMap::new();
+                responses.insert(
+                    "/repos/mxcl/missing/releases/latest".to_string(),
+                    (404, b"{}".to_vec()),
+                );
+                responses
+            });
+
+            let dest = tempfile::tempdir().expect("temp dir");
+            let list = dest.path().join("repos.txt");
+            std::fs::write(&list, "mxcl/missing\nmxcl/tool\n").expect("write repo file");
+            let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+            let code = run_with_args(vec![
+                format!("--repo-file={}", list.display()),
+                format!("--max-parallel-repos={jobs}"),
+                "-C".to_string(),
+                dest.path().display().to_string(),
+            ]);
+            assert_eq!(code, ExitCode::from(1), "jobs={jobs}");
+            assert!(!dest.path().join("tool").exists(), "jobs={jobs}");
+
+            server.finish();
+        }
+    }
+
+    #[test]
+    fn arch_all_rejects_info_only() {
+        let code = run_with_args(vec![
+            "-I".to_string(),
+            "--arch=all".to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn if_newer_than_requires_info_only() {
+        let code = run_with_args(vec![
+            "--if-newer-than".to_string(),
+            "10m".to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(2));
+
+        let code = run_with_args(vec![
+            "-I".to_string(),
+            "--if-newer-than=soon".to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    fn run_requires_repo_and_known_options() {
+        let code = run_with_args(vec!["run".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+
+        let code = run_with_args(vec!["run".to_string(), "--lockfile".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+
+        let code = run_with_args(vec![
+            "run".to_string(),
+            "--bogus".to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    #[serial]
+    fn run_accepts_checksum_required() {
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", "http://127.0.0.1:9");
+        let code = run_with_args(vec![
+            "run".to_string(),
+            "--checksum-required".to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(1));
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn skip_if_present_skips_install_when_on_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bin_dir = tempfile::tempdir().expect("temp dir");
+        let gh = bin_dir.path().join("gh");
+        std::fs::write(&gh, "#!/bin/sh\necho gh 2.0.0\n").expect("write gh");
+        std::fs::set_permissions(&gh, std::fs::Permissions::from_mode(0o755)).expect("chmod");
+        let _path_guard = EnvGuard::set("PATH", bin_dir.path());
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", "http://127.0.0.1:9");
+        let install_dir = tempfile::tempdir().expect("temp dir");
+        let _dir_guard = EnvGuard::set("YOINKDIR", install_dir.path());
+
+        let code = run_with_args(vec![
+            "-i".to_string(),
+            "--skip-if-present".to_string(),
+            "--min-version".to_string(),
+            "1.5".to_string(),
+            "--bin".to_string(),
+            "gh".to_string(),
+            "cli/cli".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(
+            std::fs::read_dir(install_dir.path())
+                .expect("read dir")
+                .count(),
+            0
+        );
+
+        // Downloading isn't an install, so there is nothing to skip.
+        let dest = tempfile::tempdir().expect("temp dir");
+        let code = run_with_args(vec![
+            "--skip-if-present".to_string(),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+            "cli/cli".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(2));
+        let _action_guard = EnvGuard::set("YOINK_DEFAULT_ACTION", "download");
+        let code = run_with_args(vec!["--skip-if-present".to_string(), "cli/cli".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
+
+    #[test]
+    #[serial]
+    fn skip_if_present_is_rejected_when_running() {
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", "http://127.0.0.1:9");
+        let code = run_with_args(vec![
+            "--skip-if-present".to_string(),
+            "mxcl/tool".to_string(),
+            "--version".to_string(),
+        ]);
         assert_eq!(code, ExitCode::from(2));
     }
 
     #[test]
-    fn inline_c_sets_download_dir() {
-        let code = run_with_args(vec!["-Ctmp".to_string(), "bad".to_string()]);
-        assert_eq!(code, ExitCode::from(2));
+    #[serial]
+    fn timeout_flag_keeps_millisecond_precision() {
+        // Connections queue in the backlog but never get a response.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base = format!("http://{}", listener.local_addr().expect("addr"));
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &base);
+        let dest = tempfile::tempdir().expect("temp dir");
+
+        let started = std::time::Instant::now();
+        let code = run_with_args(vec![
+            "--timeout".to_string(),
+            "500ms".to_string(),
+            "-C".to_string(),
+            dest.path().display().to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::from(1));
+        let elapsed = started.elapsed();
+        assert!(
+            elapsed >= std::time::Duration::from_millis(400),
+            "{elapsed:?}"
+        );
+        assert!(elapsed < std::time::Duration::from_secs(5), "{elapsed:?}");
     }
 
     #[test]
-    fn invalid_repo_shape_errors() {
-        let code = run_with_args(vec!["not-a-repo".to_string()]);
-        assert_eq!(code, ExitCode::from(2));
+    fn parallel_download_needs_at_least_two_parts() {
+        for parts in ["0", "1"] {
+            let code = run_with_args(vec![
+                "--parallel-download".to_string(),
+                parts.to_string(),
+                "mxcl/tool".to_string(),
+            ]);
+            assert_eq!(code, ExitCode::from(2));
+        }
     }
 
     #[test]
-    fn missing_repo_after_flags_errors() {
-        let code = run_with_args(vec!["-j".to_string()]);
+    fn min_version_requires_skip_if_present() {
+        let code = run_with_args(vec![
+            "--min-version=1.0".to_string(),
+            "mxcl/tool".to_string(),
+        ]);
         assert_eq!(code, ExitCode::from(2));
     }
 
     #[test]
-    fn unknown_option_errors() {
-        let code = run_with_args(vec!["-Z".to_string()]);
+    #[serial]
+    fn which_json_lists_every_bin() {
+        let home = tempfile::tempdir().expect("temp dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let bin_dir = home.path().join("bin");
+        let dir = home.path().join("yoink");
+        std::fs::create_dir_all(&dir).expect("mkdir state dir");
+        let state = serde_json::json!({
+            "installs": {
+                "astral-sh/uv": {"version": "0.5.0", "bin": bin_dir.join("uv"), "bins": [bin_dir.join("uvx")]}
+            }
+        });
+        std::fs::write(dir.join("installed.json"), state.to_string()).expect("write state");
+
+        let code = run_with_args(vec![
+            "which".to_string(),
+            "-j".to_string(),
+            "astral-sh/uv".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        let payload = which_json(yoink::installed_bins("astral-sh/uv").expect("installed bins"));
+        let json = serde_json::to_value(&payload).expect("encode json");
+        assert_eq!(json["repo"], "astral-sh/uv");
+        assert_eq!(json["primary"], bin_dir.join("uv").display().to_string());
+        assert_eq!(
+            json["bins"],
+            serde_json::json!([
+                bin_dir.join("uv").display().to_string(),
+                bin_dir.join("uvx").display().to_string()
+            ])
+        );
+
+        let code = run_with_args(vec![
+            "which".to_string(),
+            "-a".to_string(),
+            "astral-sh/uv".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        let code = run_with_args(vec!["which".to_string(), "mxcl/missing".to_string()]);
+        assert_eq!(code, ExitCode::from(1));
+        let code = run_with_args(vec!["which".to_string(), "-x".to_string()]);
         assert_eq!(code, ExitCode::from(2));
     }
 
     #[test]
-    fn combined_flags_unknown_option_errors() {
-        let code = run_with_args(vec!["-jX".to_string(), "bad".to_string()]);
+    fn migrate_dir_requires_one_directory() {
+        let code = run_with_args(vec!["migrate-dir".to_string()]);
         assert_eq!(code, ExitCode::from(2));
     }
 
     #[test]
-    fn combined_flags_reject_extra_args() {
+    fn unknown_progress_mode_errors() {
         let code = run_with_args(vec![
-            "-jI".to_string(),
+            "--progress=fancy".to_string(),
             "mxcl/tool".to_string(),
-            "extra".to_string(),
         ]);
         assert_eq!(code, ExitCode::from(2));
     }
 
     #[test]
-    fn combined_flags_missing_dir_errors() {
-        let code = run_with_args(vec!["-jC".to_string(), "mxcl/tool".to_string()]);
+    fn missing_arch_value_errors() {
+        let code = run_with_args(vec!["--arch".to_string()]);
         assert_eq!(code, ExitCode::from(2));
     }
 
     #[test]
-    fn combined_flags_inline_dir_sets_download_dir() {
-        let code = run_with_args(vec!["-jCtmp".to_string(), "bad".to_string()]);
-        assert_eq!(code, ExitCode::from(2));
+    fn json_schema_describes_download_output() {
+        let code = run_with_args(vec!["--json-schema".to_string()]);
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        let text = serde_json::to_string(&json_schemas()).expect("serialize schema");
+        let schemas: serde_json::Value = serde_json::from_str(&text).expect("parse schema");
+        assert!(schemas["download"]["properties"]["executables"].is_object());
+        assert!(schemas["info"]["properties"]["tag"].is_object());
+        assert!(schemas["install"]["properties"]["used_sudo"].is_object());
     }
 
     #[test]
-    fn dash_dash_stops_option_parsing() {
-        let code = run_with_args(vec!["--".to_string(), "bad".to_string()]);
+    fn export_rejects_arguments() {
+        let code = run_with_args(vec!["export".to_string(), "extra".to_string()]);
         assert_eq!(code, ExitCode::from(2));
     }
 
     #[test]
     #[serial]
-    fn info_only_uses_release_info() {
-        let server = TestServer::new(|base| {
-            let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool");
-            let body = format!(
-                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
-            );
-            responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                body.into_bytes(),
-            );
-            responses
-        });
+    fn export_prints_manifest() {
+        let home = tempfile::tempdir().expect("temp dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        write_state(home.path(), "mxcl/tool", "v1.0.0");
 
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let code = run_with_args(vec!["-I".to_string(), "mxcl/tool".to_string()]);
+        let code = run_with_args(vec!["export".to_string()]);
         assert_eq!(code, ExitCode::SUCCESS);
-
-        server.finish();
     }
 
     #[test]
-    #[serial]
-    fn info_only_reports_errors() {
-        let server = TestServer::new(|_base| {
-            let mut responses = BTreeMap::new();
-            responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                b"not-json".to_vec(),
-            );
-            responses
-        });
-
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let code = run_with_args(vec!["-I".to_string(), "mxcl/tool".to_string()]);
-        assert_eq!(code, ExitCode::from(1));
+    fn rollback_expects_one_repo() {
+        let code = run_with_args(vec!["rollback".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
 
-        server.finish();
+    #[test]
+    fn doctor_rejects_unknown_arguments() {
+        let code = run_with_args(vec!["doctor".to_string(), "--all".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
     }
 
     #[test]
-    #[serial]
-    fn json_download_writes_to_directory() {
-        let server = TestServer::new(|base| {
-            let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool");
-            let body = format!(
-                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
-            );
-            responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                body.into_bytes(),
-            );
-            responses.insert("/download/tool".to_string(), b"bin".to_vec());
-            responses
-        });
+    fn uninstall_expects_one_target() {
+        let code = run_with_args(vec!["uninstall".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
+    }
 
-        let dest = tempfile::tempdir().expect("temp dir");
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+    #[test]
+    fn ensure_expects_one_repo() {
+        let code = run_with_args(vec!["ensure".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
         let code = run_with_args(vec![
-            "-j".to_string(),
-            "-C".to_string(),
-            dest.path().display().to_string(),
-            "mxcl/tool".to_string(),
+            "ensure".to_string(),
+            "mxcl/a@v1".to_string(),
+            "mxcl/b@v1".to_string(),
         ]);
-        assert_eq!(code, ExitCode::SUCCESS);
-        assert!(dest.path().join("tool").exists());
-
-        server.finish();
+        assert_eq!(code, ExitCode::from(2));
     }
 
     #[test]
     #[serial]
-    fn json_output_skips_duplicate_executables() {
-        let server = TestServer::new(|base| {
-            let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool.zip");
-            let body = format!(
-                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool.zip\",\"browser_download_url\":\"{url}\"}}]}}"
-            );
-            let zip = make_zip_bytes(&[("bin/tool", b"bin"), ("alt/tool", b"bin")]);
-            responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                body.into_bytes(),
-            );
-            responses.insert("/download/tool.zip".to_string(), zip);
-            responses
-        });
+    fn list_accepts_sort_and_rejects_unknown_keys() {
+        let home = tempfile::tempdir().expect("temp dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        write_state(home.path(), "mxcl/tool", "v1.0.0");
 
-        let dest = tempfile::tempdir().expect("temp dir");
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
         let code = run_with_args(vec![
-            "-j".to_string(),
-            "-C".to_string(),
-            dest.path().display().to_string(),
-            "mxcl/tool".to_string(),
+            "list".to_string(),
+            "--sort".to_string(),
+            "installed-at".to_string(),
         ]);
         assert_eq!(code, ExitCode::SUCCESS);
 
-        server.finish();
-    }
-
-    #[test]
-    #[serial]
-    fn download_relative_paths_use_cwd() {
-        let server = TestServer::new(|base| {
-            let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool");
-            let body = format!(
-                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
-            );
-            responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                body.into_bytes(),
-            );
-            responses.insert("/download/tool".to_string(), b"bin".to_vec());
-            responses
-        });
-
-        let cwd = tempfile::tempdir().expect("temp dir");
-        let _cwd_guard = DirGuard::set(cwd.path());
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let code = run_with_args(vec!["mxcl/tool".to_string()]);
+        let code = run_with_args(vec!["list".to_string(), "-j".to_string()]);
         assert_eq!(code, ExitCode::SUCCESS);
-        assert!(cwd.path().join("tool").exists());
 
-        server.finish();
+        let code = run_with_args(vec!["list".to_string(), "--sort=size".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
     }
 
     #[test]
     #[serial]
-    fn relative_c_dir_joins_cwd() {
+    #[cfg(unix)]
+    fn self_test_downloads_and_runs_repo() {
         let server = TestServer::new(|base| {
             let mut responses = BTreeMap::new();
-            let url = format!("{base}/download/tool");
+            let url = format!("{base}/download/canary");
             let body = format!(
-                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
+                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"canary\",\"browser_download_url\":\"{url}\"}}]}}"
             );
             responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
+                "/repos/mxcl/canary/releases/latest".to_string(),
                 body.into_bytes(),
             );
-            responses.insert("/download/tool".to_string(), b"bin".to_vec());
+            responses.insert(
+                "/download/canary".to_string(),
+                b"#!/bin/sh\nexit 0\n".to_vec(),
+            );
             responses
         });
 
-        let cwd = tempfile::tempdir().expect("temp dir");
-        let _cwd_guard = DirGuard::set(cwd.path());
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let code = run_with_args(vec![
-            "-C".to_string(),
-            "bin".to_string(),
-            "mxcl/tool".to_string(),
-        ]);
+        let _api_guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
+        let _repo_guard = EnvGuard::set("YOINK_SELFTEST_REPO", "mxcl/canary");
+        let code = run_with_args(vec!["--self-test".to_string()]);
         assert_eq!(code, ExitCode::SUCCESS);
-        assert!(cwd.path().join("bin").join("tool").exists());
 
         server.finish();
     }
 
     #[test]
     #[serial]
-    #[cfg(unix)]
-    fn run_branch_executes_binary() {
+    fn isolated_flag_is_accepted() {
         let server = TestServer::new(|base| {
             let mut responses = BTreeMap::new();
             let url = format!("{base}/download/tool");
             let body = format!(
-                "{{\"tag_name\":\"v1.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
-            );
-            let script = b"#!/bin/sh\nexit 3\n";
-            responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                body.into_bytes(),
+                "{{\"tag_name\":\"v3.0.0\",\"assets\":[{{\"name\":\"tool\",\"browser_download_url\":\"{url}\"}}]}}"
             );
-            responses.insert("/download/tool".to_string(), script.to_vec());
+            responses.insert("/gist/release.json".to_string(), body.into_bytes());
+            responses.insert("/download/tool".to_string(), b"bin".to_vec());
             responses
         });
 
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let code = run_with_args(vec!["mxcl/tool".to_string(), "arg".to_string()]);
-        assert_eq!(code, ExitCode::from(3));
+        let temp = tempfile::tempdir().expect("temp dir");
+        let config = temp.path().join("config.toml");
+        std::fs::write(&config, "not [valid toml").expect("write config");
+        let _config_guard = EnvGuard::set("YOINK_CONFIG", &config);
+        let _owner_guard = EnvGuard::set("YOINK_DEFAULT_OWNER", "mxcl");
+        let args = |isolated: bool| {
+            let mut args = vec![
+                "--release-json".to_string(),
+                format!("{}/gist/release.json", server.base),
+                "-C".to_string(),
+                temp.path().display().to_string(),
+                "mxcl/tool".to_string(),
+            ];
+            if isolated {
+                args.insert(0, "--isolated".to_string());
+            }
+            args
+        };
+
+        // Without the flag the broken config fails the run before anything is fetched.
+        assert_eq!(run_with_args(args(false)), ExitCode::from(1));
+        assert_eq!(run_with_args(args(true)), ExitCode::SUCCESS);
+        assert_eq!(
+            std::fs::read(temp.path().join("tool")).expect("read tool"),
+            b"bin"
+        );
+        // A bare name only resolves through `YOINK_DEFAULT_OWNER`.
+        let code = run_with_args(vec!["--isolated".to_string(), "tool".to_string()]);
+        assert_eq!(code, ExitCode::from(2));
 
         server.finish();
     }
 
     #[test]
     #[serial]
-    fn run_branch_reports_errors() {
+    fn notes_only_prints_newer_release_notes() {
         let server = TestServer::new(|_base| {
             let mut responses = BTreeMap::new();
+            let body = r#"[{"tag_name":"v1.1.0","body":"new","assets":[]},{"tag_name":"v1.0.0","body":"old","assets":[]}]"#;
             responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                b"not-json".to_vec(),
+                "/repos/mxcl/tool/releases?per_page=100".to_string(),
+                body.as_bytes().to_vec(),
             );
             responses
         });
 
+        let home = tempfile::tempdir().expect("temp dir");
+        let _home_guard = EnvGuard::set("HOME", home.path());
+        let _xdg_guard = EnvGuard::set("XDG_DATA_HOME", home.path());
+        let _dir_guard = EnvGuard::set("YOINKDIR", home.path().join("bin"));
         let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let code = run_with_args(vec!["mxcl/tool".to_string(), "arg".to_string()]);
-        assert_eq!(code, ExitCode::from(1));
+        write_state(home.path(), "mxcl/tool", "v1.0.0");
+
+        let code = run_with_args(vec![
+            "--notes-only".to_string(),
+            "-j".to_string(),
+            "mxcl/tool".to_string(),
+        ]);
+        assert_eq!(code, ExitCode::SUCCESS);
 
         server.finish();
     }
 
-    #[test]
-    #[serial]
-    fn download_to_dir_reports_errors() {
-        let server = TestServer::new(|_base| {
-            let mut responses = BTreeMap::new();
-            responses.insert(
-                "/repos/mxcl/tool/releases/latest".to_string(),
-                b"not-json".to_vec(),
-            );
-            responses
+    fn write_state(data_home: &Path, repo: &str, version: &str) {
+        let dir = data_home.join("yoink");
+        std::fs::create_dir_all(&dir).expect("mkdir state dir");
+        let state = serde_json::json!({
+            "installs": {
+                repo: {"version": version, "bin": data_home.join("bin").join("tool")}
+            }
         });
-
-        let _guard = EnvGuard::set("YOINK_GITHUB_API_BASE", &server.base);
-        let code = run_with_args(vec!["mxcl/tool".to_string()]);
-        assert_eq!(code, ExitCode::from(1));
-
-        server.finish();
+        std::fs::write(dir.join("installed.json"), state.to_string()).expect("write state");
     }
 
     struct EnvGuard {
@@ -619,6 +3147,18 @@ mod tests {
         fn new<F>(make_responses: F) -> Self
         where
             F: FnOnce(&str) -> BTreeMap<String, Vec<u8>>,
+        {
+            Self::with_status(|base| {
+                make_responses(base)
+                    .into_iter()
+                    .map(|(path, body)| (path, (200, body)))
+                    .collect()
+            })
+        }
+
+        fn with_status<F>(make_responses: F) -> Self
+        where
+            F: FnOnce(&str) -> BTreeMap<String, (u16, Vec<u8>)>,
         {
             let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
             let addr = listener.local_addr().expect("addr");
@@ -637,6 +3177,27 @@ mod tests {
             }
         }
 
+        /// Serves `(path, status, body)` responses strictly in order, one per connection.
+        fn sequence<F>(make_responses: F) -> Self
+        where
+            F: FnOnce(&str) -> Vec<(String, u16, Vec<u8>)>,
+        {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+            let addr = listener.local_addr().expect("addr");
+            let base = format!("http://{addr}");
+            let responses = make_responses(&base);
+            let handle = thread::spawn(move || {
+                for (path, status, body) in responses {
+                    let (mut stream, _) = listener.accept().expect("accept");
+                    respond(&mut stream, &BTreeMap::from([(path, (status, body))]));
+                }
+            });
+            Self {
+                base,
+                handle: Some(handle),
+            }
+        }
+
         fn finish(mut self) {
             if let Some(handle) = self.handle.take() {
                 handle.join().expect("server thread");
@@ -644,7 +3205,7 @@ mod tests {
         }
     }
 
-    fn respond(stream: &mut std::net::TcpStream, responses: &BTreeMap<String, Vec<u8>>) {
+    fn respond(stream: &mut std::net::TcpStream, responses: &BTreeMap<String, (u16, Vec<u8>)>) {
         let mut reader = BufReader::new(stream);
         let mut request_line = String::new();
         reader
@@ -658,11 +3219,11 @@ mod tests {
                 break;
             }
         }
-        let body = responses
+        let (status, body) = responses
             .get(path)
             .unwrap_or_else(|| panic!("unexpected path {path}"));
         let header = format!(
-            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 {status} OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
             body.len()
         );
         reader
@@ -0,0 +1,282 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+
+/// Runs yoink in `cwd` with its state under `home`, feeding `stdin` to it.
+fn yoink(args: &[&str], home: &Path, cwd: &Path, stdin: &str) -> Output {
+    yoink_with_env(args, home, cwd, stdin, &[])
+}
+
+fn yoink_with_env(
+    args: &[&str],
+    home: &Path,
+    cwd: &Path,
+    stdin: &str,
+    envs: &[(&str, &Path)],
+) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_yoink"))
+        .args(args)
+        .current_dir(cwd)
+        .env("HOME", home)
+        .env("XDG_DATA_HOME", home)
+        .env("YOINK_GITHUB_API_BASE", "http://127.0.0.1:1")
+        .envs(envs.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run yoink");
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(stdin.as_bytes())
+        .expect("write stdin");
+    child.wait_with_output().expect("wait for yoink")
+}
+
+/// Serves `body` for a single request and returns the server's base URL.
+fn serve_once(body: &'static [u8]) -> (String, thread::JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("addr"));
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("accept");
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            let read = stream.read(&mut buf).expect("read request");
+            assert!(read > 0, "request ended early");
+            request.extend_from_slice(&buf[..read]);
+        }
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )
+        .expect("write headers");
+        stream.write_all(body).expect("write body");
+    });
+    (base, handle)
+}
+
+#[test]
+fn quiet_errors_prints_one_line() {
+    let temp = tempfile::tempdir().expect("create temp dir");
+    let output = Command::new(env!("CARGO_BIN_EXE_yoink"))
+        .args(["--quiet-errors", "--retries=0", "mxcl/tool"])
+        .env("HOME", temp.path())
+        .env("XDG_DATA_HOME", temp.path())
+        .env("YOINKDIR", temp.path())
+        .env("YOINK_GITHUB_API_BASE", "http://127.0.0.1:1")
+        .env("RUST_BACKTRACE", "1")
+        .output()
+        .expect("run yoink");
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.lines().count(), 1, "{stderr}");
+    assert!(
+        stderr.starts_with("yoink: fetch latest release for mxcl/tool: "),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("Caused by"), "{stderr}");
+}
+
+#[test]
+fn quiet_errors_is_only_a_leading_flag() {
+    let temp = tempfile::tempdir().expect("create temp dir");
+    let yoink = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_yoink"))
+            .args(args)
+            .env("HOME", temp.path())
+            .env("XDG_DATA_HOME", temp.path())
+            .env("YOINKDIR", temp.path())
+            .env("YOINK_GITHUB_API_BASE", "http://127.0.0.1:1")
+            .output()
+            .expect("run yoink")
+    };
+
+    let output = yoink(&["--retries=0", "--quiet-errors", "mxcl/tool"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.lines().count(), 1, "{stderr}");
+
+    // After the repo it belongs to the binary's own arguments.
+    let output = yoink(&["--retries=0", "mxcl/tool", "--quiet-errors"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Caused by"), "{stderr}");
+}
+
+#[test]
+fn print_path_prints_the_install_destination() {
+    let temp = tempfile::tempdir().expect("create temp dir");
+    let bin_dir = temp.path().join("bin");
+    let print_path = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_yoink"))
+            .arg("--print-path")
+            .args(args)
+            .env("HOME", temp.path())
+            .env("YOINKDIR", &bin_dir)
+            .env("YOINK_GITHUB_API_BASE", "http://127.0.0.1:1")
+            .output()
+            .expect("run yoink");
+        assert_eq!(output.status.code(), Some(0), "{output:?}");
+        String::from_utf8(output.stdout).expect("utf-8 stdout")
+    };
+    let exe = |name: &str| {
+        let path = bin_dir.join(format!("{name}{}", std::env::consts::EXE_SUFFIX));
+        format!("{}\n", path.display())
+    };
+
+    assert_eq!(print_path(&["mxcl/tool"]), exe("tool"));
+    assert_eq!(print_path(&["--bin", "helper", "mxcl/tool"]), exe("helper"));
+}
+
+#[test]
+fn dry_run_reports_the_install_path_under_an_install_default() {
+    let temp = tempfile::tempdir().expect("create temp dir");
+    let bin_dir = temp.path().join("bin");
+    let release = r#"{"tag_name":"v1.0.0","assets":[
+        {"name":"tool","browser_download_url":"http://127.0.0.1:1/tool"}]}"#;
+    let envs = [
+        ("YOINK_DEFAULT_ACTION", Path::new("install")),
+        ("YOINKDIR", bin_dir.as_path()),
+    ];
+    let args = ["-n", "--release-json", "-", "mxcl/tool"];
+    let output = yoink_with_env(&args, temp.path(), temp.path(), release, &envs);
+
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+    let exe = bin_dir.join(format!("tool{}", std::env::consts::EXE_SUFFIX));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("tag: v1.0.0\nasset: tool\ndestination: {}\n", exe.display())
+    );
+    assert!(!bin_dir.exists());
+}
+
+#[test]
+fn plain_paths_prints_absolute_paths_in_cwd() {
+    let temp = tempfile::tempdir().expect("create temp dir");
+    let cwd = tempfile::tempdir().expect("create cwd");
+    let tool = cwd.path().join("tool");
+    let download = |extra: &[&str]| {
+        let (base, server) = serve_once(b"bin");
+        let release = format!(
+            r#"{{"tag_name":"v1.0.0","assets":[{{"name":"tool","browser_download_url":"{base}/tool"}}]}}"#
+        );
+        let mut args = extra.to_vec();
+        args.extend(["--release-json", "-", "mxcl/tool"]);
+        let output = yoink(&args, temp.path(), cwd.path(), &release);
+        server.join().expect("server");
+        assert_eq!(output.status.code(), Some(0), "{output:?}");
+        std::fs::remove_file(&tool).expect("remove download");
+        String::from_utf8(output.stdout).expect("utf-8 stdout")
+    };
+
+    let relative = Path::new(".").join("tool");
+    assert_eq!(download(&[]), format!("{}\n", relative.display()));
+    assert_eq!(
+        download(&["--plain-paths"]),
+        format!("{}\n", tool.display())
+    );
+}
+
+#[test]
+fn dry_run_prints_the_plan_without_downloading() {
+    let temp = tempfile::tempdir().expect("create temp dir");
+    let dest = temp.path().join("out");
+    let release = r#"{"tag_name":"v1.0.0","assets":[
+        {"name":"tool","browser_download_url":"http://127.0.0.1:1/tool"}]}"#;
+    let dest_arg = dest.display().to_string();
+    let args = ["-n", "-C", &dest_arg, "--release-json", "-", "mxcl/tool"];
+    let output = yoink(&args, temp.path(), temp.path(), release);
+
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "tag: v1.0.0\nasset: tool\ndestination: {}\n",
+            dest.join("tool").display()
+        )
+    );
+    assert!(!dest.join("tool").exists());
+}
+
+#[test]
+fn print_asset_name_prints_only_the_resolved_asset() {
+    let temp = tempfile::tempdir().expect("create temp dir");
+    let release = r#"{"tag_name":"v1.0.0","assets":[
+        {"name":"tool-darwin-amd64.tar.gz","browser_download_url":"http://127.0.0.1:1/darwin"},
+        {"name":"tool-linux-amd64.tar.gz","browser_download_url":"http://127.0.0.1:1/linux"}]}"#;
+    let args = [
+        "--print-asset-name",
+        "--only-os",
+        "linux",
+        "--arch",
+        "x86_64",
+        "--release-json",
+        "-",
+        "mxcl/tool",
+    ];
+    let output = yoink(&args, temp.path(), temp.path(), release);
+
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "tool-linux-amd64.tar.gz\n"
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[test]
+fn which_prints_the_primary_unless_all() {
+    let temp = tempfile::tempdir().expect("create temp dir");
+    let bin_dir = temp.path().join("bin");
+    let state_dir = temp.path().join("yoink");
+    std::fs::create_dir_all(&state_dir).expect("create state dir");
+    let uv = bin_dir.join("uv");
+    let uvx = bin_dir.join("uvx");
+    let state = format!(
+        r#"{{"installs":{{"astral-sh/uv":{{"version":"0.5.0","bin":{:?},"bins":[{:?}]}}}}}}"#,
+        uv.display().to_string(),
+        uvx.display().to_string()
+    );
+    std::fs::write(state_dir.join("installed.json"), state).expect("write state");
+    let which = |args: &[&str]| {
+        let mut full = vec!["which"];
+        full.extend(args);
+        yoink(&full, temp.path(), temp.path(), "")
+    };
+
+    let output = which(&["astral-sh/uv"]);
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{}\n", uv.display())
+    );
+
+    let output = which(&["-a", "astral-sh/uv"]);
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{}\n{}\n", uv.display(), uvx.display())
+    );
+
+    let output = which(&["mxcl/missing"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("mxcl/missing"),
+        "{output:?}"
+    );
+
+    let output = which(&["-x"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "yoink: which expects [-j] [-a] <owner/repo>\n"
+    );
+}